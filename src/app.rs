@@ -1,6 +1,10 @@
 use crate::{
-    components::page::Page,
+    components::{
+        common::models::permission_model::{get_permissions, Permission},
+        page::Page,
+    },
     error_template::{AppError, ErrorTemplate},
+    route_guard::{SessionGuard, SessionKeepAlive},
 };
 use leptos::*;
 use leptos_meta::*;
@@ -15,6 +19,9 @@ pub struct HasError(pub RwSignal<bool>);
 #[derive(Copy, Clone)]
 pub struct CheckCookie(pub Resource<bool, Result<bool, ServerFnError>>);
 
+#[derive(Copy, Clone)]
+pub struct UserPermissions(pub Resource<bool, Result<Vec<Permission>, ServerFnError>>);
+
 #[allow(non_snake_case)]
 #[component]
 pub fn App() -> impl IntoView {
@@ -34,13 +41,16 @@ pub fn App() -> impl IntoView {
 
     let auth_resource: Resource<bool, Result<bool, ServerFnError>> =
         create_local_resource(refetcher, move |_| async move {
-            crate::components::wrapper::check_server_cookie("jabra-admin-portal-v2".to_string())
-                .await
+            crate::components::common::functions::wrapper::check_server_cookie().await
         });
 
+    let permissions_resource: Resource<bool, Result<Vec<Permission>, ServerFnError>> =
+        create_local_resource(refetcher, move |_| async move { get_permissions().await });
+
     provide_context(Refetcher(refetcher));
     provide_context(HasError(has_error));
     provide_context(CheckCookie(auth_resource));
+    provide_context(UserPermissions(permissions_resource));
 
     view! {
 
@@ -63,16 +73,26 @@ pub fn App() -> impl IntoView {
         }>
             <main class="font-poppins" data-theme = theme>
                 <div class="min-h-screen">
+                    <SessionKeepAlive />
+                    <SessionGuard>
                     <Routes>
                         <Route path="/" view=Page/>
                         <Route path="/login" view=Page/>
+                        <Route path="/forgot_password" view=Page/>
+                        <Route path="/reset_password" view=Page/>
+                        <Route path="/settings" view=Page/>
                         <Route path="/quote_builder" view=Page/>
                         <Route path="/active_quotes" view=Page/>
                         <Route path="/positions" view=Page/>
                         <Route path="/trade_history" view=Page/>
                         <Route path="/components" view=Page/>
+                        <Route path="/audit_log" view=Page/>
+                        <Route path="/reference_data" view=Page/>
+                        <Route path="/counterparties" view=Page/>
+                        <Route path="/supported_pairs" view=Page/>
                         <Route path="/perp-aggregator" view=Page/>
                     </Routes>
+                    </SessionGuard>
                 </div>
             </main>
         </Router>
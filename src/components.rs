@@ -1,11 +1,16 @@
+pub mod audit_log;
 pub mod common;
 pub mod component_testing;
 pub mod content;
+pub mod counterparties;
 pub mod dark_mode_toggle;
 pub mod login;
 pub mod models;
 pub mod page;
+pub mod password_reset;
 pub mod quote_builder;
+pub mod reference_data;
+pub mod settings;
 pub mod sidebar;
-pub mod wrapper;
+pub mod supported_pairs;
 pub mod perp_aggregator;
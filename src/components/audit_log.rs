@@ -0,0 +1,122 @@
+use leptos::*;
+
+use crate::{
+    components::common::models::audit_model::{
+        get_audit_log, AuditAction, AuditEntry, AuditFilter,
+    },
+    errors::JabraError,
+};
+
+/// Number of entries the viewer loads at once.
+const AUDIT_PAGE_SIZE: usize = 200;
+
+const INPUT_CLASS: &str =
+    "input input-sm text-xs input-bordered w-full max-w-xs rounded hover:shadow-md";
+
+fn json_or_dash(value: &Option<serde_json::Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn AuditLog() -> impl IntoView {
+    let actor = RwSignal::new(String::new());
+    let action = RwSignal::new(String::new());
+    let group_id = RwSignal::new(String::new());
+    let filter = RwSignal::new(AuditFilter::default());
+    let audit_resource = create_resource(filter, move |f| get_audit_log(f, AUDIT_PAGE_SIZE));
+
+    let apply = move |_| {
+        filter.set(AuditFilter {
+            actor: actor.get_untracked(),
+            action: action.get_untracked(),
+            group_id: group_id.get_untracked(),
+        })
+    };
+
+    view! {
+        <div class="p-6">
+            <span class="text-lg text-success font-normal pl-1">Audit Log</span>
+            <div class="flex flex-row gap-4 items-end rounded-lg bg-base-300 mt-2 p-4 shadow-lg">
+                <div>
+                    <label class="label"><span class="text-xs text-success">Actor</span></label>
+                    <input class=INPUT_CLASS type="text" placeholder="user id"
+                        prop:value=actor on:input=move |ev| actor.set(event_target_value(&ev))/>
+                </div>
+                <div>
+                    <label class="label"><span class="text-xs text-success">Action</span></label>
+                    <select class="select select-sm text-xs select-bordered w-full max-w-xs rounded"
+                        on:change=move |ev| action.set(event_target_value(&ev))>
+                        <option value="">All</option>
+                        {AuditAction::all().into_iter().map(|a| view! {
+                            <option value=a.as_str()>{a.as_str()}</option>
+                        }).collect_view()}
+                    </select>
+                </div>
+                <div>
+                    <label class="label"><span class="text-xs text-success">Group Id</span></label>
+                    <input class=INPUT_CLASS type="text"
+                        prop:value=group_id on:input=move |ev| group_id.set(event_target_value(&ev))/>
+                </div>
+                <button class="btn btn-sm btn-success" on:click=apply>SEARCH</button>
+            </div>
+            <div class="rounded-md bg-base-300 mt-4 overflow-x-auto">
+                <Transition fallback = move || view! {<span class="loading loading-bars loading-sm text-success"></span>}>
+                    {
+                        move || audit_resource.get().map(|res| match res {
+                            Ok(entries) => view! { <AuditTable entries = entries/> }.into_view(),
+                            Err(e) => view! {
+                                <p class="p-4 text-sm text-error">{JabraError::from_server_fn_error(&e).to_string()}</p>
+                            }.into_view(),
+                        })
+                    }
+                </Transition>
+            </div>
+        </div>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn AuditTable(entries: Vec<AuditEntry>) -> impl IntoView {
+    view! {
+        <table class = "table table-zebra table-xs">
+            <thead class = "text-success font-extralight bg-base-300">
+                <tr class="border-y border-y-base-100">
+                    <th>TIME</th>
+                    <th>ACTOR</th>
+                    <th>ACTION</th>
+                    <th>GROUP ID</th>
+                    <th>ITEM</th>
+                    <th>BEFORE</th>
+                    <th>AFTER</th>
+                    <th>IP</th>
+                    <th>RESULT</th>
+                </tr>
+            </thead>
+            <tbody>
+                {entries.into_iter().map(|entry| view! {
+                    <tr class="hover">
+                        <td>{entry.timestamp.clone()}</td>
+                        <td>{entry.actor.clone()}</td>
+                        <td>{entry.action.as_str()}</td>
+                        <td>{entry.group_id.clone().unwrap_or("-".to_string())}</td>
+                        <td>{entry.item_id.clone().unwrap_or("-".to_string())}</td>
+                        <td class="font-mono break-all">{json_or_dash(&entry.before)}</td>
+                        <td class="font-mono break-all">{json_or_dash(&entry.after)}</td>
+                        <td>{entry.client_ip.clone()}</td>
+                        <td>
+                            {match entry.error.clone() {
+                                Some(e) => view! {<span class="text-error">{e}</span>}.into_view(),
+                                None => view! {<span class="text-success">"ok"</span>}.into_view(),
+                            }}
+                        </td>
+                    </tr>
+                }).collect_view()}
+            </tbody>
+        </table>
+    }
+}
@@ -4,3 +4,4 @@ pub mod generated_quotes;
 pub mod input;
 pub mod modals;
 pub mod range_selector;
+pub mod spot_feed;
@@ -0,0 +1,22 @@
+use leptos::*;
+use leptos_use::{use_event_source, utils::JsonCodec, UseEventSourceReturn};
+
+use crate::{
+    components::common::models::spot_price_model::SpotPrice, spot_stream::spot_stream_url,
+};
+
+/// Listens to the live spot stream of `pair` (`BASE-QUOTE`) and hands every price to `on_price`.
+/// Renders nothing. The stream is closed when the component is dropped, e.g. on a pair change.
+
+#[allow(non_snake_case)]
+#[component]
+pub fn SpotFeed(pair: String, on_price: Callback<SpotPrice>) -> impl IntoView {
+    let UseEventSourceReturn { data, .. } =
+        use_event_source::<SpotPrice, JsonCodec>(&spot_stream_url(&pair));
+
+    create_effect(move |_| {
+        if let Some(price) = data.get() {
+            on_price.call(price);
+        }
+    });
+}
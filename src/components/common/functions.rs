@@ -1,4 +1,15 @@
+#[cfg(feature = "ssr")]
+pub mod audit;
+#[cfg(feature = "ssr")]
+pub mod cache;
 pub mod helpers;
 pub mod precision;
+pub mod rate_limit;
+#[cfg(feature = "ssr")]
+pub mod seal;
+pub mod security;
+#[cfg(feature = "ssr")]
+pub mod spot_price;
+pub mod upstream;
 pub mod utils;
 pub mod wrapper;
@@ -0,0 +1,273 @@
+use super::upstream::Upstream;
+use super::wrapper::{call, call_and_parse, HttpMethod};
+use crate::components::common::models::audit_model::{AuditEntry, AuditFilter, AuditLogResponse};
+use crate::errors::JabraError;
+
+/// Where audit entries are kept.
+/// `AUDIT_SINK=directus` writes to the Directus `audit_log` collection with the service token in
+/// `DIRECTUS_AUDIT_TOKEN`, so traders cannot write or alter entries with their own token.
+/// Otherwise entries are appended as JSON lines to `AUDIT_LOG_FILE` (`audit.log` by default).
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuditSink {
+    Directus { token: String },
+    File { path: String },
+}
+
+impl AuditSink {
+    pub fn from_env() -> Self {
+        match (
+            std::env::var("AUDIT_SINK").as_deref(),
+            std::env::var("DIRECTUS_AUDIT_TOKEN"),
+        ) {
+            (Ok("directus"), Ok(token)) => AuditSink::Directus { token },
+            (Ok("directus"), Err(_)) => {
+                log::error!(
+                    "AUDIT_SINK=directus needs DIRECTUS_AUDIT_TOKEN, writing to the audit file"
+                );
+                AuditSink::file_from_env()
+            }
+            _ => AuditSink::file_from_env(),
+        }
+    }
+
+    fn file_from_env() -> Self {
+        AuditSink::File {
+            path: std::env::var("AUDIT_LOG_FILE").unwrap_or_else(|_| "audit.log".to_string()),
+        }
+    }
+}
+
+fn directus_headers(token: &str) -> reqwest::header::HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        "Authorization",
+        reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+    );
+    headers
+}
+
+/// Appends `entries` as JSON lines. The file is only ever opened in append mode.
+pub async fn append_to_file(path: &str, entries: &[AuditEntry]) -> Result<(), JabraError> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut lines = String::new();
+    for entry in entries {
+        lines.push_str(
+            &serde_json::to_string(entry)
+                .map_err(|e| JabraError::SerializationError(e.to_string()))?,
+        );
+        lines.push('\n');
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| file_error(path, e))?;
+    // A tokio file finishes writes in the background, flushing waits for them
+    file.write_all(lines.as_bytes())
+        .await
+        .map_err(|e| file_error(path, e))?;
+    file.flush().await.map_err(|e| file_error(path, e))
+}
+
+/// Reads the newest `limit` entries matching `filter` from an audit file.
+/// The file is streamed line by line and only the last `limit` matches are kept, so a long
+/// audit history is never loaded into memory at once.
+pub async fn read_from_file(
+    path: &str,
+    filter: &AuditFilter,
+    limit: usize,
+) -> Result<Vec<AuditEntry>, JabraError> {
+    use tokio::io::AsyncBufReadExt;
+
+    let file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(file_error(path, e)),
+    };
+    let mut lines = tokio::io::BufReader::new(file).lines();
+    let mut newest = std::collections::VecDeque::new();
+    while let Some(line) = lines.next_line().await.map_err(|e| file_error(path, e))? {
+        match serde_json::from_str::<AuditEntry>(&line) {
+            Ok(entry) if filter.matches(&entry) => {
+                newest.push_back(entry);
+                if newest.len() > limit {
+                    newest.pop_front();
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(newest.into_iter().rev().collect())
+}
+
+fn file_error(path: &str, e: std::io::Error) -> JabraError {
+    JabraError::SerializationError(format!("audit log {}: {}", path, e))
+}
+
+/// Records `entries`. A failure is logged with the entries themselves so nothing is lost
+/// silently, but never fails the change that is being audited.
+pub async fn record(entries: Vec<AuditEntry>) {
+    if entries.is_empty() {
+        return;
+    }
+    let result = match AuditSink::from_env() {
+        AuditSink::File { path } => append_to_file(&path, &entries).await,
+        AuditSink::Directus { token } => {
+            let url = std::env::var("DIRECTUSURL").unwrap();
+            call::<Vec<AuditEntry>>(
+                Some(entries.clone()),
+                format!("{}/items/audit_log", url),
+                directus_headers(&token),
+                HttpMethod::POST,
+                Upstream::Directus,
+            )
+            .await
+            .map(|_| ())
+        }
+    };
+    if let Err(e) = result {
+        log::error!(
+            "Audit Error: {}: {}",
+            e,
+            serde_json::to_string(&entries).unwrap_or_default()
+        );
+    }
+}
+
+pub async fn read(filter: &AuditFilter, limit: usize) -> Result<Vec<AuditEntry>, JabraError> {
+    match AuditSink::from_env() {
+        AuditSink::File { path } => read_from_file(&path, filter, limit).await,
+        AuditSink::Directus { token } => {
+            let url = std::env::var("DIRECTUSURL").unwrap();
+            let mut query = vec![
+                ("sort".to_string(), "-timestamp".to_string()),
+                ("limit".to_string(), limit.to_string()),
+            ];
+            if !filter.actor.is_empty() {
+                query.push((
+                    "filter[actor][_icontains]".to_string(),
+                    filter.actor.trim().to_string(),
+                ));
+            }
+            if !filter.action.is_empty() {
+                query.push(("filter[action][_eq]".to_string(), filter.action.clone()));
+            }
+            if !filter.group_id.is_empty() {
+                query.push((
+                    "filter[group_id][_eq]".to_string(),
+                    filter.group_id.trim().to_string(),
+                ));
+            }
+            let query = serde_urlencoded::to_string(query).unwrap_or_default();
+            call_and_parse::<(), AuditLogResponse>(
+                None,
+                format!("{}/items/audit_log?{}", url, query),
+                directus_headers(&token),
+                HttpMethod::GET,
+                Upstream::Directus,
+            )
+            .await
+            .map(|res| res.data)
+        }
+    }
+}
+
+/// Loads the current values of the given `quotes_option` rows before they are changed.
+/// Returns the rows keyed by id; a failure leaves `before` empty rather than blocking the change.
+pub async fn quotes_option_before(
+    ids: &[u32],
+    fields: &str,
+    headers: reqwest::header::HeaderMap,
+) -> std::collections::HashMap<u32, serde_json::Value> {
+    #[derive(serde::Deserialize)]
+    struct Rows {
+        data: Vec<serde_json::Value>,
+    }
+
+    if ids.is_empty() {
+        return Default::default();
+    }
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let ids = ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>()
+        .join(",");
+    let path = format!(
+        "{}/items/quotes_option?filter[id][_in]={}&fields=id,group_id,{}&limit=-1",
+        url, ids, fields
+    );
+    match call_and_parse::<(), Rows>(None, path, headers, HttpMethod::GET, Upstream::Directus).await
+    {
+        Ok(rows) => rows
+            .data
+            .into_iter()
+            .filter_map(|row| Some((row.get("id")?.as_u64()? as u32, row)))
+            .collect(),
+        Err(e) => {
+            log::error!("Audit Error: could not load quotes before change: {}", e);
+            Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::common::models::audit_model::AuditAction;
+
+    #[tokio::test]
+    async fn test_file_sink_appends_and_reads_newest_first() {
+        let path = std::env::temp_dir().join(format!("audit-{}.log", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        let entry = |actor: &str, action: AuditAction| {
+            AuditEntry::new(actor.to_string(), action, "10.0.0.1".to_string())
+        };
+
+        append_to_file(path, &[entry("a@jabra.io", AuditAction::CreateQuote)])
+            .await
+            .unwrap();
+        append_to_file(
+            path,
+            &[
+                entry("b@jabra.io", AuditAction::ApproveQuote),
+                entry("a@jabra.io", AuditAction::EditQuote),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let all = read_from_file(path, &AuditFilter::default(), 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            all.iter().map(|e| e.action).collect::<Vec<AuditAction>>(),
+            vec![
+                AuditAction::EditQuote,
+                AuditAction::ApproveQuote,
+                AuditAction::CreateQuote
+            ]
+        );
+        let filter = AuditFilter {
+            actor: "a@jabra.io".to_string(),
+            ..Default::default()
+        };
+        let newest = read_from_file(path, &filter, 1).await.unwrap();
+        assert_eq!(
+            newest
+                .iter()
+                .map(|e| e.action)
+                .collect::<Vec<AuditAction>>(),
+            vec![AuditAction::EditQuote]
+        );
+        std::fs::remove_file(path).unwrap();
+
+        assert!(read_from_file(path, &AuditFilter::default(), 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
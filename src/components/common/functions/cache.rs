@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Bumped by [`invalidate_all`]; entries stored under an older generation are stale.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// How long reference data is served from memory, `REFERENCE_CACHE_TTL_SECS` (5 minutes by default).
+/// `0` turns caching off.
+pub fn reference_ttl() -> Duration {
+    Duration::from_secs(
+        std::env::var("REFERENCE_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300),
+    )
+}
+
+/// Drops every cached value, so the next call of each cached server function goes to Directus.
+pub fn invalidate_all() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry<T> {
+    value: T,
+    stored_at: Instant,
+    generation: u64,
+}
+
+impl<T> CacheEntry<T> {
+    fn is_fresh(&self, now: Instant, ttl: Duration, generation: u64) -> bool {
+        self.generation == generation && now.duration_since(self.stored_at) < ttl
+    }
+}
+
+/// Cached reference data, e.g. one Directus reference collection, kept per Directus role.
+/// A value read with one role's token is never served to another role, whose Directus
+/// permissions may differ, and values read without a known role are not cached at all.
+/// Concurrent misses of a role wait for the first fetch instead of all going upstream.
+/// Errors are not cached. Callers still check the session before reading from the cache.
+
+#[derive(Debug)]
+pub struct TtlCache<T> {
+    pub name: &'static str,
+    entries: Mutex<HashMap<String, Arc<tokio::sync::Mutex<Option<CacheEntry<T>>>>>>,
+}
+
+impl<T: Clone> TtlCache<T> {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the value cached for `role` while it is younger than `ttl`, otherwise stores the
+    /// result of `fetch`, which must read with the token of a session holding `role`.
+    pub async fn get_or_fetch<F, Fut, E>(
+        &self,
+        role: Option<&str>,
+        ttl: Duration,
+        fetch: F,
+    ) -> Result<T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let role = match role {
+            Some(role) if !ttl.is_zero() => role,
+            _ => return fetch().await,
+        };
+        let slot = self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(role.to_string())
+            .or_default()
+            .clone();
+        let mut entry = slot.lock().await;
+        let generation = GENERATION.load(Ordering::SeqCst);
+        if let Some(cached) = entry.as_ref() {
+            if cached.is_fresh(Instant::now(), ttl, generation) {
+                log::debug!("Serving {} of role {} from cache", self.name, role);
+                return Ok(cached.value.clone());
+            }
+        }
+
+        let value = fetch().await?;
+        *entry = Some(CacheEntry {
+            value: value.clone(),
+            stored_at: Instant::now(),
+            generation,
+        });
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_freshness() {
+        let now = Instant::now();
+        let entry = CacheEntry {
+            value: 1,
+            stored_at: now,
+            generation: 3,
+        };
+        let ttl = Duration::from_secs(60);
+        assert!(entry.is_fresh(now + Duration::from_secs(59), ttl, 3));
+        assert!(!entry.is_fresh(now + Duration::from_secs(60), ttl, 3));
+        assert!(!entry.is_fresh(now, ttl, 4));
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch() {
+        let cache = TtlCache::<u32>::new("test");
+        let ttl = Duration::from_secs(60);
+        let trader = Some("trader-role");
+
+        let first: Result<u32, ()> = cache.get_or_fetch(trader, ttl, || async { Ok(1) }).await;
+        assert_eq!(first, Ok(1));
+        // Served from the cache
+        let second: Result<u32, ()> = cache.get_or_fetch(trader, ttl, || async { Ok(2) }).await;
+        assert_eq!(second, Ok(1));
+
+        invalidate_all();
+        let failed: Result<u32, &str> = cache
+            .get_or_fetch(trader, ttl, || async { Err("down") })
+            .await;
+        assert_eq!(failed, Err("down"));
+        // Errors are not cached
+        let third: Result<u32, ()> = cache.get_or_fetch(trader, ttl, || async { Ok(3) }).await;
+        assert_eq!(third, Ok(3));
+    }
+
+    #[tokio::test]
+    async fn test_roles_do_not_share_values() {
+        let cache = TtlCache::<u32>::new("test");
+        let ttl = Duration::from_secs(60);
+
+        let admin: Result<u32, ()> = cache
+            .get_or_fetch(Some("admin-role"), ttl, || async { Ok(1) })
+            .await;
+        assert_eq!(admin, Ok(1));
+        let trader: Result<u32, ()> = cache
+            .get_or_fetch(Some("trader-role"), ttl, || async { Ok(2) })
+            .await;
+        assert_eq!(trader, Ok(2));
+
+        // A session without a known role always reads with its own token
+        let unknown: Result<u32, ()> = cache.get_or_fetch(None, ttl, || async { Ok(3) }).await;
+        assert_eq!(unknown, Ok(3));
+        let unknown: Result<u32, ()> = cache.get_or_fetch(None, ttl, || async { Ok(4) }).await;
+        assert_eq!(unknown, Ok(4));
+    }
+}
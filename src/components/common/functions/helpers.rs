@@ -128,22 +128,6 @@ pub fn parse_timestamp(timestamp: &str) -> Option<NaiveDateTime> {
     None // Return None if parsing failed with all formats
 }
 
-#[cfg(feature = "ssr")]
-pub fn enc(plain_text: String) -> String {
-    use magic_crypt::{new_magic_crypt, MagicCryptTrait};
-    let encryption_key = std::env::var("JABRAKEY").unwrap();
-    let magic_crypt = new_magic_crypt!(encryption_key, 256);
-    magic_crypt.encrypt_str_to_base64(plain_text)
-}
-#[cfg(feature = "ssr")]
-pub fn dec(encrypted_text: String) -> String {
-    use magic_crypt::{new_magic_crypt, MagicCryptTrait};
-    let encryption_key = std::env::var("JABRAKEY").unwrap();
-    let magic_crypt = new_magic_crypt!(encryption_key, 256);
-    magic_crypt
-        .decrypt_base64_to_string(encrypted_text)
-        .unwrap()
-}
 pub fn transform_string(input: &str) -> String {
     // Split the input string into parts
     let parts: Vec<&str> = input.split('-').collect();
@@ -485,6 +469,16 @@ pub fn create_trade_expiry_in_utc(datetime: String, time: String) -> String {
     }
 }
 
+/// Days from `now` until `expiry_timestamp`, a UTC expiry as built by [`create_trade_expiry_in_utc`].
+pub fn get_ttm_until(expiry_timestamp: &str, now: DateTime<Utc>) -> Option<f64> {
+    let expiry = NaiveDateTime::parse_from_str(expiry_timestamp, "%Y-%m-%dT%H:%M:%S").ok()?;
+    let seconds = Utc
+        .from_utc_datetime(&expiry)
+        .signed_duration_since(now)
+        .num_seconds();
+    Some(seconds as f64 / 86400.0)
+}
+
 pub fn get_trade_expiry_date(datetime: String) -> String {
     let expiry_datetime = parse_timestamp(&datetime);
     match expiry_datetime {
@@ -551,21 +545,6 @@ pub fn get_conditional_loss(
     }
 }
 
-//Simple encryption and decryption
-use magic_crypt::{new_magic_crypt, MagicCryptTrait};
-
-pub fn encrypt(plain_text: String) -> String {
-    let magic_crypt = new_magic_crypt!("U2VjcmV0Q29kZTEyMzQ", 256);
-    magic_crypt.encrypt_str_to_base64(plain_text)
-}
-
-pub fn decrypt(encrypted_text: String) -> String {
-    let magic_crypt = new_magic_crypt!("U2VjcmV0Q29kZTEyMzQ", 256);
-    magic_crypt
-        .decrypt_base64_to_string(encrypted_text)
-        .unwrap()
-}
-
 pub fn format_number_en(number_str: String, precision: usize) -> String {
     let test_number = number_str.parse::<f64>();
     if test_number.is_err() {
@@ -1,13 +1,191 @@
-use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
 
+use crate::components::common::models::common_models::{
+    Currency, CurrencyPair, UnifiedCurrencyPairConfigurationResponse,
+};
+
+/// How a value that falls between two steps is rounded onto one.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RoundType {
-    Default,
-    Ceiling,
+    /// Down, towards negative infinity.
     Floor,
+    /// Up, towards positive infinity.
+    Ceiling,
+    /// To the nearest step, ties to the even step.
+    HalfEven,
+    /// Towards zero, so the size of the value never grows.
+    TowardZero,
+}
+
+impl RoundType {
+    pub const ALL: [RoundType; 4] = [
+        RoundType::Floor,
+        RoundType::Ceiling,
+        RoundType::HalfEven,
+        RoundType::TowardZero,
+    ];
+
+    fn strategy(self) -> RoundingStrategy {
+        match self {
+            RoundType::Floor => RoundingStrategy::ToNegativeInfinity,
+            RoundType::Ceiling => RoundingStrategy::ToPositiveInfinity,
+            RoundType::HalfEven => RoundingStrategy::MidpointNearestEven,
+            RoundType::TowardZero => RoundingStrategy::ToZero,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            RoundType::Floor => "rounded down",
+            RoundType::Ceiling => "rounded up",
+            RoundType::HalfEven => "rounded to the nearest step",
+            RoundType::TowardZero => "rounded towards zero",
+        }
+    }
+}
+
+/// Finest tick or lot size supported, as a number of decimal places.
+pub const MAX_STEP_DECIMALS: u32 = 8;
+/// Coarsest tick or lot size supported, as a power of ten.
+pub const MAX_STEP_EXPONENT: u32 = 4;
+
+/// Every supported tick and lot size, the powers of ten from `0.00000001` to `10000`.
+pub fn supported_step_sizes() -> Vec<Decimal> {
+    let fractions = (1..=MAX_STEP_DECIMALS)
+        .rev()
+        .map(|scale| Decimal::new(1, scale));
+    let wholes = (0..=MAX_STEP_EXPONENT).map(|exponent| Decimal::from(10_i64.pow(exponent)));
+    fractions.chain(wholes).collect()
+}
+
+pub fn is_supported_step(step: Decimal) -> bool {
+    supported_step_sizes().contains(&step)
+}
+
+/// Why [`fit_to_specs`] changed a value.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Adjustment {
+    /// The value was not a multiple of `step` and was rounded onto one.
+    OffStep {
+        step: Decimal,
+        round_type: RoundType,
+        from: Decimal,
+        to: Decimal,
+    },
+    /// The value was below `minimum` and was raised to it.
+    BelowMinimum { minimum: Decimal, from: Decimal },
+}
+
+impl std::fmt::Display for Adjustment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Adjustment::OffStep {
+                step,
+                round_type,
+                from,
+                to,
+            } => write!(
+                f,
+                "{} is not a multiple of {}, {} to {}",
+                from,
+                step.normalize(),
+                round_type.describe(),
+                to
+            ),
+            Adjustment::BelowMinimum { minimum, from } => {
+                write!(
+                    f,
+                    "{} is below the minimum of {}",
+                    from,
+                    minimum.normalize()
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecError {
+    /// Tick and lot sizes must be positive.
+    InvalidStep(Decimal),
+}
+
+impl std::fmt::Display for SpecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpecError::InvalidStep(step) => write!(f, "{} is not a valid step size", step),
+        }
+    }
 }
 
+/// A value fitted to a tick or lot size, with the adjustments made to it in order.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fitted {
+    pub value: Decimal,
+    pub adjustments: Vec<Adjustment>,
+}
+
+impl Fitted {
+    pub fn is_adjusted(&self) -> bool {
+        !self.adjustments.is_empty()
+    }
+
+    /// The adjustments as one sentence for the builders, `None` when the value was kept.
+    pub fn note(&self) -> Option<String> {
+        if !self.is_adjusted() {
+            return None;
+        }
+        Some(
+            self.adjustments
+                .iter()
+                .map(|a| a.to_string())
+                .collect::<Vec<String>>()
+                .join(", then "),
+        )
+    }
+}
+
+/// Fits `value` to a multiple of `step` with `round_type`, then raises it to `minimum` if it
+/// ended up below it. Every change is reported in the result.
+pub fn fit_to_specs(
+    value: Decimal,
+    step: Decimal,
+    minimum: Option<Decimal>,
+    round_type: RoundType,
+) -> Result<Fitted, SpecError> {
+    if step <= Decimal::ZERO {
+        return Err(SpecError::InvalidStep(step));
+    }
+    let mut adjustments = Vec::new();
+    let mut fitted = snap(value, step, round_type);
+    if fitted != value {
+        adjustments.push(Adjustment::OffStep {
+            step,
+            round_type,
+            from: value,
+            to: fitted,
+        });
+    }
+    if let Some(minimum) = minimum.filter(|minimum| fitted < *minimum) {
+        adjustments.push(Adjustment::BelowMinimum {
+            minimum,
+            from: fitted,
+        });
+        fitted = minimum;
+    }
+    Ok(Fitted {
+        value: fitted,
+        adjustments,
+    })
+}
+
+/// [`fit_to_specs`] for the `f64` signals of the builders. The order size is the minimum unless
+/// `allow_negative` is set, e.g. for premiums paid out. An invalid tick size leaves `amount` as is.
 pub fn format_with_specs(
     amount: f64,
     tick_size: f64,
@@ -15,154 +193,72 @@ pub fn format_with_specs(
     round_type: RoundType,
     allow_negative: bool,
 ) -> f64 {
-    if !allow_negative {
-        if amount < order_size {
-            return order_size;
-        }
+    let minimum = (!allow_negative).then(|| convert_to_decimal(order_size));
+    match fit_to_specs(
+        convert_to_decimal(amount),
+        convert_to_decimal(tick_size),
+        minimum,
+        round_type,
+    ) {
+        Ok(fitted) => fitted.value.to_f64().unwrap_or_default(),
+        Err(_) => amount,
     }
-    // Get decimal precisions
-    let amount_precision = get_precision(amount);
-    let standard_precision = get_precision(tick_size);
-
-    let converted_amount = convert_to_decimal(amount);
-    let converted_min_amount = convert_to_decimal(tick_size);
-
-    let divisible = Decimal::from_str("0.0").unwrap();
-
-    // Check if amount meets the standard specs
-    if (amount >= tick_size)
-        && (amount_precision <= standard_precision)
-        && (converted_amount % converted_min_amount == divisible)
-    {
-        // Return original amount if true
-        println!("Return original amount");
-        amount
-    } else {
-        // Perform Calculation if false
-        let value: f64 = amount / tick_size;
-        let rounded_value = match round_type {
-            RoundType::Default => ((value).round()) * tick_size,
-            RoundType::Ceiling => ((value).ceil()) * tick_size,
-            RoundType::Floor => ((value).floor()) * tick_size,
-        };
-        let rounded_value = format!("{:.standard_precision$}", rounded_value)
-            .parse::<f64>()
-            .unwrap();
+}
 
-        rounded_value
+/// Rounds `value` to a multiple of `step`, e.g. `1.3` with a step of `0.2` floors to `1.2`.
+/// A value already on a step is returned as is, and so is any value for a step that is not positive.
+pub fn snap(value: Decimal, step: Decimal, round_type: RoundType) -> Decimal {
+    if step <= Decimal::ZERO || (value % step).is_zero() {
+        return value;
     }
+    (value / step).round_dp_with_strategy(0, round_type.strategy()) * step
 }
 
+/// Number of decimal places of `decimal`, e.g. `3` for `0.001` and `0` for `100.0`.
 pub fn get_precision(decimal: f64) -> usize {
-    let decimal = decimal.to_string().split(".").last().unwrap().len();
-    decimal
+    convert_to_decimal(decimal).normalize().scale() as usize
 }
 
+/// Converts through the shortest decimal representation of `decimal`, e.g. `0.1` stays `0.1`.
+/// `NaN` and infinities become zero.
 pub fn convert_to_decimal(decimal: f64) -> Decimal {
-    Decimal::from_str(decimal.to_string().as_str()).unwrap()
+    Decimal::from_f64(decimal).unwrap_or_default()
+}
+
+/// The instrument types that have their own tick and lot sizes in Directus.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstrumentType {
+    Spot,
+    Future,
+    Perp,
+    Option,
 }
 
-// pub fn format_to_specs(amount: f64, tick_size: f64, order_size: f64) -> f64 {
-//     if amount < order_size {
-//         return order_size;
-//     }
-//     // Get decimal precisions
-//     let amount_precision = get_precision(amount);
-//     let standard_precision = get_precision(tick_size);
-
-//     let converted_amount = convert_to_decimal(amount);
-//     let converted_min_amount = convert_to_decimal(tick_size);
-
-//     let divisible = Decimal::from_str("0.0").unwrap();
-
-//     // Check if amount meets the standard specs
-//     if (amount >= tick_size)
-//         && (amount_precision <= standard_precision)
-//         && (converted_amount % converted_min_amount == divisible)
-//     {
-//         // Return original amount if true
-//         println!("Return original amount");
-//         amount
-//     } else {
-//         // Perform Calculation if false
-//         let value: f64 = amount / tick_size;
-//         let rounded_value = ((value).floor()) * tick_size;
-//         let rounded_value = format!("{:.standard_precision$}", rounded_value)
-//             .parse::<f64>()
-//             .unwrap();
-//         // println!("Return floored amount");
-
-//         rounded_value
-//     }
-// }
-
-// pub fn format_to_specs_neg(amount: f64, tick_size: f64, _order_size: f64) -> f64 {
-//     // if amount < order_size {
-//     //     return order_size;
-//     // }
-//     // Get decimal precisions
-//     let amount_precision = get_precision(amount);
-//     let standard_precision = get_precision(tick_size);
-
-//     let converted_amount = convert_to_decimal(amount);
-//     let converted_min_amount = convert_to_decimal(tick_size);
-
-//     let divisible = Decimal::from_str("0.0").unwrap();
-
-//     // Check if amount meets the standard specs
-//     if (amount >= tick_size)
-//         && (amount_precision <= standard_precision)
-//         && (converted_amount % converted_min_amount == divisible)
-//     {
-//         // Return original amount if true
-//         println!("Return original amount");
-//         amount
-//     } else {
-//         // Perform Calculation if false
-//         let value: f64 = amount / tick_size;
-//         let rounded_value = ((value).floor()) * tick_size;
-//         let rounded_value = format!("{:.standard_precision$}", rounded_value)
-//             .parse::<f64>()
-//             .unwrap();
-//         // println!("Return floored amount");
-
-//         rounded_value
-//     }
-// }
-
-// Same as format_to_specs but instead of flooring the amount, it rounds it.
-
-pub fn format_to_specs_round(amount: f64, tick_size: f64, _order_size: f64) -> f64 {
-    // if amount < order_size {
-    //     return order_size;
-    // }
-    // Get decimal precisions
-    let amount_precision = get_precision(amount);
-    let standard_precision = get_precision(tick_size);
-
-    let converted_amount = convert_to_decimal(amount);
-    let converted_min_amount = convert_to_decimal(tick_size);
-
-    let divisible = Decimal::from_str("0.0").unwrap();
-
-    // Check if amount meets the standard specs
-    if (amount >= tick_size)
-        && (amount_precision <= standard_precision)
-        && (converted_amount % converted_min_amount == divisible)
-    {
-        // Return original amount if true
-        println!("Return original amount");
-        amount
-    } else {
-        // Perform Calculation if false
-        let value: f64 = amount / tick_size;
-        let rounded_value = ((value).round()) * tick_size;
-        let rounded_value = format!("{:.standard_precision$}", rounded_value)
-            .parse::<f64>()
-            .unwrap();
-        // println!("Return floored amount");
-
-        rounded_value
+impl InstrumentType {
+    pub const ALL: [InstrumentType; 4] = [
+        InstrumentType::Spot,
+        InstrumentType::Future,
+        InstrumentType::Perp,
+        InstrumentType::Option,
+    ];
+
+    /// The `instrument_type` of the specs in Directus.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InstrumentType::Spot => "spot",
+            InstrumentType::Future => "future",
+            InstrumentType::Perp => "perp",
+            InstrumentType::Option => "option",
+        }
+    }
+
+    /// The type of a futures builder quote, whose `rfq_type` is `Future` or `Perpetual`.
+    pub fn from_rfq_type(rfq_type: &str) -> Self {
+        match rfq_type {
+            "Perpetual" => InstrumentType::Perp,
+            _ => InstrumentType::Future,
+        }
     }
 }
 
@@ -186,18 +282,29 @@ pub struct InstrumentSpecTickerName {
     pub ticker: String,
 }
 
+impl InstrumentSpecs {
+    /// Tick and lot sizes must be finite and positive, any such step can be [snapped](snap) onto.
+    /// Only the admin page limits new steps to the [supported](is_supported_step) powers of ten.
+    pub fn validate(&self) -> Result<(), SpecError> {
+        for step in [self.min_price_increment, self.min_contract_increment] {
+            let decimal = convert_to_decimal(step);
+            if !step.is_finite() || decimal <= Decimal::ZERO {
+                return Err(SpecError::InvalidStep(decimal));
+            }
+        }
+        Ok(())
+    }
+}
+
 impl InstrumentSpecsResponse {
     pub fn find_by_ticker_and_instrument_type(
         &self,
         ticker: &str,
         instrument_type: &str,
     ) -> Option<&InstrumentSpecs> {
-        for specs in &self.data {
-            if specs.ccy_id.ticker == ticker && specs.instrument_type == instrument_type {
-                return Some(specs);
-            }
-        }
-        None
+        self.data
+            .iter()
+            .find(|specs| specs.ccy_id.ticker == ticker && specs.instrument_type == instrument_type)
     }
 
     pub fn get_limit_and_precision_by_currency(
@@ -238,6 +345,49 @@ impl InstrumentSpecsResponse {
             None => LimitAndPrecision::new(tick_size, order_size),
         }
     }
+
+    /// `currency` with the tick and lot size of `instrument_type`. Without specs for it, the
+    /// currency keeps the option specs it was configured with.
+    pub fn apply(&self, currency: &Currency, instrument_type: InstrumentType) -> Currency {
+        let limits = self.get_limit_and_precision_by_currency_with_default(
+            &currency.ticker,
+            instrument_type.as_str(),
+            currency.tick_size(),
+            currency.order_size(),
+        );
+        let mut currency = currency.clone();
+        currency.instrument_option.min_price_increment = limits.tick_size;
+        currency.instrument_option.min_contract_increment = limits.order_size;
+        currency
+    }
+
+    /// [`Self::apply`] to both currencies of `pair`.
+    pub fn apply_to_pair(
+        &self,
+        pair: &CurrencyPair,
+        instrument_type: InstrumentType,
+    ) -> CurrencyPair {
+        CurrencyPair {
+            base: self.apply(&pair.base, instrument_type),
+            quote: self.apply(&pair.quote, instrument_type),
+            ..pair.clone()
+        }
+    }
+
+    /// [`Self::apply`] to every pair of a builder for `instrument_type`.
+    pub fn apply_to_configuration(
+        &self,
+        configuration: &UnifiedCurrencyPairConfigurationResponse,
+        instrument_type: InstrumentType,
+    ) -> UnifiedCurrencyPairConfigurationResponse {
+        UnifiedCurrencyPairConfigurationResponse {
+            data: configuration
+                .data
+                .iter()
+                .map(|pair| self.apply_to_pair(pair, instrument_type))
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -267,6 +417,13 @@ impl Default for LimitAndPrecision {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use std::str::FromStr;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
     #[test]
     fn test1() {
         let amount = 0.00001;
@@ -307,4 +464,219 @@ mod tests {
         let result = format_with_specs(amount, min_amount, order_size, RoundType::Floor, false);
         assert_eq!(result, 5.0);
     }
+    #[test]
+    fn test_snap() {
+        let step = dec("0.05");
+        let value = dec("1.275");
+        assert_eq!(snap(value, step, RoundType::Floor).to_string(), "1.25");
+        assert_eq!(snap(value, step, RoundType::Ceiling).to_string(), "1.30");
+        assert_eq!(snap(value, step, RoundType::HalfEven).to_string(), "1.30");
+        assert_eq!(snap(value, step, RoundType::TowardZero).to_string(), "1.25");
+        assert_eq!(snap(-value, step, RoundType::Floor).to_string(), "-1.30");
+        assert_eq!(
+            snap(-value, step, RoundType::TowardZero).to_string(),
+            "-1.25"
+        );
+        // Ties go to the even step
+        assert_eq!(
+            snap(dec("0.25"), dec("0.1"), RoundType::HalfEven),
+            dec("0.2")
+        );
+        assert_eq!(
+            snap(dec("0.35"), dec("0.1"), RoundType::HalfEven),
+            dec("0.4")
+        );
+        assert_eq!(snap(value, Decimal::ZERO, RoundType::Floor), value);
+        // No binary floating point error creeps in
+        assert_eq!(
+            format_with_specs(0.3, 0.1, 0.1, RoundType::Floor, false),
+            0.3
+        );
+        assert_eq!(
+            format_with_specs(-0.07, 0.01, 0.01, RoundType::Floor, true),
+            -0.07
+        );
+    }
+
+    #[test]
+    fn test_fit_to_specs() {
+        let fitted = fit_to_specs(dec("1.3"), dec("0.2"), None, RoundType::Floor).unwrap();
+        assert_eq!(fitted.value, dec("1.2"));
+        assert_eq!(
+            fitted.note().unwrap(),
+            "1.3 is not a multiple of 0.2, rounded down to 1.2"
+        );
+
+        let fitted =
+            fit_to_specs(dec("0.04"), dec("0.1"), Some(dec("0.1")), RoundType::Floor).unwrap();
+        assert_eq!(fitted.value, dec("0.1"));
+        assert_eq!(
+            fitted.note().unwrap(),
+            "0.04 is not a multiple of 0.1, rounded down to 0, then 0 is below the minimum of 0.1"
+        );
+
+        let fitted = fit_to_specs(dec("13.5"), dec("0.1"), Some(dec("0.1")), RoundType::Floor);
+        assert_eq!(fitted.unwrap().note(), None);
+
+        assert_eq!(
+            fit_to_specs(dec("1"), Decimal::ZERO, None, RoundType::Floor),
+            Err(SpecError::InvalidStep(Decimal::ZERO))
+        );
+    }
+
+    #[test]
+    fn test_get_precision() {
+        assert_eq!(get_precision(0.0001), 4);
+        assert_eq!(get_precision(1e-7), 7);
+        assert_eq!(get_precision(1e-8), 8);
+        assert_eq!(get_precision(100.0), 0);
+        assert_eq!(get_precision(1234.1234), 4);
+    }
+
+    #[test]
+    fn test_supported_step_sizes() {
+        let steps = supported_step_sizes();
+        assert_eq!(steps.first(), Some(&dec("0.00000001")));
+        assert_eq!(steps.last(), Some(&dec("10000")));
+        assert!(is_supported_step(dec("0.010")));
+        assert!(!is_supported_step(dec("0.05")));
+        assert!(!is_supported_step(dec("0.000000001")));
+        assert!(!is_supported_step(Decimal::ZERO));
+    }
+
+    fn specs(ticker: &str, instrument_type: &str, tick: f64, lot: f64) -> InstrumentSpecs {
+        InstrumentSpecs {
+            instrument_type: instrument_type.to_string(),
+            contract_multiplier: 1.0,
+            min_price_increment: tick,
+            min_contract_increment: lot,
+            ccy_id: InstrumentSpecTickerName {
+                ticker: ticker.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_apply_specs() {
+        let response = InstrumentSpecsResponse {
+            data: vec![
+                specs("BTC", "spot", 0.01, 0.0001),
+                specs("BTC", "perp", 0.1, 0.001),
+                specs("USD", "spot", 0.01, 1.0),
+            ],
+        };
+        let mut pair = CurrencyPair::default();
+        pair.base.ticker = "BTC".to_string();
+        pair.quote.ticker = "USD".to_string();
+
+        let spot = response.apply_to_pair(&pair, InstrumentType::Spot);
+        assert_eq!(spot.base_tick_size(), 0.01);
+        assert_eq!(spot.base_order_size(), 0.0001);
+        assert_eq!(spot.quote_order_size(), 1.0);
+
+        let perp = response.apply(&pair.base, InstrumentType::from_rfq_type("Perpetual"));
+        assert_eq!(perp.tick_size(), 0.1);
+        assert_eq!(perp.order_size(), 0.001);
+
+        // Without future specs the configured option specs stay
+        let future = response.apply(&pair.base, InstrumentType::Future);
+        assert_eq!(future, pair.base);
+    }
+
+    #[test]
+    fn test_validate_specs() {
+        assert_eq!(specs("BTC", "spot", 0.01, 0.0001).validate(), Ok(()));
+        assert_eq!(
+            specs("BTC", "spot", 0.0, 0.0001).validate(),
+            Err(SpecError::InvalidStep(Decimal::ZERO))
+        );
+        assert!(specs("BTC", "spot", 0.01, -1.0).validate().is_err());
+        assert!(specs("BTC", "perp", 1000.0, 1.0).validate().is_ok());
+        // Steps that are not a power of ten can still be snapped onto
+        assert_eq!(specs("BTC", "perp", 0.5, 0.001).validate(), Ok(()));
+        assert_eq!(specs("BTC", "spot", 0.01, 0.0025).validate(), Ok(()));
+        assert!(specs("BTC", "spot", f64::NAN, 0.0001).validate().is_err());
+        assert!(specs("BTC", "spot", 0.01, f64::INFINITY)
+            .validate()
+            .is_err());
+    }
+
+    /// Decimals of up to 13 digits and 12 decimal places, both signs.
+    fn values() -> impl Strategy<Value = Decimal> {
+        (-1_000_000_000_000_i64..=1_000_000_000_000, 0u32..=12)
+            .prop_map(|(mantissa, scale)| Decimal::new(mantissa, scale))
+    }
+
+    /// Decimals below a million, which stay exact through `f64` once snapped to any step.
+    fn small_values() -> impl Strategy<Value = Decimal> {
+        (-1_000_000_000_000_i64..=1_000_000_000_000, 6u32..=12)
+            .prop_map(|(mantissa, scale)| Decimal::new(mantissa, scale))
+    }
+
+    fn steps() -> impl Strategy<Value = Decimal> {
+        prop::sample::select(supported_step_sizes())
+    }
+
+    fn round_types() -> impl Strategy<Value = RoundType> {
+        prop::sample::select(RoundType::ALL.to_vec())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_snap_lands_on_step_within_one_step(
+            value in values(),
+            step in steps(),
+            round_type in round_types(),
+        ) {
+            let snapped = snap(value, step, round_type);
+            prop_assert!((snapped % step).is_zero());
+            prop_assert!((snapped - value).abs() < step);
+            prop_assert_eq!(snap(snapped, step, round_type), snapped);
+        }
+
+        #[test]
+        fn prop_snap_rounds_in_its_direction(value in values(), step in steps()) {
+            prop_assert!(snap(value, step, RoundType::Floor) <= value);
+            prop_assert!(snap(value, step, RoundType::Ceiling) >= value);
+            prop_assert!(snap(value, step, RoundType::TowardZero).abs() <= value.abs());
+            let nearest = snap(value, step, RoundType::HalfEven);
+            prop_assert!((nearest - value).abs() * Decimal::TWO <= step);
+        }
+
+        #[test]
+        fn prop_fit_reports_every_adjustment(
+            value in values(),
+            step in steps(),
+            round_type in round_types(),
+            minimum in prop::option::of(steps()),
+        ) {
+            let fitted = fit_to_specs(value, step, minimum, round_type).unwrap();
+            if let Some(minimum) = minimum {
+                prop_assert!(fitted.value >= minimum);
+            }
+            let off_step = !(value % step).is_zero();
+            let raised = fitted
+                .adjustments
+                .iter()
+                .any(|a| matches!(a, Adjustment::BelowMinimum { .. }));
+            prop_assert_eq!(
+                fitted.adjustments.first().is_some_and(|a| matches!(a, Adjustment::OffStep { .. })),
+                off_step
+            );
+            prop_assert_eq!(fitted.value == value, !off_step && !raised);
+            prop_assert_eq!(fitted.note().is_some(), fitted.is_adjusted());
+        }
+
+        #[test]
+        fn prop_format_with_specs_matches_decimal_engine(
+            value in small_values(),
+            step in steps(),
+            round_type in round_types(),
+        ) {
+            let amount = value.to_f64().unwrap();
+            let expected = snap(convert_to_decimal(amount), step, round_type);
+            let result = format_with_specs(amount, step.to_f64().unwrap(), 0.0, round_type, true);
+            prop_assert_eq!(convert_to_decimal(result), expected.normalize());
+        }
+    }
 }
@@ -0,0 +1,268 @@
+use crate::errors::JabraError;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Sliding window limiter keyed by an arbitrary string, e.g. the client address.
+/// State is kept in memory, so limits are per server instance.
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    pub max_attempts: usize,
+    pub window: Duration,
+    attempts: Mutex<HashMap<String, Vec<Instant>>>,
+}
+
+impl RateLimiter {
+    pub fn new(max_attempts: usize, window: Duration) -> Self {
+        Self {
+            max_attempts,
+            window,
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records an attempt for `key`.
+    /// Fails with [`JabraError::TooManyRequests`] once `max_attempts` were made within the window.
+    pub fn check(&self, key: &str) -> Result<(), JabraError> {
+        self.check_at(key, Instant::now())
+    }
+
+    pub fn check_at(&self, key: &str, now: Instant) -> Result<(), JabraError> {
+        let mut attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        // Drop keys that went quiet so the map does not grow without bound
+        attempts.retain(|_, times| {
+            times.retain(|t| now.duration_since(*t) < self.window);
+            !times.is_empty()
+        });
+
+        let times = attempts.entry(key.to_string()).or_default();
+        if times.len() >= self.max_attempts {
+            let retry_after = self.window - now.duration_since(times[0]);
+            return Err(JabraError::TooManyRequests {
+                retry_after_secs: retry_after.as_secs().max(1),
+            });
+        }
+        times.push(now);
+        Ok(())
+    }
+}
+
+/// Failure based throttling for credentials, e.g. logins keyed by email.
+/// The first `free_attempts` failures are not delayed, after that each failure doubles the wait
+/// before the next attempt, and `lockout_after` failures lock the key for `lockout`.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackoffPolicy {
+    pub free_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub lockout_after: u32,
+    pub lockout: Duration,
+    /// Failures older than this are forgotten.
+    pub forget_after: Duration,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            free_attempts: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            lockout_after: 10,
+            lockout: Duration::from_secs(15 * 60),
+            forget_after: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FailureState {
+    failures: u32,
+    last_failure: Instant,
+}
+
+#[derive(Debug)]
+pub struct LoginThrottle {
+    pub policy: BackoffPolicy,
+    state: Mutex<HashMap<String, FailureState>>,
+}
+
+impl LoginThrottle {
+    pub fn new(policy: BackoffPolicy) -> Self {
+        Self {
+            policy,
+            state: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fails with [`JabraError::AccountLocked`] during a lockout and with
+    /// [`JabraError::TooManyRequests`] while the backoff after the last failure has not passed.
+    pub fn check(&self, key: &str) -> Result<(), JabraError> {
+        self.check_at(key, Instant::now())
+    }
+
+    pub fn check_at(&self, key: &str, now: Instant) -> Result<(), JabraError> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.retain(|_, s| now.duration_since(s.last_failure) < self.policy.forget_after);
+
+        let entry = match state.get(key) {
+            Some(entry) => *entry,
+            None => return Ok(()),
+        };
+        let since_failure = now.duration_since(entry.last_failure);
+        if entry.failures >= self.policy.lockout_after {
+            return match self.policy.lockout.checked_sub(since_failure) {
+                Some(remaining) if !remaining.is_zero() => Err(JabraError::AccountLocked {
+                    retry_after_secs: remaining.as_secs().max(1),
+                }),
+                _ => {
+                    state.remove(key);
+                    Ok(())
+                }
+            };
+        }
+        if entry.failures > self.policy.free_attempts {
+            let delay = self.backoff(entry.failures - self.policy.free_attempts - 1);
+            if let Some(remaining) = delay.checked_sub(since_failure) {
+                if !remaining.is_zero() {
+                    return Err(JabraError::TooManyRequests {
+                        retry_after_secs: remaining.as_secs().max(1),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Delay after failure number `free_attempts + attempt + 1`, doubled each time and capped at `max_delay`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.policy
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.policy.max_delay)
+    }
+
+    /// Records a failed attempt and returns the number of consecutive failures for `key`.
+    pub fn record_failure(&self, key: &str) -> u32 {
+        self.record_failure_at(key, Instant::now())
+    }
+
+    pub fn record_failure_at(&self, key: &str, now: Instant) -> u32 {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = state.entry(key.to_string()).or_insert(FailureState {
+            failures: 0,
+            last_failure: now,
+        });
+        entry.failures += 1;
+        entry.last_failure = now;
+        entry.failures
+    }
+
+    pub fn record_success(&self, key: &str) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.remove(key);
+    }
+}
+
+/// Password failures per account, shared by the login and every form that asks for the password again,
+/// so each account has one budget of guesses.
+pub fn password_throttle() -> &'static LoginThrottle {
+    static THROTTLE: std::sync::OnceLock<LoginThrottle> = std::sync::OnceLock::new();
+    THROTTLE.get_or_init(|| LoginThrottle::new(BackoffPolicy::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_within_window() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+        assert!(limiter.check_at("a", start).is_ok());
+        assert!(limiter
+            .check_at("a", start + Duration::from_secs(1))
+            .is_ok());
+        assert_eq!(
+            limiter.check_at("a", start + Duration::from_secs(10)),
+            Err(JabraError::TooManyRequests {
+                retry_after_secs: 50
+            })
+        );
+        // Other keys are not affected
+        assert!(limiter
+            .check_at("b", start + Duration::from_secs(10))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_window_slides() {
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        let start = Instant::now();
+        assert!(limiter.check_at("a", start).is_ok());
+        assert!(limiter
+            .check_at("a", start + Duration::from_secs(59))
+            .is_err());
+        assert!(limiter
+            .check_at("a", start + Duration::from_secs(60))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_login_backoff_and_lockout() {
+        let throttle = LoginThrottle::new(BackoffPolicy {
+            free_attempts: 2,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(4),
+            lockout_after: 5,
+            lockout: Duration::from_secs(60),
+            forget_after: Duration::from_secs(3600),
+        });
+        let key = "trader@jabra.io";
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        throttle.record_failure_at(key, at(0));
+        throttle.record_failure_at(key, at(0));
+        // Free attempts are over, the next one waits a second
+        throttle.record_failure_at(key, at(0));
+        assert_eq!(
+            throttle.check_at(key, at(0)),
+            Err(JabraError::TooManyRequests {
+                retry_after_secs: 1
+            })
+        );
+        assert!(throttle.check_at(key, at(1)).is_ok());
+
+        throttle.record_failure_at(key, at(1));
+        assert!(throttle.check_at(key, at(2)).is_err());
+        assert!(throttle.check_at(key, at(3)).is_ok());
+
+        assert_eq!(throttle.record_failure_at(key, at(3)), 5);
+        assert_eq!(
+            throttle.check_at(key, at(13)),
+            Err(JabraError::AccountLocked {
+                retry_after_secs: 50
+            })
+        );
+        // Other emails are unaffected
+        assert!(throttle.check_at("other@jabra.io", at(13)).is_ok());
+        // The lockout ends and the failures are forgotten
+        assert!(throttle.check_at(key, at(63)).is_ok());
+        assert!(throttle.check_at(key, at(63)).is_ok());
+    }
+
+    #[test]
+    fn test_login_success_resets() {
+        let throttle = LoginThrottle::new(BackoffPolicy {
+            free_attempts: 0,
+            ..Default::default()
+        });
+        let now = Instant::now();
+        throttle.record_failure_at("key", now);
+        assert!(throttle.check_at("key", now).is_err());
+        throttle.record_success("key");
+        assert!(throttle.check_at("key", now).is_ok());
+    }
+}
@@ -0,0 +1,298 @@
+use crate::errors::JabraError;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use sha2::{Digest, Sha256};
+
+const NONCE_LEN: usize = 24;
+
+/// A sealing key and the id it is stored under in every cookie it seals.
+
+#[derive(Clone)]
+pub struct SessionKey {
+    pub id: String,
+    key: Key,
+}
+
+impl std::fmt::Debug for SessionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionKey").field("id", &self.id).finish()
+    }
+}
+
+/// The keys used to seal session cookies, newest first.
+/// New cookies are always sealed with the first key; the others are only used to open
+/// cookies issued before a rotation, until they expire.
+///
+/// Sealed values look like `{key id}.{expires at}.{nonce + ciphertext}` where the key id and
+/// expiry are authenticated with the ciphertext, so neither can be swapped without detection.
+
+#[derive(Debug, Clone)]
+pub struct SessionKeys {
+    keys: Vec<SessionKey>,
+    legacy: Option<LegacyKey>,
+}
+
+/// The `JABRAKEY` secret the previous portal encrypted session cookies with, through magic-crypt.
+
+#[derive(Clone)]
+struct LegacyKey(String);
+
+impl std::fmt::Debug for LegacyKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LegacyKey")
+    }
+}
+
+fn rejected(reason: &str) -> JabraError {
+    log::info!("CookieFetchError: {}", reason);
+    JabraError::CookieFetchError
+}
+
+impl SessionKeys {
+    /// Parses a comma separated list of `id:secret`, newest first, e.g. `2:new-secret,1:old-secret`.
+    /// Secrets are stretched to 256 bit keys with SHA-256.
+    pub fn parse(config: &str) -> Result<Self, String> {
+        let keys = config
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (id, secret) = entry
+                    .split_once(':')
+                    .ok_or_else(|| format!("session key `{}` is not `id:secret`", entry))?;
+                if id.is_empty()
+                    || !id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    return Err(format!("invalid session key id `{}`", id));
+                }
+                if secret.is_empty() {
+                    return Err(format!("session key `{}` has an empty secret", id));
+                }
+                Ok(SessionKey {
+                    id: id.to_string(),
+                    key: Key::clone_from_slice(&Sha256::digest(secret.as_bytes())),
+                })
+            })
+            .collect::<Result<Vec<SessionKey>, String>>()?;
+        if keys.is_empty() {
+            return Err("no session keys configured".to_string());
+        }
+        Ok(Self { keys, legacy: None })
+    }
+
+    /// Also opens cookies the previous portal encrypted with `secret`, see [`SessionKeys::open_legacy`].
+    pub fn with_legacy_secret(mut self, secret: &str) -> Self {
+        self.legacy = Some(LegacyKey(secret.to_string()));
+        self
+    }
+
+    /// Reads `SESSION_KEYS`, falling back to `JABRAKEY` as key `1` for existing deployments.
+    /// `JABRAKEY`, when set, is also kept to open the cookies of the previous portal.
+    pub fn from_env() -> Result<Self, String> {
+        let keys = match std::env::var("SESSION_KEYS") {
+            Ok(keys) => Self::parse(&keys)?,
+            Err(_) => match std::env::var("JABRAKEY") {
+                Ok(secret) => Self::parse(&format!("1:{}", secret))?,
+                Err(_) => return Err("neither SESSION_KEYS nor JABRAKEY is set".to_string()),
+            },
+        };
+        Ok(match std::env::var("JABRAKEY") {
+            Ok(secret) => keys.with_legacy_secret(&secret),
+            Err(_) => keys,
+        })
+    }
+
+    pub fn current(&self) -> &SessionKey {
+        &self.keys[0]
+    }
+
+    /// Seals `plain_text` with the current key. The value can be opened until `expires_at` (unix seconds).
+    pub fn seal(&self, plain_text: &str, expires_at: i64) -> String {
+        let key = self.current();
+        let header = format!("{}.{}", key.id, expires_at);
+        let cipher = XChaCha20Poly1305::new(&key.key);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plain_text.as_bytes(),
+                    aad: header.as_bytes(),
+                },
+            )
+            .expect("session payload is too large to seal");
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        format!("{}.{}", header, URL_SAFE_NO_PAD.encode(sealed))
+    }
+
+    /// Opens a value sealed by [`SessionKeys::seal`].
+    /// Unknown key ids, expired or tampered values all fail with [`JabraError::CookieFetchError`].
+    pub fn open(&self, sealed: &str, now: i64) -> Result<String, JabraError> {
+        let mut parts = sealed.splitn(3, '.');
+        let (id, expires_at, body) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(id), Some(expires_at), Some(body)) => (id, expires_at, body),
+            _ => return Err(rejected("malformed session cookie")),
+        };
+        let key = self
+            .keys
+            .iter()
+            .find(|key| key.id == id)
+            .ok_or_else(|| rejected("session cookie sealed with an unknown key"))?;
+        let expiry = expires_at
+            .parse::<i64>()
+            .map_err(|_| rejected("malformed session cookie expiry"))?;
+        if expiry <= now {
+            return Err(rejected("session cookie expired"));
+        }
+
+        let body = URL_SAFE_NO_PAD
+            .decode(body)
+            .map_err(|_| rejected("malformed session cookie encoding"))?;
+        if body.len() <= NONCE_LEN {
+            return Err(rejected("session cookie is too short"));
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        let header = format!("{}.{}", id, expires_at);
+        let plain_text = XChaCha20Poly1305::new(&key.key)
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header.as_bytes(),
+                },
+            )
+            .map_err(|_| rejected("session cookie failed authentication"))?;
+        String::from_utf8(plain_text).map_err(|_| rejected("session cookie is not utf-8"))
+    }
+
+    /// Opens a cookie encrypted by the previous portal: magic-crypt AES-256-CBC, base64 encoded,
+    /// without a key id, an expiry or authentication. It is only read so the sessions that were
+    /// live when sealing was introduced can be re-sealed; callers must bound how old it may be.
+    pub fn open_legacy(&self, encrypted: &str) -> Result<String, JabraError> {
+        use magic_crypt::{new_magic_crypt, MagicCryptTrait};
+
+        let LegacyKey(secret) = self
+            .legacy
+            .as_ref()
+            .ok_or_else(|| rejected("no legacy session key configured"))?;
+        new_magic_crypt!(secret, 256)
+            .decrypt_base64_to_string(encrypted)
+            .map_err(|_| rejected("legacy session cookie failed to decrypt"))
+    }
+}
+
+static SESSION_KEYS: std::sync::OnceLock<SessionKeys> = std::sync::OnceLock::new();
+
+/// The configured session keys. `main` loads them at startup so a bad configuration fails fast.
+pub fn session_keys() -> &'static SessionKeys {
+    SESSION_KEYS.get_or_init(|| {
+        SessionKeys::from_env().unwrap_or_else(|e| panic!("invalid session keys: {}", e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn test_seal_and_open() {
+        let keys = SessionKeys::parse("1:secret").unwrap();
+        let sealed = keys.seal("{\"user_id\":\"abc\"}", NOW + 60);
+        assert!(sealed.starts_with("1.1700000060."));
+        assert_eq!(keys.open(&sealed, NOW).unwrap(), "{\"user_id\":\"abc\"}");
+    }
+
+    #[test]
+    fn test_rotation_keeps_old_cookies() {
+        let old = SessionKeys::parse("1:old-secret").unwrap();
+        let sealed = old.seal("payload", NOW + 60);
+
+        let rotated = SessionKeys::parse("2:new-secret,1:old-secret").unwrap();
+        assert_eq!(rotated.open(&sealed, NOW).unwrap(), "payload");
+        assert!(rotated.seal("payload", NOW + 60).starts_with("2."));
+
+        let retired = SessionKeys::parse("2:new-secret").unwrap();
+        assert_eq!(
+            retired.open(&sealed, NOW),
+            Err(JabraError::CookieFetchError)
+        );
+    }
+
+    #[test]
+    fn test_expired_cookie() {
+        let keys = SessionKeys::parse("1:secret").unwrap();
+        let sealed = keys.seal("payload", NOW);
+        assert_eq!(keys.open(&sealed, NOW), Err(JabraError::CookieFetchError));
+    }
+
+    #[test]
+    fn test_tampered_cookie() {
+        let keys = SessionKeys::parse("1:secret").unwrap();
+        let sealed = keys.seal("payload", NOW + 60);
+
+        // Extending the expiry breaks the authentication tag
+        let extended = sealed.replacen("1700000060", "1800000000", 1);
+        assert_eq!(keys.open(&extended, NOW), Err(JabraError::CookieFetchError));
+
+        let mut flipped = sealed.clone().into_bytes();
+        let last = flipped.len() - 1;
+        flipped[last] = if flipped[last] == b'A' { b'B' } else { b'A' };
+        let flipped = String::from_utf8(flipped).unwrap();
+        assert_eq!(keys.open(&flipped, NOW), Err(JabraError::CookieFetchError));
+
+        for garbage in [
+            "",
+            "1",
+            "1.abc.def",
+            "1.1700000060.!!!",
+            "9.1700000060.AAAA",
+        ] {
+            assert_eq!(keys.open(garbage, NOW), Err(JabraError::CookieFetchError));
+        }
+    }
+
+    #[test]
+    fn test_open_legacy() {
+        use magic_crypt::{new_magic_crypt, MagicCryptTrait};
+
+        let encrypted = new_magic_crypt!("old-secret", 256).encrypt_str_to_base64("payload");
+        let keys = SessionKeys::parse("1:new-secret").unwrap();
+        assert_eq!(
+            keys.open_legacy(&encrypted),
+            Err(JabraError::CookieFetchError)
+        );
+
+        let keys = keys.with_legacy_secret("old-secret");
+        assert_eq!(keys.open_legacy(&encrypted).unwrap(), "payload");
+        // Sealed cookies, other secrets and garbage fail without panicking
+        let sealed = keys.seal("payload", NOW + 60);
+        let other = new_magic_crypt!("other-secret", 256).encrypt_str_to_base64("payload");
+        for garbage in [
+            "",
+            "!!!",
+            "AAAA",
+            "AAAAAAAAAAAAAAAAAAAAAA==",
+            &sealed,
+            &other,
+        ] {
+            assert_eq!(keys.open_legacy(garbage), Err(JabraError::CookieFetchError));
+        }
+    }
+
+    #[test]
+    fn test_invalid_configuration() {
+        assert!(SessionKeys::parse("").is_err());
+        assert!(SessionKeys::parse("secret-without-id").is_err());
+        assert!(SessionKeys::parse("1:").is_err());
+        assert!(SessionKeys::parse("a.b:secret").is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use serde::Serialize;
+
+/// Security relevant events. Each one is logged as a single JSON object under the `security`
+/// target so they can be filtered and shipped separately from the application logs.
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SecurityEvent {
+    LoginSucceeded {
+        email: String,
+        ip: String,
+    },
+    LoginFailed {
+        email: String,
+        ip: String,
+        reason: String,
+        failures: u32,
+    },
+    LoginBlocked {
+        email: String,
+        ip: String,
+        locked: bool,
+        retry_after_secs: u64,
+    },
+}
+
+#[derive(Serialize)]
+struct SecurityRecord<'a> {
+    at: String,
+    #[serde(flatten)]
+    event: &'a SecurityEvent,
+}
+
+impl SecurityEvent {
+    pub fn to_json(&self, at: chrono::DateTime<chrono::Utc>) -> String {
+        serde_json::to_string(&SecurityRecord {
+            at: at.to_rfc3339(),
+            event: self,
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn log(&self) {
+        let record = self.to_json(chrono::Utc::now());
+        match self {
+            SecurityEvent::LoginSucceeded { .. } => log::info!(target: "security", "{}", record),
+            _ => log::warn!(target: "security", "{}", record),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_security_event_json() {
+        let event = SecurityEvent::LoginFailed {
+            email: "trader@jabra.io".to_string(),
+            ip: "10.0.0.1".to_string(),
+            reason: "invalid_credentials".to_string(),
+            failures: 2,
+        };
+        let at = chrono::Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            event.to_json(at),
+            r#"{"at":"2024-03-01T12:00:00+00:00","event":"login_failed","email":"trader@jabra.io","ip":"10.0.0.1","reason":"invalid_credentials","failures":2}"#
+        );
+    }
+}
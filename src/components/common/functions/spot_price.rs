@@ -0,0 +1,387 @@
+use super::upstream::Upstream;
+use super::wrapper::{call_and_parse, HttpMethod};
+use crate::components::common::models::common_models::{
+    BlankRequest, CoinBaseSpotPriceResponse, CurrencyPair,
+};
+use crate::components::common::models::spot_price_model::SpotPrice;
+use crate::errors::JabraError;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::OnceLock;
+
+pub type SpotFuture<'a> = Pin<Box<dyn Future<Output = Result<SpotPrice, JabraError>> + Send + 'a>>;
+
+/// A source of spot prices. Every price carries its source and the time it was observed.
+
+pub trait SpotPriceProvider: Send + Sync {
+    fn name(&self) -> String;
+    fn spot_price<'a>(&'a self, pair: &'a CurrencyPair) -> SpotFuture<'a>;
+}
+
+/// Prices older than this are stale, `SPOT_MAX_AGE_SECS` (30 seconds by default).
+pub fn max_age_ms() -> i64 {
+    std::env::var("SPOT_MAX_AGE_SECS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(30)
+        * 1_000
+}
+
+fn parse_price(provider: &str, amount: &str) -> Result<f64, JabraError> {
+    amount
+        .parse::<f64>()
+        .ok()
+        .filter(|price| price.is_finite() && *price > 0.0)
+        .ok_or_else(|| {
+            JabraError::SerializationError(format!(
+                "{} returned an invalid price `{}`",
+                provider, amount
+            ))
+        })
+}
+
+/// Coinbase `GET /v2/prices/{BASE-QUOTE}/spot`. The response has no timestamp,
+/// so the price is stamped with the time it was received.
+pub struct CoinbaseProvider {
+    pub url: String,
+}
+
+impl SpotPriceProvider for CoinbaseProvider {
+    fn name(&self) -> String {
+        Upstream::Coinbase.name().to_string()
+    }
+
+    fn spot_price<'a>(&'a self, pair: &'a CurrencyPair) -> SpotFuture<'a> {
+        Box::pin(async move {
+            let path = format!("{}/prices/{}/spot", self.url, pair.coinbase_name());
+            let response = call_and_parse::<BlankRequest, CoinBaseSpotPriceResponse>(
+                Option::None,
+                path,
+                reqwest::header::HeaderMap::default(),
+                HttpMethod::GET,
+                Upstream::Coinbase,
+            )
+            .await?;
+            Ok(SpotPrice::new(
+                pair.coinbase_name(),
+                parse_price(&self.name(), &response.data.amount)?,
+                self.name(),
+                chrono::Utc::now().timestamp_millis(),
+                max_age_ms(),
+            ))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BitstampTickerResponse {
+    /// Unix seconds, as a string.
+    pub timestamp: String,
+    pub last: String,
+}
+
+/// Bitstamp `GET /api/v2/ticker/{basequote}/`, which reports when the last trade happened.
+pub struct BitstampProvider {
+    pub url: String,
+}
+
+impl SpotPriceProvider for BitstampProvider {
+    fn name(&self) -> String {
+        Upstream::Bitstamp.name().to_string()
+    }
+
+    fn spot_price<'a>(&'a self, pair: &'a CurrencyPair) -> SpotFuture<'a> {
+        Box::pin(async move {
+            let market = format!("{}{}", pair.base.ticker, pair.quote.ticker).to_lowercase();
+            let path = format!("{}/ticker/{}/", self.url, market);
+            let response = call_and_parse::<BlankRequest, BitstampTickerResponse>(
+                Option::None,
+                path,
+                reqwest::header::HeaderMap::default(),
+                HttpMethod::GET,
+                Upstream::Bitstamp,
+            )
+            .await?;
+            let timestamp = response.timestamp.parse::<i64>().map_err(|_| {
+                JabraError::SerializationError(format!(
+                    "bitstamp returned an invalid timestamp `{}`",
+                    response.timestamp
+                ))
+            })?;
+            Ok(SpotPrice::new(
+                pair.coinbase_name(),
+                parse_price(&self.name(), &response.last)?,
+                self.name(),
+                timestamp * 1_000,
+                max_age_ms(),
+            ))
+        })
+    }
+}
+
+pub fn median(prices: &[f64]) -> Option<f64> {
+    let mut prices = prices.to_vec();
+    prices.sort_by(|a, b| a.total_cmp(b));
+    let mid = prices.len() / 2;
+    match prices.len() {
+        0 => None,
+        n if n % 2 == 0 => Some((prices[mid - 1] + prices[mid]) / 2.0),
+        _ => Some(prices[mid]),
+    }
+}
+
+/// Combines the fresh prices into their median. Stale prices are dropped first, so one slow
+/// source falls back to the others. The result is as old as the oldest price it used.
+/// Fails as unavailable when no source answered, and as stale when every answer was too old.
+pub fn aggregate(
+    pair: &str,
+    prices: Vec<SpotPrice>,
+    now_ms: i64,
+    max_age_ms: i64,
+) -> Result<SpotPrice, JabraError> {
+    if prices.is_empty() {
+        return Err(JabraError::Upstream {
+            service: "spot price".to_string(),
+            status: 503,
+            code: None,
+            body: String::new(),
+        });
+    }
+    let fresh = prices
+        .into_iter()
+        .filter(|price| !price.is_stale_at(now_ms))
+        .collect::<Vec<SpotPrice>>();
+    if fresh.len() == 1 {
+        return Ok(fresh.into_iter().next().unwrap());
+    }
+    let price = median(&fresh.iter().map(|p| p.price).collect::<Vec<f64>>()).ok_or_else(|| {
+        JabraError::StaleSpotPrice {
+            pair: pair.to_string(),
+        }
+    })?;
+    let sources = fresh
+        .iter()
+        .map(|p| p.source.as_str())
+        .collect::<Vec<&str>>()
+        .join(",");
+    let timestamp = fresh.iter().map(|p| p.timestamp).min().unwrap_or(now_ms);
+    Ok(SpotPrice::new(
+        pair.to_string(),
+        price,
+        format!("median({})", sources),
+        timestamp,
+        max_age_ms,
+    ))
+}
+
+/// Asks every provider at once and returns the median of the fresh answers.
+pub struct MedianAggregator {
+    pub providers: Vec<Box<dyn SpotPriceProvider>>,
+}
+
+impl SpotPriceProvider for MedianAggregator {
+    fn name(&self) -> String {
+        let names = self
+            .providers
+            .iter()
+            .map(|p| p.name())
+            .collect::<Vec<String>>()
+            .join(",");
+        format!("median({})", names)
+    }
+
+    fn spot_price<'a>(&'a self, pair: &'a CurrencyPair) -> SpotFuture<'a> {
+        Box::pin(async move {
+            let results = futures::future::join_all(
+                self.providers
+                    .iter()
+                    .map(|provider| provider.spot_price(pair)),
+            )
+            .await;
+            let prices = results
+                .into_iter()
+                .zip(self.providers.iter())
+                .filter_map(|(result, provider)| match result {
+                    Ok(price) => Some(price),
+                    Err(e) => {
+                        log::warn!(
+                            "Spot price from {} for {} failed: {}",
+                            provider.name(),
+                            pair.coinbase_name(),
+                            e
+                        );
+                        None
+                    }
+                })
+                .collect::<Vec<SpotPrice>>();
+            aggregate(
+                &pair.coinbase_name(),
+                prices,
+                chrono::Utc::now().timestamp_millis(),
+                max_age_ms(),
+            )
+        })
+    }
+}
+
+pub fn bitstamp_url() -> String {
+    std::env::var("BITSTAMP_URL").unwrap_or_else(|_| "https://www.bitstamp.net/api/v2".to_string())
+}
+
+/// Parses a comma separated list of spot sources, e.g. `coinbase,bitstamp`.
+/// Falls back to Coinbase when nothing usable is listed.
+pub fn parse_sources(sources: &str) -> Vec<Upstream> {
+    let mut upstreams = Vec::new();
+    for source in sources.split(',').map(|s| s.trim().to_lowercase()) {
+        let upstream = match source.as_str() {
+            "coinbase" => Upstream::Coinbase,
+            "bitstamp" => Upstream::Bitstamp,
+            "" => continue,
+            other => {
+                log::error!("Unknown spot price source `{}` in SPOT_SOURCES", other);
+                continue;
+            }
+        };
+        if !upstreams.contains(&upstream) {
+            upstreams.push(upstream);
+        }
+    }
+    if upstreams.is_empty() {
+        upstreams.push(Upstream::Coinbase);
+    }
+    upstreams
+}
+
+/// The sources listed in `SPOT_SOURCES`, Coinbase by default.
+pub fn configured_sources() -> Vec<Upstream> {
+    parse_sources(&std::env::var("SPOT_SOURCES").unwrap_or_default())
+}
+
+/// Builds the configured providers. More than one source is combined with a [`MedianAggregator`].
+pub fn provider_from_env() -> Box<dyn SpotPriceProvider> {
+    let mut providers = configured_sources()
+        .into_iter()
+        .map(|upstream| -> Box<dyn SpotPriceProvider> {
+            match upstream {
+                Upstream::Bitstamp => Box::new(BitstampProvider {
+                    url: bitstamp_url(),
+                }),
+                _ => Box::new(CoinbaseProvider {
+                    url: std::env::var("COINBASE_V2").unwrap_or_default(),
+                }),
+            }
+        })
+        .collect::<Vec<Box<dyn SpotPriceProvider>>>();
+    match providers.len() {
+        1 => providers.remove(0),
+        _ => Box::new(MedianAggregator { providers }),
+    }
+}
+
+static SPOT_PRICE_SOURCE: OnceLock<Box<dyn SpotPriceProvider>> = OnceLock::new();
+
+/// The configured spot price source.
+pub fn spot_price_source() -> &'static dyn SpotPriceProvider {
+    SPOT_PRICE_SOURCE.get_or_init(provider_from_env).as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000_000;
+
+    fn price(source: &str, price: f64, age_ms: i64) -> SpotPrice {
+        SpotPrice::new(
+            "BTC-USD".to_string(),
+            price,
+            source.to_string(),
+            NOW - age_ms,
+            30_000,
+        )
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[]), None);
+        assert_eq!(median(&[3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+
+    #[test]
+    fn test_aggregate_drops_stale_prices() {
+        let result = aggregate(
+            "BTC-USD",
+            vec![
+                price("coinbase", 100.0, 1_000),
+                price("bitstamp", 102.0, 5_000),
+                price("kraken", 500.0, 60_000),
+            ],
+            NOW,
+            30_000,
+        )
+        .unwrap();
+        assert_eq!(result.price, 101.0);
+        assert_eq!(result.source, "median(coinbase,bitstamp)");
+        assert_eq!(result.timestamp, NOW - 5_000);
+        assert_eq!(result.stale_after, NOW + 25_000);
+    }
+
+    #[test]
+    fn test_aggregate_falls_back_to_single_source() {
+        let result = aggregate(
+            "BTC-USD",
+            vec![
+                price("coinbase", 100.0, 60_000),
+                price("bitstamp", 102.0, 0),
+            ],
+            NOW,
+            30_000,
+        )
+        .unwrap();
+        assert_eq!(result.source, "bitstamp");
+        assert_eq!(result.price, 102.0);
+    }
+
+    #[test]
+    fn test_aggregate_refuses_stale_prices() {
+        assert_eq!(
+            aggregate(
+                "BTC-USD",
+                vec![price("coinbase", 100.0, 60_000)],
+                NOW,
+                30_000
+            ),
+            Err(JabraError::StaleSpotPrice {
+                pair: "BTC-USD".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_aggregate_without_any_price_is_unavailable() {
+        let result = aggregate("BTC-USD", vec![], NOW, 30_000);
+        assert!(matches!(
+            result,
+            Err(JabraError::Upstream { status: 503, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_sources() {
+        assert_eq!(parse_sources(""), vec![Upstream::Coinbase]);
+        assert_eq!(
+            parse_sources("Bitstamp, coinbase,bitstamp"),
+            vec![Upstream::Bitstamp, Upstream::Coinbase]
+        );
+        assert_eq!(parse_sources("unknown"), vec![Upstream::Coinbase]);
+    }
+
+    #[test]
+    fn test_parse_price() {
+        assert_eq!(parse_price("coinbase", "64000.5"), Ok(64_000.5));
+        assert!(parse_price("coinbase", "").is_err());
+        assert!(parse_price("coinbase", "0").is_err());
+    }
+}
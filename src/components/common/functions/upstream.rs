@@ -0,0 +1,247 @@
+use std::time::Duration;
+
+/// The external services the portal calls.
+/// Each one carries its own request timeout and retry policy.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Upstream {
+    Directus,
+    PricingGateway,
+    Coinbase,
+    Bitstamp,
+}
+
+impl Upstream {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Upstream::Directus => "directus",
+            Upstream::PricingGateway => "pricing_gateway",
+            Upstream::Coinbase => "coinbase",
+            Upstream::Bitstamp => "bitstamp",
+        }
+    }
+
+    /// Prefix of the environment variables that tune this upstream,
+    /// e.g. `DIRECTUS_MAX_RETRIES` or `COINBASE_TIMEOUT_MS`.
+    fn env_prefix(&self) -> &'static str {
+        match self {
+            Upstream::Directus => "DIRECTUS",
+            Upstream::PricingGateway => "PRICING_GATEWAY",
+            Upstream::Coinbase => "COINBASE",
+            Upstream::Bitstamp => "BITSTAMP",
+        }
+    }
+
+    /// Reads the policy for this upstream from the environment, falling back to defaults.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::from_vars(self.env_prefix(), |key| std::env::var(key).ok())
+    }
+}
+
+fn parse_or<T: std::str::FromStr>(value: Option<String>, default: T) -> T {
+    value.and_then(|v| v.parse::<T>().ok()).unwrap_or(default)
+}
+
+#[cfg(feature = "ssr")]
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    parse_or(std::env::var(key).ok(), default)
+}
+
+/// Parses a `Retry-After` header, either delay seconds or an HTTP date, into the time left to wait.
+pub fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    match value.parse::<u64>() {
+        Ok(secs) => Some(Duration::from_secs(secs)),
+        Err(_) => chrono::DateTime::parse_from_rfc2822(value).ok().map(|at| {
+            (at.with_timezone(&chrono::Utc) - now)
+                .to_std()
+                .unwrap_or_default()
+        }),
+    }
+}
+
+/// Retry with exponential backoff. Only applied to idempotent requests.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Builds the policy from the `{prefix}_MAX_RETRIES`, `{prefix}_RETRY_BACKOFF_MS` and
+    /// `{prefix}_TIMEOUT_MS` values returned by `var`, falling back to defaults.
+    pub fn from_vars(prefix: &str, var: impl Fn(&str) -> Option<String>) -> Self {
+        let default = RetryPolicy::default();
+        RetryPolicy {
+            max_retries: parse_or(var(&format!("{}_MAX_RETRIES", prefix)), default.max_retries),
+            base_delay: Duration::from_millis(parse_or(
+                var(&format!("{}_RETRY_BACKOFF_MS", prefix)),
+                default.base_delay.as_millis() as u64,
+            )),
+            max_delay: default.max_delay,
+            timeout: Duration::from_millis(parse_or(
+                var(&format!("{}_TIMEOUT_MS", prefix)),
+                default.timeout.as_millis() as u64,
+            )),
+        }
+    }
+
+    /// Delay before retry number `attempt` (zero based), doubled each time and capped at `max_delay`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+
+    /// Delay before retry number `attempt`. A `Retry-After` sent by the upstream replaces the
+    /// backoff, still capped at `max_delay`.
+    pub fn retry_delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        match retry_after {
+            Some(retry_after) => retry_after.min(self.max_delay),
+            None => self.backoff(attempt),
+        }
+    }
+
+    /// Sends the request built by `send`, retrying on timeouts, connection errors, 429 and 5xx.
+    /// A 429 or 503 waits for the upstream's `Retry-After` when it sends one.
+    #[cfg(feature = "ssr")]
+    pub async fn send_with_retry<F, Fut>(
+        &self,
+        upstream: Upstream,
+        mut send: F,
+    ) -> Result<reqwest::Response, reqwest::Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<reqwest::Response, reqwest::Error>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let response = send().await;
+            let retryable = match &response {
+                Ok(res) => {
+                    res.status().is_server_error()
+                        || res.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(e) => e.is_timeout() || e.is_connect(),
+            };
+            if !retryable || attempt >= self.max_retries {
+                return response;
+            }
+            let retry_after = match &response {
+                Ok(res)
+                    if res.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+                        || res.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE =>
+                {
+                    res.headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| parse_retry_after(v, chrono::Utc::now()))
+                }
+                _ => None,
+            };
+            let delay = self.retry_delay(attempt, retry_after);
+            log::warn!(
+                "Retrying {} request in {:?} ({}/{})",
+                upstream.name(),
+                delay,
+                attempt + 1,
+                self.max_retries
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+static HTTP_CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+
+/// The pooled client shared by every upstream call.
+/// Built on first use; `main` calls it at startup so configuration errors surface early.
+#[cfg(feature = "ssr")]
+pub fn http_client() -> &'static reqwest::Client {
+    HTTP_CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .connect_timeout(Duration::from_millis(env_or("HTTP_CONNECT_TIMEOUT_MS", 3_000)))
+            .timeout(Duration::from_millis(env_or("HTTP_TIMEOUT_MS", 30_000)))
+            .pool_idle_timeout(Duration::from_secs(90))
+            .pool_max_idle_per_host(env_or("HTTP_POOL_MAX_IDLE_PER_HOST", 16))
+            .build()
+            .expect("couldn't build the shared HTTP client")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+            timeout: Duration::from_secs(1),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(3), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_retry_policy_from_vars() {
+        let policy = RetryPolicy::from_vars("COINBASE", |key| match key {
+            "COINBASE_MAX_RETRIES" => Some("4".to_string()),
+            "COINBASE_RETRY_BACKOFF_MS" => Some("50".to_string()),
+            "COINBASE_TIMEOUT_MS" => Some("not-a-number".to_string()),
+            _ => None,
+        });
+        assert_eq!(policy.max_retries, 4);
+        assert_eq!(policy.base_delay, Duration::from_millis(50));
+        assert_eq!(policy.timeout, RetryPolicy::default().timeout);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        use chrono::TimeZone;
+
+        let now = chrono::Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        assert_eq!(parse_retry_after("3", now), Some(Duration::from_secs(3)));
+        assert_eq!(
+            parse_retry_after("Fri, 01 Mar 2024 12:00:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Fri, 01 Mar 2024 11:59:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+
+        let policy = RetryPolicy::default();
+        assert_eq!(
+            policy.retry_delay(0, Some(Duration::from_secs(2))),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            policy.retry_delay(0, Some(Duration::from_secs(3600))),
+            policy.max_delay
+        );
+        assert_eq!(policy.retry_delay(1, None), policy.backoff(1));
+    }
+}
@@ -1,12 +1,9 @@
 use leptos::*;
 
-use crate::components::common::{
-    functions::wrapper,
-    models::common_models::{
-        BlankRequest, CoinBaseSpotPriceResponse, CounterPartyResponse, CurrencyPair,
-        EstimateIVRequest, EstimateIVResponse, Quote, QuoteOptionRequest, QuoteOptionResponse,
-        UnifiedCurrencyPairConfigurationResponse,
-    },
+use crate::components::common::models::common_models::{
+    BlankRequest, CounterPartyResponse, CurrencyPair, EstimateIVRequest,
+    EstimateIVResponse, Quote, QuoteOptionRequest, QuoteOptionResponse,
+    UnifiedCurrencyPairConfigurationResponse,
 };
 
 /// Server function that gets the unified configuration for the currency pair.
@@ -16,99 +13,143 @@ pub async fn fetch_unified_configuration() -> Result<
     crate::components::common::models::common_models::UnifiedCurrencyPairConfigurationResponse,
     ServerFnError,
 > {
-    use crate::components::wrapper::get_cookie_value;
-    use crate::components::wrapper::JabraCookie;
-    use crate::components::wrapper::{call_and_parse, HttpMethod};
+    use crate::components::common::functions::wrapper::JabraSession;
 
-    let cookie = get_cookie_value("jabra-admin-portal-v2").await;
-    let jwt_cookie = JabraCookie::decrypt(cookie.unwrap()).unwrap_or_default();
-    let bearer = format!("Bearer {}", jwt_cookie.access_token);
-    let url = std::env::var("DIRECTUSURL").unwrap();
-    let path = format!("{}/items/supported_pair?filter[is_active][_eq]=true&sort=id&fields=id,name,is_active,base.id,base.ticker,base.name,base.instrument_option.ccy_id,base.instrument_option.contract_multiplier,base.instrument_option.min_price_increment,base.instrument_option.min_contract_increment,quote.id,quote.ticker,quote.name,quote.instrument_option.ccy_id,quote.instrument_option.contract_multiplier,quote.instrument_option.min_price_increment,quote.instrument_option.min_contract_increment", url);
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        reqwest::header::HeaderValue::from_str(&bearer).unwrap(),
-    );
-
-    let response = call_and_parse::<BlankRequest, UnifiedCurrencyPairConfigurationResponse>(
-        Option::None,
-        path,
-        headers,
-        HttpMethod::GET,
-    )
-    .await;
-    match response {
+    let session = JabraSession::extract().await?;
+    match unified_configuration(&session).await {
         Ok(res) => Ok(res),
         Err(e) => {
-            log::error!("error: {:?}", e);
-            Err(ServerFnError::new(e.to_string()))
+            tracing::error!(server_fn = "fetch_unified_configuration", error = %e, "server function failed");
+            Err(e.into())
         }
     }
 }
 
-/// Server function that gets the coin base spot price.
-/// Accepts a [`CurrencyPair`].
+/// The active supported pairs as the role of `session` sees them, served from the reference
+/// data cache. Shared with handlers outside of server functions, e.g. the spot stream.
+#[cfg(feature = "ssr")]
+pub async fn unified_configuration(
+    session: &crate::components::common::functions::wrapper::JabraSession,
+) -> Result<UnifiedCurrencyPairConfigurationResponse, crate::errors::JabraError> {
+    use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod};
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::cache::{reference_ttl, TtlCache};
+    use std::sync::OnceLock;
+
+    static CACHE: OnceLock<TtlCache<UnifiedCurrencyPairConfigurationResponse>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| TtlCache::new("supported_pair"));
+
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let path = format!("{}/items/supported_pair?filter[is_active][_eq]=true&sort=id&fields=id,name,is_active,base.id,base.ticker,base.name,base.instrument_option.ccy_id,base.instrument_option.contract_multiplier,base.instrument_option.min_price_increment,base.instrument_option.min_contract_increment,quote.id,quote.ticker,quote.name,quote.instrument_option.ccy_id,quote.instrument_option.contract_multiplier,quote.instrument_option.min_price_increment,quote.instrument_option.min_contract_increment", url);
+    let headers = session.auth_headers();
+
+    cache
+        .get_or_fetch(session.role(), reference_ttl(), || {
+            call_and_parse::<BlankRequest, UnifiedCurrencyPairConfigurationResponse>(
+                Option::None,
+                path,
+                headers,
+                HttpMethod::GET,
+                Upstream::Directus,
+            )
+        })
+        .await
+}
+
+/// Server function that gets the tick and lot sizes of every currency for spot, future, perp and option.
+/// Fails when a tick or lot size can't be rounded onto, rather than quoting with the option specs.
 
 #[server]
-pub async fn coin_base_spot(
-    currency_pair: CurrencyPair,
-) -> Result<CoinBaseSpotPriceResponse, ServerFnError> {
+pub async fn fetch_instrument_specs() -> Result<
+    crate::components::common::functions::precision::InstrumentSpecsResponse,
+    ServerFnError,
+> {
+    use crate::components::common::functions::wrapper::JabraSession;
     use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod};
-    let url = std::env::var("COINBASE_V2").unwrap();
-    let coinbase_name = currency_pair.coinbase_name();
-    if coinbase_name == "-" {
-        return Ok(CoinBaseSpotPriceResponse::default());
-    }
-    let path = format!("{}/prices/{}/spot", url, coinbase_name);
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::cache::{reference_ttl, TtlCache};
+    use crate::components::common::functions::precision::{InstrumentSpecsResponse, InstrumentType};
+    use crate::errors::JabraError;
+    use std::sync::OnceLock;
 
-    let response = call_and_parse::<BlankRequest, CoinBaseSpotPriceResponse>(
-        Option::None,
-        path,
-        reqwest::header::HeaderMap::default(),
-        HttpMethod::GET,
-    )
-    .await;
+    static CACHE: OnceLock<TtlCache<InstrumentSpecsResponse>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| TtlCache::new("instrument_specs"));
+
+    let session = JabraSession::extract().await?;
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let instrument_types = InstrumentType::ALL.map(|t| t.as_str()).join(",");
+    let path = format!("{}/items/instrument_specs?filter[instrument_type][_in]={}&fields=instrument_type,contract_multiplier,min_price_increment,min_contract_increment,ccy_id.ticker", url, instrument_types);
+    let headers = session.auth_headers();
+
+    // Checked before caching, so fixing the specs in Directus takes effect on the next call
+    let response = cache
+        .get_or_fetch(session.role(), reference_ttl(), || async move {
+            let res = call_and_parse::<BlankRequest, InstrumentSpecsResponse>(
+                Option::None,
+                path,
+                headers,
+                HttpMethod::GET,
+                Upstream::Directus,
+            )
+            .await?;
+            for specs in &res.data {
+                specs.validate().map_err(|e| JabraError::Validation {
+                    message: format!(
+                        "The {} specs of {} are invalid: {}",
+                        specs.instrument_type, specs.ccy_id.ticker, e
+                    ),
+                    fields: vec![],
+                })?;
+            }
+            Ok::<_, JabraError>(res)
+        })
+        .await;
     match response {
         Ok(res) => Ok(res),
         Err(e) => {
-            log::info!("error: {:?}", e);
-            Err(ServerFnError::new(e.to_string()))
+            tracing::error!(server_fn = "fetch_instrument_specs", error = %e, "server function failed");
+            Err(e.into())
         }
     }
 }
 
-/// Server function that gets counterparties.
+/// Server function that gets the active counterparties, the ones the builders can quote.
 
 #[server]
 pub async fn sb_counter_parties() -> Result<CounterPartyResponse, ServerFnError> {
-    use crate::components::common::functions::wrapper::get_cookie_value;
-    use crate::components::common::functions::wrapper::JabraCookie;
+    use crate::components::common::functions::wrapper::JabraSession;
     use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod};
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::cache::{reference_ttl, TtlCache};
+    use std::sync::OnceLock;
+
+    static CACHE: OnceLock<TtlCache<CounterPartyResponse>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| TtlCache::new("counterparty"));
 
-    let cookie = get_cookie_value("jabra-admin-portal-v2").await;
-    let jwt_cookie = JabraCookie::decrypt(cookie.unwrap()).unwrap_or_default();
-    let bearer = format!("Bearer {}", jwt_cookie.access_token);
+    let session = JabraSession::extract().await?;
     let url = std::env::var("DIRECTUSURL").unwrap();
     let path = format!("{}/items/counterparty", url);
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        reqwest::header::HeaderValue::from_str(&bearer).unwrap(),
-    );
-
-    let response = call_and_parse::<BlankRequest, CounterPartyResponse>(
-        Option::None,
-        path,
-        headers,
-        HttpMethod::GET,
-    )
-    .await;
+    let headers = session.auth_headers();
+
+    let response = cache
+        .get_or_fetch(session.role(), reference_ttl(), || {
+            call_and_parse::<BlankRequest, CounterPartyResponse>(
+                Option::None,
+                path,
+                headers,
+                HttpMethod::GET,
+                Upstream::Directus,
+            )
+        })
+        .await;
     match response {
-        Ok(res) => Ok(res),
+        Ok(mut res) => {
+            res.data.retain(|cp| cp.is_active);
+            Ok(res)
+        }
         Err(e) => {
-            log::error!("error6: {:?}", e);
-            Err(ServerFnError::new(e.to_string()))
+            tracing::error!(server_fn = "sb_counter_parties", error = %e, "server function failed");
+            Err(e.into())
         }
     }
 }
@@ -120,38 +161,33 @@ pub async fn sb_counter_parties() -> Result<CounterPartyResponse, ServerFnError>
 pub async fn sb_fetch_estimate_iv(
     request: EstimateIVRequest,
 ) -> Result<EstimateIVResponse, ServerFnError> {
-    use crate::components::common::functions::wrapper::get_cookie_value;
-    use crate::components::common::functions::wrapper::JabraCookie;
+    use crate::components::common::functions::wrapper::JabraSession;
     use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod};
+    use crate::components::common::functions::upstream::Upstream;
     // use gloo_timers::future::TimeoutFuture;
     // TimeoutFuture::new(1000).await; //Try delaying
     log::info!("Estimate IVz request: {:?}", request);
     if request.strike == 0.0 {
         return Ok(EstimateIVResponse::default());
     }
-    let cookie = get_cookie_value("jabra-admin-portal-v2").await;
-    let jwt_cookie = JabraCookie::decrypt(cookie.unwrap()).unwrap_or_default();
-    let bearer = format!("Bearer {}", jwt_cookie.access_token);
+    let session = JabraSession::extract().await?;
     let url = std::env::var("JABRAAPIGATEWAY").unwrap();
     let path = format!("{}/option_pricer/estimate_iv", url);
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        reqwest::header::HeaderValue::from_str(&bearer).unwrap(),
-    );
+    let headers = session.auth_headers();
 
     let response = call_and_parse::<EstimateIVRequest, EstimateIVResponse>(
         Some(request),
         path,
         headers,
         HttpMethod::POST,
+        Upstream::PricingGateway,
     )
     .await;
     match response {
         Ok(res) => Ok(res),
         Err(e) => {
-            log::error!("error4: {:?}", e);
-            Err(ServerFnError::new(e.to_string()))
+            tracing::error!(server_fn = "sb_fetch_estimate_iv", error = %e, "server function failed");
+            Err(e.into())
         }
     }
 }
@@ -163,52 +199,30 @@ pub async fn sb_fetch_estimate_iv(
 pub async fn sb_post_qoute_option(
     request: QuoteOptionRequest,
 ) -> Result<QuoteOptionResponse, ServerFnError> {
-    use crate::components::common::functions::wrapper::JabraCookie;
-    use crate::components::common::functions::wrapper::{
-        call_and_parse, get_cookie_value, HttpMethod,
-    };
-
-    let cookie = get_cookie_value("jabra-admin-portal-v2").await;
-    let jwt_cookie = JabraCookie::decrypt(cookie.unwrap()).unwrap_or_default();
-    let mut bearer = format!("Bearer {}", jwt_cookie.access_token);
-
-    // Check if token expires, this checking will be available only to actions and server action
-    // Other resources will still work due to 10 minutes buffer time
-    if jwt_cookie.is_expired() {
-        let refresh = wrapper::refresh_token(jwt_cookie.user_id, jwt_cookie.refresh_token).await;
-        match refresh {
-            Ok(r) => {
-                bearer = format!("Bearer {}", r.access_token);
-                wrapper::set_jabra_cookie(Some(r), "jabra-admin-portal-v2".to_string()).await;
-            }
-            Err(e) => {
-                // directus_wrapper::set_jabra_cookie(cx, None, "JabraOPv1_2023".to_string()).await;
-                return Err(ServerFnError::new(e.to_string()));
-            }
-        }
-    }
+    use crate::components::common::functions::wrapper::JabraSession;
+    use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod};
+    use crate::components::common::functions::upstream::Upstream;
+
+    let session = JabraSession::extract().await?;
 
     let url = std::env::var("JABRAAPIGATEWAY").unwrap();
     let path = format!("{}/option_pricer/quote_option", url);
 
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        reqwest::header::HeaderValue::from_str(&bearer).unwrap(),
-    );
+    let headers = session.auth_headers();
 
     let response = call_and_parse::<QuoteOptionRequest, QuoteOptionResponse>(
         Some(request),
         path,
         headers,
         HttpMethod::POST,
+        Upstream::PricingGateway,
     )
     .await;
     match response {
         Ok(res) => Ok(res),
         Err(e) => {
-            log::error!("error: {:?}", e);
-            Err(ServerFnError::new(e.to_string()))
+            tracing::error!(server_fn = "sb_post_qoute_option", error = %e, "server function failed");
+            Err(e.into())
         }
     }
 }
@@ -218,52 +232,86 @@ pub async fn sb_post_qoute_option(
 
 #[server(AddQuote, "/api")]
 pub async fn add_quote(request: Vec<Quote>) -> Result<bool, ServerFnError> {
-    use crate::components::common::functions::wrapper::JabraCookie;
-    use crate::components::common::functions::wrapper::{call, get_cookie_value, HttpMethod};
-
-    let cookie = get_cookie_value("jabra-admin-portal-v2").await;
-    let jwt_cookie = JabraCookie::decrypt(cookie.unwrap()).unwrap_or_default();
-    let mut bearer = format!("Bearer {}", jwt_cookie.access_token);
-
-    // Check if token expires, this checking will be available only to actions and server action
-    // Other resources will still work due to 10 minutes buffer time
-    if jwt_cookie.is_expired() {
-        let refresh = wrapper::refresh_token(jwt_cookie.user_id, jwt_cookie.refresh_token).await;
-        match refresh {
-            Ok(r) => {
-                bearer = format!("Bearer {}", r.access_token);
-                wrapper::set_jabra_cookie(Some(r), "jabra-admin-portal-v2".to_string()).await;
-            }
-            Err(e) => {
-                return Err(ServerFnError::new(e.to_string()));
-            }
-        }
-    }
+    use crate::components::common::functions::wrapper::JabraSession;
+    use crate::components::common::models::permission_model::Permission;
+    use crate::components::common::functions::wrapper::{call, client_ip, HttpMethod};
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::audit;
+    use crate::components::common::models::audit_model::{AuditAction, AuditEntry};
+
+    let session = JabraSession::extract().await?;
+    session.require(Permission::CreateQuote)?;
 
     let url = std::env::var("DIRECTUSURL").unwrap();
     let path = format!("{}/items/quotes_option", url);
 
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        reqwest::header::HeaderValue::from_str(&bearer).unwrap(),
-    );
+    let headers = session.auth_headers();
     // log::info!("request: {:?}", request);
+    let audit_entries = request
+        .iter()
+        .map(|quote| {
+            AuditEntry::new(
+                session.cookie.user_id.clone(),
+                AuditAction::CreateQuote,
+                client_ip(),
+            )
+            .group_id(Some(quote.group_id.clone()))
+            .after(serde_json::to_value(quote).ok())
+        })
+        .collect::<Vec<AuditEntry>>();
+    let quote_count = request.len();
     let response = call::<Vec<crate::components::common::models::common_models::Quote>>(
         Some(request),
         path,
         headers,
         HttpMethod::POST,
+        Upstream::Directus,
+    )
+    .await;
+    let error = response.as_ref().err().map(|e| e.to_string());
+    audit::record(
+        audit_entries
+            .into_iter()
+            .map(|entry| entry.error(error.clone()))
+            .collect(),
     )
     .await;
     match response {
-        Ok(res) => Ok(res),
+        Ok(res) => {
+            crate::metrics::record_quotes(crate::metrics::QuoteEvent::Submitted, quote_count);
+            Ok(res)
+        }
         Err(e) => {
-            log::error!("error: {:?}", e);
-            Err(ServerFnError::new(e.to_string()))
+            tracing::error!(server_fn = "add_quote", error = %e, "server function failed");
+            Err(e.into())
         }
     }
     // Err(ServerFnError::ServerError("Transaction failed".to_string()))
     // log::info!("request: {:?}", request);
     // Ok(false)
 }
+
+/// Server function that drops the cached reference data (pairs, currencies and counterparties),
+/// e.g. right after they were changed in Directus. Admins only.
+
+#[server(InvalidateReferenceCache, "/api")]
+pub async fn invalidate_reference_cache() -> Result<bool, ServerFnError> {
+    use crate::components::common::functions::audit;
+    use crate::components::common::functions::cache::invalidate_all;
+    use crate::components::common::functions::wrapper::{client_ip, JabraSession};
+    use crate::components::common::models::audit_model::{AuditAction, AuditEntry};
+    use crate::components::common::models::permission_model::Permission;
+
+    let session = JabraSession::extract().await?;
+    session.require(Permission::Admin)?;
+
+    invalidate_all();
+    log::info!("Reference cache invalidated by {}", session.cookie.user_id);
+    audit::record(vec![AuditEntry::new(
+        session.cookie.user_id.clone(),
+        AuditAction::InvalidateCache,
+        client_ip(),
+    )])
+    .await;
+    Ok(true)
+}
@@ -1,74 +1,404 @@
+#[cfg(feature = "ssr")]
+use super::upstream::Upstream;
+use crate::components::common::models::permission_model::Permission;
 use crate::errors::JabraError;
 use leptos::*;
 use serde::{Deserialize, Serialize};
 
+/// How long before the Directus access token expires the session counts as expired and is refreshed.
+/// Leaves room for requests that are in flight when the refresh is due.
+pub const TOKEN_REFRESH_MARGIN_MS: i64 = 60_000;
+
+/// Name of the encrypted session cookie issued at login.
+pub const SESSION_COOKIE_NAME: &str = "jabra-admin-portal-v2";
+
+/// Session cookie name used by the previous portal.
+/// It is still accepted on read during the rollout and is re-issued under [`SESSION_COOKIE_NAME`].
+pub const LEGACY_SESSION_COOKIE_NAME: &str = "JabraOPv1_2023";
+
+/// Returns the value of `cookie_name` from a raw `Cookie` request header.
+pub fn find_cookie(cookie_header: &str, cookie_name: &str) -> Option<String> {
+    cookie_header
+        .split(';')
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, value)| *name == cookie_name && !value.is_empty())
+        .map(|(_, value)| value.to_string())
+}
+
+/// The raw `Cookie` header of the request being served, empty when it has none.
 #[cfg(feature = "ssr")]
-pub async fn get_cookie_value(cookie_name: &str) -> Result<String, crate::errors::JabraError> {
-    use axum::http::HeaderName;
-    use std::str::FromStr;
-    let cookie_value = use_context::<http::request::Parts>()
+pub fn request_cookie_header() -> String {
+    use_context::<http::request::Parts>()
         .and_then(|req| {
             req.headers
-                .get(HeaderName::from_str("cookie").unwrap())
-                .and_then(|v| match v.to_str().ok() {
-                    Some(r) => Some(r.to_string()),
-                    None => None,
-                })
+                .get(http::header::COOKIE)
+                .and_then(|v| v.to_str().ok().map(|r| r.to_string()))
         })
-        .unwrap_or("".to_string());
-    let cookies: Vec<&str> = cookie_value.split("; ").collect();
-
-    let mut cookie_value = String::new();
-    for cookie in cookies {
-        let parts: Vec<&str> = cookie.splitn(2, '=').collect();
-        if parts.len() == 2 && parts[0] == cookie_name {
-            cookie_value = parts[1].to_string();
+        .unwrap_or_default()
+}
+
+/// Parses `TRUSTED_PROXIES`, a comma separated list of proxy addresses.
+/// Entries that are not IP addresses are logged and skipped.
+pub fn parse_trusted_proxies(value: &str) -> Vec<std::net::IpAddr> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| match entry.parse() {
+            Ok(ip) => Some(ip),
+            Err(_) => {
+                log::warn!("Ignoring invalid TRUSTED_PROXIES entry: {}", entry);
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(feature = "ssr")]
+fn trusted_proxies() -> &'static [std::net::IpAddr] {
+    static TRUSTED_PROXIES: std::sync::OnceLock<Vec<std::net::IpAddr>> = std::sync::OnceLock::new();
+    TRUSTED_PROXIES.get_or_init(|| {
+        parse_trusted_proxies(&std::env::var("TRUSTED_PROXIES").unwrap_or_default())
+    })
+}
+
+/// Address of the client as seen from `peer`, the socket address of the connection.
+/// `X-Forwarded-For` and `X-Real-IP` are client controlled, so they are only read when `peer`
+/// is a trusted proxy. `X-Forwarded-For` is walked from the right and the first address that is
+/// not a trusted proxy wins, which skips whatever the client put in front of it.
+pub fn resolve_client_ip(
+    peer: Option<std::net::IpAddr>,
+    forwarded_for: Option<&str>,
+    real_ip: Option<&str>,
+    trusted: &[std::net::IpAddr],
+) -> Option<std::net::IpAddr> {
+    let peer = peer?;
+    if !trusted.contains(&peer) {
+        return Some(peer);
+    }
+    if let Some(forwarded_for) = forwarded_for {
+        // Stop at the first hop that does not parse, everything left of it is untrusted anyway
+        let client = forwarded_for
+            .rsplit(',')
+            .map(|hop| hop.trim().parse::<std::net::IpAddr>())
+            .map_while(Result::ok)
+            .find(|ip| !trusted.contains(ip));
+        if client.is_some() {
+            return client;
         }
     }
+    real_ip.and_then(|ip| ip.trim().parse().ok()).or(Some(peer))
+}
 
-    match cookie_value {
-        value if value.len() > 0 => Ok(value),
-        _ => Err(JabraError::CookieFetchError),
-    }
+/// Address of the client, see [`resolve_client_ip`].
+/// The socket address comes from `ConnectInfo`, so the server has to be started with
+/// `into_make_service_with_connect_info`.
+#[cfg(feature = "ssr")]
+pub fn client_ip() -> String {
+    use axum::extract::ConnectInfo;
+    use std::net::SocketAddr;
+
+    use_context::<http::request::Parts>()
+        .and_then(|req| {
+            let header = |name: &str| req.headers.get(name).and_then(|v| v.to_str().ok());
+            let peer = req
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip());
+            resolve_client_ip(
+                peer,
+                header("x-forwarded-for"),
+                header("x-real-ip"),
+                trusted_proxies(),
+            )
+        })
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
 }
 
-#[server]
-pub async fn check_server_cookie() -> Result<bool, ServerFnError> {
-    let cookie_value = get_cookie_value("jabra-admin-portal-v2").await;
-    match cookie_value {
-        Ok(val) => {
-            if val.len() > 0 {
-                Ok(true)
-            } else {
-                Ok(false)
+/// Id of the current request, assigned by [`request_id_layer`](crate::telemetry::request_id_layer).
+#[cfg(feature = "ssr")]
+pub fn request_id() -> String {
+    use_context::<http::request::Parts>()
+        .and_then(|req| {
+            req.headers
+                .get(crate::telemetry::REQUEST_ID_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        })
+        .unwrap_or_else(|| "-".to_string())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_cookie_value(cookie_name: &str) -> Result<String, crate::errors::JabraError> {
+    find_cookie(&request_cookie_header(), cookie_name).ok_or(JabraError::CookieFetchError)
+}
+
+/// The session of the current request, decrypted from the session cookie.
+/// Every server function that talks to an upstream on behalf of the user goes through [`JabraSession::extract`].
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JabraSession {
+    pub cookie: JabraCookie,
+    /// Set when the session was read from a cookie of the previous portal and must be re-sealed.
+    pub from_legacy_cookie: bool,
+}
+
+impl JabraSession {
+    /// Reads the session from a raw `Cookie` header with the configured session keys.
+    #[cfg(feature = "ssr")]
+    pub fn from_cookie_header(cookie_header: &str) -> Result<Self, JabraError> {
+        Self::from_cookie_header_with(
+            cookie_header,
+            super::seal::session_keys(),
+            CookieOptions::from_env().max_age_secs,
+            chrono::Utc::now().timestamp_millis(),
+        )
+    }
+
+    /// Reads the session from a raw `Cookie` header, opened with `keys` at `now` (unix ms).
+    /// The current cookie wins over the legacy one when both are present.
+    ///
+    /// During the rollout, cookies the previous portal encrypted with magic-crypt are still
+    /// accepted under either name. They carry no expiry of their own, so one whose access token
+    /// was due for a refresh more than `max_age_secs` ago is rejected. Once a session lifetime has
+    /// passed since the rollout, none of them can be opened anymore and the fallback can go.
+    /// A cookie that opens but carries no tokens is rejected like a missing one.
+    #[cfg(feature = "ssr")]
+    pub fn from_cookie_header_with(
+        cookie_header: &str,
+        keys: &super::seal::SessionKeys,
+        max_age_secs: i64,
+        now: i64,
+    ) -> Result<Self, JabraError> {
+        let (value, from_legacy_name) = match find_cookie(cookie_header, SESSION_COOKIE_NAME) {
+            Some(value) => (value, false),
+            None => match find_cookie(cookie_header, LEGACY_SESSION_COOKIE_NAME) {
+                Some(value) => (value, true),
+                None => return Err(JabraError::CookieFetchError),
+            },
+        };
+        let (cookie, from_legacy_format) = match keys.open(&value, now / 1000) {
+            Ok(plain_text) => (JabraCookie::from_string(plain_text)?, false),
+            Err(_) => {
+                let cookie = JabraCookie::from_string(keys.open_legacy(&value)?)?;
+                if now - cookie.expires_in > max_age_secs * 1000 {
+                    log::info!("CookieFetchError: legacy session cookie outlived the session");
+                    return Err(JabraError::CookieFetchError);
+                }
+                (cookie, true)
             }
+        };
+        if cookie.access_token.is_empty() || cookie.refresh_token.is_empty() {
+            return Err(JabraError::CookieFetchError);
         }
-        // Err(e) => {
-        //     Err(ServerFnError::ServerError(e.to_string()))
-        // }
-        _ => Ok(false),
+        Ok(Self {
+            cookie,
+            from_legacy_cookie: from_legacy_name || from_legacy_format,
+        })
+    }
+
+    /// Extracts the session of the request being served, refreshing the access token on demand
+    /// once it is within [`TOKEN_REFRESH_MARGIN_MS`] of expiring.
+    /// A session read from a legacy cookie is re-sealed under [`SESSION_COOKIE_NAME`].
+    #[cfg(feature = "ssr")]
+    pub async fn extract() -> Result<Self, JabraError> {
+        let mut session = Self::from_cookie_header(&request_cookie_header())?;
+        if session.cookie.is_expired() {
+            session.refresh().await?;
+        } else if session.from_legacy_cookie {
+            set_jabra_cookie(
+                Some(session.cookie.clone()),
+                SESSION_COOKIE_NAME.to_string(),
+            )
+            .await;
+            session.from_legacy_cookie = false;
+        }
+        Ok(session)
+    }
+
+    /// Exchanges the refresh token for a new access token and re-issues the session cookie.
+    /// Directus refresh tokens are single use, so concurrent requests carrying the same token
+    /// share the result of the first refresh instead of racing each other. Each token has its own
+    /// lock, so refreshes of different sessions never wait on one another.
+    #[cfg(feature = "ssr")]
+    pub async fn refresh(&mut self) -> Result<(), JabraError> {
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex, OnceLock};
+
+        type RefreshSlot = Arc<tokio::sync::Mutex<Option<(JabraCookie, i64)>>>;
+        static REFRESHES: OnceLock<Mutex<HashMap<String, RefreshSlot>>> = OnceLock::new();
+
+        let slot = {
+            let mut refreshes = REFRESHES
+                .get_or_init(Default::default)
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            let now = chrono::Utc::now().timestamp_millis();
+            // Keep slots that are in use, and results that are still recent enough to share
+            refreshes.retain(|_, slot| {
+                Arc::strong_count(slot) > 1
+                    || slot.try_lock().is_ok_and(|done| {
+                        done.as_ref()
+                            .is_some_and(|(_, at)| now - *at < TOKEN_REFRESH_MARGIN_MS)
+                    })
+            });
+            refreshes
+                .entry(self.cookie.refresh_token.clone())
+                .or_default()
+                .clone()
+        };
+
+        let mut done = slot.lock().await;
+        let cookie = match done.as_ref() {
+            Some((cookie, _)) => cookie.clone(),
+            None => {
+                let cookie = refresh_token(
+                    self.cookie.user_id.clone(),
+                    self.cookie.refresh_token.clone(),
+                )
+                .await;
+                crate::metrics::record_token_refresh(cookie.is_ok());
+                let cookie = cookie?
+                    .with_permissions(self.cookie.permissions.clone())
+                    .with_role(self.cookie.role_id.clone());
+                *done = Some((cookie.clone(), chrono::Utc::now().timestamp_millis()));
+                cookie
+            }
+        };
+        drop(done);
+
+        set_jabra_cookie(Some(cookie.clone()), SESSION_COOKIE_NAME.to_string()).await;
+        self.cookie = cookie;
+        self.from_legacy_cookie = false;
+        Ok(())
+    }
+
+    /// Fails with [`JabraError::Forbidden`] unless the session carries `permission`.
+    pub fn require(&self, permission: Permission) -> Result<(), JabraError> {
+        match self.cookie.permissions.contains(&permission) {
+            true => Ok(()),
+            false => Err(JabraError::Forbidden(format!(
+                "{} lacks {:?}",
+                self.cookie.user_id, permission
+            ))),
+        }
+    }
+
+    /// Directus role the session reads reference data as, the key of the reference data caches.
+    pub fn role(&self) -> Option<&str> {
+        self.cookie.role_id.as_deref()
+    }
+
+    pub fn bearer(&self) -> String {
+        format!("Bearer {}", self.cookie.access_token)
+    }
+
+    /// Headers carrying the session's bearer token.
+    #[cfg(feature = "ssr")]
+    pub fn auth_headers(&self) -> reqwest::header::HeaderMap {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            reqwest::header::HeaderValue::from_str(&self.bearer()).unwrap(),
+        );
+        headers
     }
 }
 
+/// Keeps the session alive. Refreshes the token when it is due and returns how many
+/// milliseconds the client can wait before calling again.
+
+#[server(KeepSessionAlive, "/api")]
+pub async fn keep_session_alive() -> Result<i64, ServerFnError> {
+    let session = JabraSession::extract().await?;
+    Ok(session.cookie.refresh_due_in())
+}
+
+/// Returns true when the request carries a session that decrypts to a usable [`JabraCookie`].
+
+#[server]
+pub async fn check_server_cookie() -> Result<bool, ServerFnError> {
+    Ok(JabraSession::extract().await.is_ok())
+}
+
+#[derive(Debug)]
 pub enum HttpMethod {
     POST,
     GET,
     PATCH,
+    PUT,
+    DELETE,
 }
-///Return Serialized response body
+
+/// Sends the request inside an `upstream` span that records the upstream, path, status and latency.
+/// The request id is forwarded so the call can be found in the upstream's logs as well.
 #[cfg(feature = "ssr")]
-pub async fn call_and_parse<Request, Response>(
+async fn send<Request>(
+    request: Option<Request>,
+    url: String,
+    mut headers: reqwest::header::HeaderMap,
+    method: HttpMethod,
+    upstream: Upstream,
+) -> Result<reqwest::Response, reqwest::Error>
+where
+    Request: serde::Serialize,
+{
+    use tracing::Instrument;
+
+    let request_id = request_id();
+    if let Ok(value) = reqwest::header::HeaderValue::from_str(&request_id) {
+        headers.insert(crate::telemetry::REQUEST_ID_HEADER, value);
+    }
+    let span = tracing::info_span!(
+        "upstream",
+        request_id = %request_id,
+        upstream = upstream.name(),
+        method = ?method,
+        path = crate::telemetry::url_path(&url),
+        status = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+    );
+    let start = std::time::Instant::now();
+    let response = dispatch(request, url, headers, method, upstream)
+        .instrument(span.clone())
+        .await;
+
+    span.record("latency_ms", start.elapsed().as_millis() as u64);
+    match &response {
+        Ok(res) => {
+            span.record("status", res.status().as_u16());
+            crate::metrics::record_upstream(upstream, res.status().is_success());
+            if res.status().is_success() {
+                tracing::info!(parent: &span, "upstream call finished");
+            } else {
+                tracing::warn!(parent: &span, "upstream call failed");
+            }
+        }
+        Err(e) => {
+            crate::metrics::record_upstream(upstream, false);
+            tracing::error!(parent: &span, error = %e, "upstream call failed");
+        }
+    }
+    response
+}
+
+/// Sends the request through the shared client.
+/// GET requests are idempotent and are retried according to the upstream's [`RetryPolicy`](super::upstream::RetryPolicy).
+#[cfg(feature = "ssr")]
+async fn dispatch<Request>(
     request: Option<Request>,
     url: String,
     headers: reqwest::header::HeaderMap,
     method: HttpMethod,
-) -> Result<Response, crate::errors::JabraError>
+    upstream: Upstream,
+) -> Result<reqwest::Response, reqwest::Error>
 where
     Request: serde::Serialize,
-    Response: Serializable,
 {
-    let client = reqwest::Client::new();
-    let response = match method {
+    let client = super::upstream::http_client();
+    let policy = upstream.retry_policy();
+    match method {
         HttpMethod::GET => {
             let path = match request {
                 Some(req) => {
@@ -80,12 +410,21 @@ where
                 }
                 None => url,
             };
-            client.get(&path).headers(headers).send().await
+            policy
+                .send_with_retry(upstream, || {
+                    client
+                        .get(&path)
+                        .headers(headers.clone())
+                        .timeout(policy.timeout)
+                        .send()
+                })
+                .await
         }
         HttpMethod::POST => {
             client
                 .post(url)
                 .headers(headers)
+                .timeout(policy.timeout)
                 .json(&request.unwrap())
                 .send()
                 .await
@@ -94,97 +433,102 @@ where
             client
                 .patch(url)
                 .headers(headers)
+                .timeout(policy.timeout)
+                .json(&request.unwrap())
+                .send()
+                .await
+        }
+        HttpMethod::PUT => {
+            client
+                .put(url)
+                .headers(headers)
+                .timeout(policy.timeout)
                 .json(&request.unwrap())
                 .send()
                 .await
         }
-    };
+        HttpMethod::DELETE => {
+            // Directus accepts the keys to delete as a JSON body on collection endpoints
+            let builder = client.delete(url).headers(headers).timeout(policy.timeout);
+            match request {
+                Some(req) => builder.json(&req).send().await,
+                None => builder.send().await,
+            }
+        }
+    }
+}
+
+/// Reads the body of a failed upstream response into a structured [`JabraError`].
+#[cfg(feature = "ssr")]
+async fn upstream_error(upstream: Upstream, response: reqwest::Response) -> JabraError {
+    let status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    log::warn!("{} responded with {}: {}", upstream.name(), status, body);
+    JabraError::from_upstream_response(upstream.name(), status, &body)
+}
+
+///Return Serialized response body
+#[cfg(feature = "ssr")]
+pub async fn call_and_parse<Request, Response>(
+    request: Option<Request>,
+    url: String,
+    headers: reqwest::header::HeaderMap,
+    method: HttpMethod,
+    upstream: Upstream,
+) -> Result<Response, crate::errors::JabraError>
+where
+    Request: serde::Serialize,
+    Response: Serializable,
+{
+    let response = send(request, url, headers, method, upstream).await;
     // log::info!("Response: {:?}", response);
     match response {
         Ok(res) => {
-            if res.status() == reqwest::StatusCode::OK {
+            if res.status().is_success() {
                 let response = res.text().await.map_err(|e| JabraError::from(e))?;
-                Response::de(&response).map_err(|e| JabraError::from(e))
+                // An empty body (e.g. 204 No Content) parses as `null`,
+                // so endpoints that may return nothing should use an `Option` or `()` response
+                let response = match response.trim().is_empty() {
+                    true => "null".to_string(),
+                    false => response,
+                };
+                Response::de(&response).map_err(|e| {
+                    tracing::error!(upstream = upstream.name(), error = %e, "couldn't parse upstream response");
+                    JabraError::from(e)
+                })
             } else {
-                Err(JabraError::APIResponseError(res.status().to_string()))
+                Err(upstream_error(upstream, res).await)
             }
         }
         Err(e) => Err(JabraError::from(e)),
     }
 }
 
-///Return boolean, true if httpstatus == 200, Error otherwise
+///Return boolean, true if httpstatus is 2xx, Error otherwise
 #[cfg(feature = "ssr")]
 pub async fn call<Request>(
     request: Option<Request>,
     url: String,
     headers: reqwest::header::HeaderMap,
     method: HttpMethod,
+    upstream: Upstream,
 ) -> Result<bool, crate::errors::JabraError>
 where
     Request: serde::Serialize,
 {
-    let client = reqwest::Client::new();
-    let response = match method {
-        HttpMethod::GET => {
-            let path = match request {
-                Some(req) => {
-                    let query_string = serde_urlencoded::to_string(req);
-                    match query_string {
-                        Ok(query_string) => format!("{}?{}", url, query_string),
-                        _ => url,
-                    }
-                }
-                None => url,
-            };
-            client.get(&path).headers(headers).send().await
-        }
-        HttpMethod::POST => {
-            client
-                .post(url)
-                .headers(headers)
-                .json(&request.unwrap())
-                .send()
-                .await
-        }
-        HttpMethod::PATCH => {
-            client
-                .patch(url)
-                .headers(headers)
-                .json(&request.unwrap())
-                .send()
-                .await
-        }
-    };
+    let response = send(request, url, headers, method, upstream).await;
     match response {
         Ok(res) => {
-            if res.status() == reqwest::StatusCode::OK {
+            if res.status().is_success() {
                 Ok(true)
             } else {
-                Err(JabraError::APIResponseError(res.status().to_string()))
+                Err(upstream_error(upstream, res).await)
             }
         }
         Err(e) => Err(JabraError::from(e)),
     }
 }
 
-#[cfg(feature = "ssr")]
-pub fn enc(plain_text: String) -> String {
-    use magic_crypt::{new_magic_crypt, MagicCryptTrait};
-    let encryption_key = std::env::var("JABRAKEY").unwrap();
-    let magic_crypt = new_magic_crypt!(encryption_key, 256);
-    magic_crypt.encrypt_str_to_base64(plain_text)
-}
-#[cfg(feature = "ssr")]
-pub fn dec(encrypted_text: String) -> String {
-    use magic_crypt::{new_magic_crypt, MagicCryptTrait};
-    let encryption_key = std::env::var("JABRAKEY").unwrap();
-    let magic_crypt = new_magic_crypt!(encryption_key, 256);
-    magic_crypt
-        .decrypt_base64_to_string(encrypted_text)
-        .unwrap()
-}
-
 #[cfg(feature = "ssr")]
 pub async fn refresh_token(
     owner: String,
@@ -199,13 +543,17 @@ pub async fn refresh_token(
         "refresh_token": refresh_token,
         "mode": "json"
     });
-    let client = reqwest::Client::new();
-    let response = client
+    let response = super::upstream::http_client()
         .post(&path)
+        .timeout(Upstream::Directus.retry_policy().timeout)
         .json(&json_body)
         .send()
         .await
-        .map_err(|e| JabraError::from(e))?;
+        .map_err(|e| {
+            crate::metrics::record_upstream(Upstream::Directus, false);
+            JabraError::from(e)
+        })?;
+    crate::metrics::record_upstream(Upstream::Directus, response.status().is_success());
 
     if response.status().is_success() {
         let response_body = response.text().await.map_err(|e| JabraError::from(e))?;
@@ -213,20 +561,18 @@ pub async fn refresh_token(
             DirectusLoginResponse::de(&response_body).map_err(|e| JabraError::from(e));
         match directus_login_response {
             Ok(res) => {
-                let expiration_time =
-                    chrono::Utc::now().timestamp_millis() + res.data.expires - 60_000;
                 let jabra_cookie = JabraCookie::new(
                     owner,
                     res.data.access_token,
                     res.data.refresh_token,
-                    expiration_time,
+                    JabraCookie::expiry_from(res.data.expires),
                 );
                 Ok(jabra_cookie)
             }
             Err(e) => Err(JabraError::from(e)),
         }
     } else {
-        Err(JabraError::APIResponseError(response.status().to_string()))
+        Err(upstream_error(Upstream::Directus, response).await)
     }
 }
 
@@ -236,6 +582,13 @@ pub struct JabraCookie {
     pub access_token: String,
     pub refresh_token: String,
     pub expires_in: i64,
+    // Cookies issued before permissions were tracked have none until the next login
+    #[serde(default)]
+    pub permissions: Vec<Permission>,
+    /// Directus role of the user, keys the reference data caches. Older cookies have none and
+    /// read reference data uncached until the next login.
+    #[serde(default)]
+    pub role_id: Option<String>,
 }
 
 impl JabraCookie {
@@ -250,16 +603,29 @@ impl JabraCookie {
             access_token,
             refresh_token,
             expires_in,
+            permissions: vec![],
+            role_id: None,
         }
     }
+    pub fn with_permissions(mut self, permissions: Vec<Permission>) -> Self {
+        self.permissions = permissions;
+        self
+    }
+    pub fn with_role(mut self, role_id: Option<String>) -> Self {
+        self.role_id = role_id;
+        self
+    }
+    /// Seals the cookie with the current session key. It can be opened for as long as the cookie lives.
     #[cfg(feature = "ssr")]
     pub fn encrypt(&self) -> String {
         let cookie_string = serde_json::to_string(self).unwrap();
-        super::wrapper::enc(cookie_string)
+        let expires_at = chrono::Utc::now().timestamp() + CookieOptions::from_env().max_age_secs;
+        super::seal::session_keys().seal(&cookie_string, expires_at)
     }
     #[cfg(feature = "ssr")]
     pub fn decrypt(encrypted_text: String) -> Result<Self, JabraError> {
-        let decrypted_text = super::wrapper::dec(encrypted_text);
+        let decrypted_text =
+            super::seal::session_keys().open(&encrypted_text, chrono::Utc::now().timestamp())?;
         match serde_json::from_str(&decrypted_text) {
             Ok(cookie) => Ok(cookie),
             Err(e) => {
@@ -268,6 +634,16 @@ impl JabraCookie {
             }
         }
     }
+    /// When a session whose access token lives for `expires` ms, as reported by Directus, must be refreshed.
+    pub fn expiry_from(expires: i64) -> i64 {
+        chrono::Utc::now().timestamp_millis() + expires - TOKEN_REFRESH_MARGIN_MS
+    }
+
+    /// Milliseconds until the session must be refreshed, zero once it is due.
+    pub fn refresh_due_in(&self) -> i64 {
+        (self.expires_in - chrono::Utc::now().timestamp_millis()).max(0)
+    }
+
     #[cfg(feature = "ssr")]
     pub fn is_expired(&self) -> bool {
         let now = chrono::Utc::now().timestamp_millis();
@@ -284,27 +660,581 @@ impl JabraCookie {
         }
     }
 }
+
+/// Attributes of the session cookie.
+/// `COOKIE_SECURE` can be set to `false` for plain http development, `SESSION_MAX_AGE_SECS`
+/// should follow the Directus `REFRESH_TOKEN_TTL` (7 days by default) so the cookie dies with the refresh token.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CookieOptions {
+    pub secure: bool,
+    pub max_age_secs: i64,
+}
+
+impl Default for CookieOptions {
+    fn default() -> Self {
+        Self {
+            secure: true,
+            max_age_secs: 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+impl CookieOptions {
+    pub fn from_env() -> Self {
+        let default = CookieOptions::default();
+        Self {
+            secure: std::env::var("COOKIE_SECURE")
+                .ok()
+                .and_then(|v| v.parse::<bool>().ok())
+                .unwrap_or(default.secure),
+            max_age_secs: std::env::var("SESSION_MAX_AGE_SECS")
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .unwrap_or(default.max_age_secs),
+        }
+    }
+
+    /// Builds the `Set-Cookie` value. `None` expires the cookie.
+    pub fn header(&self, cookie_name: &str, value: Option<&str>) -> String {
+        let (value, max_age) = match value {
+            Some(value) => (value, self.max_age_secs),
+            None => ("", 0),
+        };
+        let secure = if self.secure { "; Secure" } else { "" };
+        format!(
+            "{}={}; HttpOnly; Path=/; SameSite=Strict; Max-Age={}{}",
+            cookie_name, value, max_age, secure
+        )
+    }
+}
+
+/// Writes the session cookie. Passing `None` expires it immediately.
 #[cfg(feature = "ssr")]
 pub async fn set_jabra_cookie(jabra_cookie: Option<JabraCookie>, cookie_name: String) {
-    use http::{header::SET_COOKIE, HeaderMap, HeaderValue};
+    use http::{header::SET_COOKIE, HeaderValue};
     use leptos::*;
-    use leptos_axum::{ResponseOptions, ResponseParts};
+    use leptos_axum::ResponseOptions;
     let response =
         use_context::<ResponseOptions>().expect("to have leptos_axum::ResponseOptions provided");
-    let mut response_parts = ResponseParts::default();
-    let mut headers = HeaderMap::new();
-    let cookie_value = match jabra_cookie {
-        Some(cookie) => cookie.encrypt(),
-        None => "".to_string(),
-    };
-    headers.insert(
+    let value = jabra_cookie.map(|cookie| cookie.encrypt());
+    let cookie = CookieOptions::from_env().header(&cookie_name, value.as_deref());
+    response.append_header(
         SET_COOKIE,
-        HeaderValue::from_str(&format!(
-            "{}={}; HttpOnly; Path=/",
-            cookie_name, cookie_value
-        ))
-        .expect("to create a header value"),
+        HeaderValue::from_str(&cookie).expect("to create a header value"),
     );
-    response_parts.headers = headers;
-    response.overwrite(response_parts);
+}
+
+/// Expires the session cookie, including the legacy one.
+#[cfg(feature = "ssr")]
+pub async fn clear_jabra_cookie() {
+    set_jabra_cookie(None, SESSION_COOKIE_NAME.to_string()).await;
+    set_jabra_cookie(None, LEGACY_SESSION_COOKIE_NAME.to_string()).await;
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::components::common::functions::seal::SessionKeys;
+
+    const NOW_MS: i64 = 1_700_000_000_000;
+    const MAX_AGE_SECS: i64 = 7 * 24 * 60 * 60;
+
+    /// Session keys for the tests, which also open legacy cookies encrypted with the same secret.
+    fn test_keys() -> SessionKeys {
+        SessionKeys::parse("1:test-key")
+            .unwrap()
+            .with_legacy_secret("test-key")
+    }
+
+    fn seal(cookie: &JabraCookie) -> String {
+        test_keys().seal(
+            &serde_json::to_string(cookie).unwrap(),
+            NOW_MS / 1000 + 3600,
+        )
+    }
+
+    fn sealed_cookie(user_id: &str) -> String {
+        seal(&JabraCookie::new(
+            user_id.to_string(),
+            "access".to_string(),
+            "refresh".to_string(),
+            0,
+        ))
+    }
+
+    /// Reads the session from `cookie_header` with [`test_keys`] at [`NOW_MS`].
+    fn session_from(cookie_header: &str) -> Result<JabraSession, JabraError> {
+        JabraSession::from_cookie_header_with(cookie_header, &test_keys(), MAX_AGE_SECS, NOW_MS)
+    }
+
+    #[test]
+    fn test_cookie_attributes() {
+        let options = CookieOptions {
+            secure: true,
+            max_age_secs: 3600,
+        };
+        assert_eq!(
+            options.header(SESSION_COOKIE_NAME, Some("abc==")),
+            "jabra-admin-portal-v2=abc==; HttpOnly; Path=/; SameSite=Strict; Max-Age=3600; Secure"
+        );
+        let options = CookieOptions {
+            secure: false,
+            ..options
+        };
+        assert_eq!(
+            options.header(SESSION_COOKIE_NAME, None),
+            "jabra-admin-portal-v2=; HttpOnly; Path=/; SameSite=Strict; Max-Age=0"
+        );
+    }
+
+    #[test]
+    fn test_resolve_client_ip() {
+        let ip = |ip: &str| ip.parse::<std::net::IpAddr>().unwrap();
+        let trusted = parse_trusted_proxies("10.0.0.1, 10.0.0.2, not-an-ip");
+        assert_eq!(trusted, vec![ip("10.0.0.1"), ip("10.0.0.2")]);
+
+        // Headers from a client that connects directly are ignored
+        assert_eq!(
+            resolve_client_ip(
+                Some(ip("203.0.113.7")),
+                Some("1.2.3.4"),
+                Some("5.6.7.8"),
+                &trusted
+            ),
+            Some(ip("203.0.113.7"))
+        );
+        // Behind the proxies, the hop in front of the last trusted proxy is the client,
+        // whatever the client prepended
+        assert_eq!(
+            resolve_client_ip(
+                Some(ip("10.0.0.1")),
+                Some("1.2.3.4, 198.51.100.9, 10.0.0.2"),
+                None,
+                &trusted
+            ),
+            Some(ip("198.51.100.9"))
+        );
+        assert_eq!(
+            resolve_client_ip(
+                Some(ip("10.0.0.1")),
+                Some("garbage, 10.0.0.2"),
+                Some("198.51.100.9"),
+                &trusted
+            ),
+            Some(ip("198.51.100.9"))
+        );
+        assert_eq!(
+            resolve_client_ip(Some(ip("10.0.0.1")), None, None, &trusted),
+            Some(ip("10.0.0.1"))
+        );
+        assert_eq!(
+            resolve_client_ip(None, Some("1.2.3.4"), None, &trusted),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_cookie() {
+        let header = "theme=dark; jabra-admin-portal-v2=abc==; other=1";
+        assert_eq!(
+            find_cookie(header, SESSION_COOKIE_NAME),
+            Some("abc==".to_string())
+        );
+        assert_eq!(find_cookie(header, LEGACY_SESSION_COOKIE_NAME), None);
+        assert_eq!(
+            find_cookie("jabra-admin-portal-v2=", SESSION_COOKIE_NAME),
+            None
+        );
+    }
+
+    #[test]
+    fn test_session_from_current_cookie() {
+        let header = format!("{}={}", SESSION_COOKIE_NAME, sealed_cookie("trader@jabra"));
+        let session = session_from(&header).unwrap();
+        assert_eq!(session.cookie.user_id, "trader@jabra");
+        assert!(!session.from_legacy_cookie);
+    }
+
+    /// A cookie as the previous portal encrypted it, due for a refresh at `expires_in`.
+    fn legacy_cookie(user_id: &str, expires_in: i64) -> String {
+        use magic_crypt::{new_magic_crypt, MagicCryptTrait};
+
+        let cookie = JabraCookie::new(
+            user_id.to_string(),
+            "access".to_string(),
+            "refresh".to_string(),
+            expires_in,
+        );
+        new_magic_crypt!("test-key", 256)
+            .encrypt_str_to_base64(serde_json::to_string(&cookie).unwrap())
+    }
+
+    #[test]
+    fn test_session_from_legacy_cookie() {
+        let header = format!(
+            "{}={}",
+            LEGACY_SESSION_COOKIE_NAME,
+            legacy_cookie("trader@jabra", NOW_MS)
+        );
+        let session = session_from(&header).unwrap();
+        assert_eq!(session.cookie.user_id, "trader@jabra");
+        assert!(session.from_legacy_cookie);
+
+        // The previous portal also wrote magic-crypt values under the current name
+        let header = format!(
+            "{}={}",
+            SESSION_COOKIE_NAME,
+            legacy_cookie("trader@jabra", NOW_MS)
+        );
+        let session = session_from(&header).unwrap();
+        assert!(session.from_legacy_cookie);
+    }
+
+    #[test]
+    fn test_legacy_cookie_expires_with_the_session() {
+        let stale = NOW_MS - MAX_AGE_SECS * 1000 - 1;
+        let header = format!(
+            "{}={}",
+            LEGACY_SESSION_COOKIE_NAME,
+            legacy_cookie("trader@jabra", stale)
+        );
+        assert!(matches!(
+            session_from(&header),
+            Err(JabraError::CookieFetchError)
+        ));
+        // Without the legacy secret, legacy cookies can't be opened at all
+        let header = format!(
+            "{}={}",
+            LEGACY_SESSION_COOKIE_NAME,
+            legacy_cookie("trader@jabra", NOW_MS)
+        );
+        let keys = SessionKeys::parse("1:test-key").unwrap();
+        assert!(matches!(
+            JabraSession::from_cookie_header_with(&header, &keys, MAX_AGE_SECS, NOW_MS),
+            Err(JabraError::CookieFetchError)
+        ));
+    }
+
+    #[test]
+    fn test_current_cookie_wins_over_legacy() {
+        let header = format!(
+            "{}={}; {}={}",
+            LEGACY_SESSION_COOKIE_NAME,
+            sealed_cookie("old@jabra"),
+            SESSION_COOKIE_NAME,
+            sealed_cookie("new@jabra")
+        );
+        let session = session_from(&header).unwrap();
+        assert_eq!(session.cookie.user_id, "new@jabra");
+        assert!(!session.from_legacy_cookie);
+    }
+
+    #[test]
+    fn test_tampered_session_is_rejected() {
+        let sealed = sealed_cookie("user@jabra");
+        let (header, body) = sealed.rsplit_once('.').unwrap();
+        let tampered = format!("{}.{}", header, body.chars().rev().collect::<String>());
+        let header = format!("{}={}", SESSION_COOKIE_NAME, tampered);
+        assert!(matches!(
+            session_from(&header),
+            Err(JabraError::CookieFetchError)
+        ));
+        let header = format!("{}=not-a-sealed-cookie", SESSION_COOKIE_NAME);
+        assert!(matches!(
+            session_from(&header),
+            Err(JabraError::CookieFetchError)
+        ));
+    }
+
+    #[test]
+    fn test_session_without_tokens_is_rejected() {
+        let empty = seal(&JabraCookie::default());
+        let header = format!("{}={}", SESSION_COOKIE_NAME, empty);
+        assert!(session_from(&header).is_err());
+    }
+
+    #[test]
+    fn test_missing_session() {
+        assert!(matches!(
+            session_from("theme=dark"),
+            Err(JabraError::CookieFetchError)
+        ));
+    }
+
+    /// A request captured by [`mock_server`].
+    #[derive(Debug)]
+    struct RecordedRequest {
+        method: String,
+        path: String,
+        body: String,
+    }
+
+    /// Serves the given `(status, body)` responses in order, one per connection,
+    /// and returns the base url together with the requests it received.
+    async fn mock_server(
+        responses: Vec<(u16, &'static str)>,
+    ) -> (
+        String,
+        tokio::sync::mpsc::UnboundedReceiver<RecordedRequest>,
+    ) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut raw = Vec::new();
+                let mut buf = [0u8; 4096];
+                let header_end = loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    raw.extend_from_slice(&buf[..n]);
+                    if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break pos + 4;
+                    }
+                };
+                let head = String::from_utf8_lossy(&raw[..header_end]).to_string();
+                let content_length = head
+                    .lines()
+                    .find_map(|l| {
+                        l.to_ascii_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|v| v.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                while raw.len() < header_end + content_length {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    raw.extend_from_slice(&buf[..n]);
+                }
+                let mut request_line = head.lines().next().unwrap().split(' ');
+                tx.send(RecordedRequest {
+                    method: request_line.next().unwrap().to_string(),
+                    path: request_line.next().unwrap().to_string(),
+                    body: String::from_utf8_lossy(&raw[header_end..]).to_string(),
+                })
+                .unwrap();
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Item {
+        id: u32,
+    }
+
+    #[tokio::test]
+    async fn test_call_accepts_no_content() {
+        let (url, mut requests) = mock_server(vec![(204, "")]).await;
+        let result = call(
+            Some(vec![Item { id: 1 }]),
+            format!("{}/items/quotes_option", url),
+            reqwest::header::HeaderMap::new(),
+            HttpMethod::PATCH,
+            Upstream::Directus,
+        )
+        .await;
+        assert!(result.unwrap());
+        let request = requests.recv().await.unwrap();
+        assert_eq!(request.method, "PATCH");
+        assert_eq!(request.body, r#"[{"id":1}]"#);
+    }
+
+    #[tokio::test]
+    async fn test_call_and_parse_created_and_empty_bodies() {
+        let (url, _requests) = mock_server(vec![(201, r#"{"id":7}"#), (204, "")]).await;
+        let created = call_and_parse::<Item, Item>(
+            Some(Item { id: 7 }),
+            format!("{}/items/quotes_option", url),
+            reqwest::header::HeaderMap::new(),
+            HttpMethod::POST,
+            Upstream::Directus,
+        )
+        .await;
+        assert_eq!(created.unwrap(), Item { id: 7 });
+
+        let empty = call_and_parse::<Item, Option<Item>>(
+            Some(Item { id: 7 }),
+            format!("{}/items/quotes_option/7", url),
+            reqwest::header::HeaderMap::new(),
+            HttpMethod::PUT,
+            Upstream::Directus,
+        )
+        .await;
+        assert_eq!(empty.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_call_delete() {
+        let (url, mut requests) = mock_server(vec![(204, ""), (204, "")]).await;
+        let by_key = call::<()>(
+            None,
+            format!("{}/items/quotes_option/3", url),
+            reqwest::header::HeaderMap::new(),
+            HttpMethod::DELETE,
+            Upstream::Directus,
+        )
+        .await;
+        assert!(by_key.is_ok());
+        let request = requests.recv().await.unwrap();
+        assert_eq!(request.method, "DELETE");
+        assert_eq!(request.path, "/items/quotes_option/3");
+        assert_eq!(request.body, "");
+
+        let by_keys = call(
+            Some(vec![3, 4]),
+            format!("{}/items/quotes_option", url),
+            reqwest::header::HeaderMap::new(),
+            HttpMethod::DELETE,
+            Upstream::Directus,
+        )
+        .await;
+        assert!(by_keys.is_ok());
+        assert_eq!(requests.recv().await.unwrap().body, "[3,4]");
+    }
+
+    #[tokio::test]
+    async fn test_call_and_parse_retries_get_then_reports_upstream_error() {
+        let (url, mut requests) = mock_server(vec![
+            (503, "unavailable"),
+            (200, r#"{"id":1}"#),
+            (
+                403,
+                r#"{"errors":[{"message":"Forbidden","extensions":{"code":"FORBIDDEN"}}]}"#,
+            ),
+        ])
+        .await;
+        let retried = call_and_parse::<(), Item>(
+            None,
+            format!("{}/items/quotes_option/1", url),
+            reqwest::header::HeaderMap::new(),
+            HttpMethod::GET,
+            Upstream::Directus,
+        )
+        .await;
+        assert_eq!(retried.unwrap(), Item { id: 1 });
+        assert_eq!(requests.recv().await.unwrap().method, "GET");
+        assert_eq!(requests.recv().await.unwrap().method, "GET");
+
+        let forbidden = call::<Item>(
+            Some(Item { id: 1 }),
+            format!("{}/items/quotes_option", url),
+            reqwest::header::HeaderMap::new(),
+            HttpMethod::POST,
+            Upstream::Directus,
+        )
+        .await;
+        assert_eq!(
+            forbidden,
+            Err(JabraError::Forbidden("Forbidden".to_string()))
+        );
+    }
+
+    /// Serializes the tests that set process environment variables.
+    static ENV_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    /// Runs [`JabraSession::extract`] the way a server function does, with the parts of a request
+    /// carrying `cookie_header` in context. Returns the session and the cookies set on the response.
+    async fn extract_in_request(
+        cookie_header: &str,
+    ) -> (Result<JabraSession, JabraError>, Vec<String>) {
+        use leptos_axum::ResponseOptions;
+
+        let runtime = create_runtime();
+        let (parts, _) = http::Request::builder()
+            .header(http::header::COOKIE, cookie_header)
+            .body(())
+            .unwrap()
+            .into_parts();
+        let response = ResponseOptions::default();
+        provide_context(parts);
+        provide_context(response.clone());
+
+        let session = JabraSession::extract().await;
+        let set_cookies = response
+            .0
+            .read()
+            .headers
+            .get_all(http::header::SET_COOKIE)
+            .iter()
+            .map(|value| value.to_str().unwrap().to_string())
+            .collect();
+        runtime.dispose();
+        (session, set_cookies)
+    }
+
+    #[tokio::test]
+    async fn test_extract_in_server_function_context() {
+        let _env = ENV_LOCK.lock().await;
+        let (url, mut requests) = mock_server(vec![(
+            200,
+            r#"{"data":{"access_token":"new-access","expires":900000,"refresh_token":"new-refresh"}}"#,
+        )])
+        .await;
+        std::env::set_var("JABRAKEY", "test-key");
+        std::env::set_var("DIRECTUSURL", &url);
+
+        // A live session is used as is
+        let live = JabraCookie::new(
+            "trader@jabra".to_string(),
+            "access".to_string(),
+            "live-refresh".to_string(),
+            chrono::Utc::now().timestamp_millis() + 600_000,
+        );
+        let header = format!("theme=dark; {}={}", SESSION_COOKIE_NAME, live.encrypt());
+        let (session, set_cookies) = extract_in_request(&header).await;
+        assert_eq!(session.unwrap().cookie, live);
+        assert!(set_cookies.is_empty());
+
+        // A legacy session is re-sealed under the current name
+        let header = format!(
+            "{}={}",
+            LEGACY_SESSION_COOKIE_NAME,
+            legacy_cookie(
+                "trader@jabra",
+                chrono::Utc::now().timestamp_millis() + 600_000
+            )
+        );
+        let (session, set_cookies) = extract_in_request(&header).await;
+        assert!(!session.unwrap().from_legacy_cookie);
+        assert_eq!(set_cookies.len(), 1);
+        assert!(set_cookies[0].starts_with(&format!("{}=1.", SESSION_COOKIE_NAME)));
+
+        // An expired session is refreshed and re-issued before the server function sees it
+        let expired = JabraCookie::new(
+            "trader@jabra".to_string(),
+            "stale-access".to_string(),
+            "expired-refresh".to_string(),
+            0,
+        );
+        let header = format!("{}={}", SESSION_COOKIE_NAME, expired.encrypt());
+        let (session, set_cookies) = extract_in_request(&header).await;
+        let session = session.unwrap();
+        assert_eq!(session.cookie.user_id, "trader@jabra");
+        assert_eq!(session.cookie.access_token, "new-access");
+        assert_eq!(session.cookie.refresh_token, "new-refresh");
+        assert!(!session.cookie.is_expired());
+
+        let request = requests.recv().await.unwrap();
+        assert_eq!(request.path, "/auth/refresh");
+        assert!(request.body.contains("expired-refresh"));
+        assert_eq!(set_cookies.len(), 1);
+        let reissued = set_cookies[0]
+            .strip_prefix(&format!("{}=", SESSION_COOKIE_NAME))
+            .and_then(|rest| rest.split(';').next())
+            .unwrap();
+        assert_eq!(
+            JabraCookie::decrypt(reissued.to_string()).unwrap(),
+            session.cookie
+        );
+    }
 }
@@ -1,7 +1,11 @@
+pub mod audit_model;
 pub mod common_models;
 pub mod counterparty_model;
 pub mod currency_model;
 pub mod currencypair_model;
+pub mod money_model;
+pub mod permission_model;
 pub mod quote_model;
 pub mod quote_option_model;
+pub mod spot_price_model;
 pub mod user_model;
@@ -0,0 +1,221 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+/// The mutations recorded in the audit log.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    CreateQuote,
+    ApproveQuote,
+    RejectQuote,
+    EditQuote,
+    EnableTfa,
+    InvalidateCache,
+    CreateCounterparty,
+    EditCounterparty,
+    DeactivateCounterparty,
+    EditPair,
+    EditCurrency,
+}
+
+impl AuditAction {
+    pub fn all() -> Vec<AuditAction> {
+        vec![
+            AuditAction::CreateQuote,
+            AuditAction::ApproveQuote,
+            AuditAction::RejectQuote,
+            AuditAction::EditQuote,
+            AuditAction::EnableTfa,
+            AuditAction::InvalidateCache,
+            AuditAction::CreateCounterparty,
+            AuditAction::EditCounterparty,
+            AuditAction::DeactivateCounterparty,
+            AuditAction::EditPair,
+            AuditAction::EditCurrency,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::CreateQuote => "create_quote",
+            AuditAction::ApproveQuote => "approve_quote",
+            AuditAction::RejectQuote => "reject_quote",
+            AuditAction::EditQuote => "edit_quote",
+            AuditAction::EnableTfa => "enable_tfa",
+            AuditAction::InvalidateCache => "invalidate_cache",
+            AuditAction::CreateCounterparty => "create_counterparty",
+            AuditAction::EditCounterparty => "edit_counterparty",
+            AuditAction::DeactivateCounterparty => "deactivate_counterparty",
+            AuditAction::EditPair => "edit_pair",
+            AuditAction::EditCurrency => "edit_currency",
+        }
+    }
+}
+
+/// One audited change. `before` and `after` hold the affected fields as JSON,
+/// `before` is empty for creations.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub actor: String,
+    pub action: AuditAction,
+    pub group_id: Option<String>,
+    pub item_id: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub client_ip: String,
+    /// `None` when the change went through, otherwise the error that stopped it.
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(actor: String, action: AuditAction, client_ip: String) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            actor,
+            action,
+            group_id: None,
+            item_id: None,
+            before: None,
+            after: None,
+            client_ip,
+            error: None,
+        }
+    }
+
+    pub fn group_id(mut self, group_id: Option<String>) -> Self {
+        self.group_id = group_id;
+        self
+    }
+
+    pub fn item_id(mut self, item_id: Option<String>) -> Self {
+        self.item_id = item_id;
+        self
+    }
+
+    pub fn before(mut self, before: Option<serde_json::Value>) -> Self {
+        self.before = before;
+        self
+    }
+
+    pub fn after(mut self, after: Option<serde_json::Value>) -> Self {
+        self.after = after;
+        self
+    }
+
+    pub fn error(mut self, error: Option<String>) -> Self {
+        self.error = error;
+        self
+    }
+}
+
+/// Filters of the audit log viewer. Empty fields match everything.
+/// `actor` matches any part of the address ignoring case, like `_icontains` in Directus.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct AuditFilter {
+    pub actor: String,
+    pub action: String,
+    pub group_id: String,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        (self.actor.is_empty()
+            || entry
+                .actor
+                .to_lowercase()
+                .contains(&self.actor.trim().to_lowercase()))
+            && (self.action.is_empty() || entry.action.as_str() == self.action)
+            && (self.group_id.is_empty() || entry.group_id.as_deref() == Some(self.group_id.trim()))
+    }
+}
+
+/// This is the response struct for the Directus `audit_log` collection.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogResponse {
+    pub data: Vec<AuditEntry>,
+}
+
+/// Server function that returns the newest audit entries matching `filter`. Admins only.
+
+#[server(GetAuditLog, "/api")]
+pub async fn get_audit_log(
+    filter: AuditFilter,
+    limit: usize,
+) -> Result<Vec<AuditEntry>, ServerFnError> {
+    use crate::components::common::functions::audit;
+    use crate::components::common::functions::wrapper::JabraSession;
+    use crate::components::common::models::permission_model::Permission;
+
+    let session = JabraSession::extract().await?;
+    session.require(Permission::Admin)?;
+
+    match audit::read(&filter, limit.clamp(1, 1_000)).await {
+        Ok(entries) => Ok(entries),
+        Err(e) => {
+            tracing::error!(server_fn = "get_audit_log", error = %e, "server function failed");
+            Err(e.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_entry_json() {
+        let entry = AuditEntry::new(
+            "approver@jabra.io".to_string(),
+            AuditAction::ApproveQuote,
+            "10.0.0.1".to_string(),
+        )
+        .group_id(Some("5f1c".to_string()))
+        .item_id(Some("42".to_string()))
+        .before(Some(serde_json::json!({"quote_status": "active"})))
+        .after(Some(serde_json::json!({"quote_status": "approved"})));
+
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["action"], "approve_quote");
+        assert_eq!(json["before"]["quote_status"], "active");
+        assert_eq!(json["error"], serde_json::Value::Null);
+        assert_eq!(serde_json::from_value::<AuditEntry>(json).unwrap(), entry);
+    }
+
+    #[test]
+    fn test_audit_filter() {
+        let entry = AuditEntry::new(
+            "Trader@jabra.io".to_string(),
+            AuditAction::CreateQuote,
+            "10.0.0.1".to_string(),
+        )
+        .group_id(Some("5f1c".to_string()));
+
+        assert!(AuditFilter::default().matches(&entry));
+        assert!(AuditFilter {
+            actor: "trader@jabra.io".to_string(),
+            action: "create_quote".to_string(),
+            group_id: "5f1c".to_string(),
+        }
+        .matches(&entry));
+        assert!(AuditFilter {
+            actor: " TRADER@".to_string(),
+            ..Default::default()
+        }
+        .matches(&entry));
+        assert!(!AuditFilter {
+            actor: "sales@".to_string(),
+            ..Default::default()
+        }
+        .matches(&entry));
+        assert!(!AuditFilter {
+            action: "edit_quote".to_string(),
+            ..Default::default()
+        }
+        .matches(&entry));
+    }
+}
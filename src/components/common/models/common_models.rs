@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
+use super::money_model::{Price, Quantity};
+
 /// This is a common model that is used multiple times in this project.
 /// It is used to send a blank request to the server to get a response.
 
@@ -284,6 +286,9 @@ pub struct CounterParty {
     pub id: u16,
     /// The default expiry of the counterparty.
     pub default_expiry: String,
+    /// Whether the counterparty can be quoted, active unless the row says otherwise.
+    #[serde(default = "crate::components::common::models::counterparty_model::active_by_default")]
+    pub is_active: bool,
 }
 
 impl Default for CounterParty {
@@ -295,6 +300,7 @@ impl Default for CounterParty {
             is_exchange: false,
             id: 0,
             default_expiry: String::from("10:00"),
+            is_active: true,
         }
     }
 }
@@ -511,17 +517,17 @@ pub struct Quote {
     pub counterparty_id: u16,
     pub pair_id: u16,
     pub ccy_id: u16,
-    pub amount: f64,
+    pub amount: Quantity,
     pub option_kind: String,
     pub ttm: f64,
     pub r2: f64,
     pub r1: f64,
     pub offstrike_percentage: f64,
-    pub spot: f64,
-    pub strike: f64,
+    pub spot: Price,
+    pub strike: Price,
     pub iv: f64,
-    pub px_in_base_ccy: f64,
-    pub px_in_quote_ccy: f64,
+    pub px_in_base_ccy: Price,
+    pub px_in_quote_ccy: Price,
     pub side: String,
     pub quote_status: String,
     pub quote_origin: String,
@@ -545,17 +551,17 @@ impl Quote {
         counterparty_id: u16,
         pair_id: u16,
         ccy_id: u16,
-        amount: f64,
+        amount: Quantity,
         option_kind: String,
         ttm: f64,
         r2: f64,
         r1: f64,
         offstrike_percentage: f64,
-        spot: f64,
-        strike: f64,
+        spot: Price,
+        strike: Price,
         iv: f64,
-        px_in_base_ccy: f64,
-        px_in_quote_ccy: f64,
+        px_in_base_ccy: Price,
+        px_in_quote_ccy: Price,
         side: String,
         quote_status: String,
         quote_origin: String,
@@ -1,6 +1,8 @@
 use leptos::*;
 use serde::{Deserialize, Serialize};
 
+use crate::errors::{FieldError, JabraError};
+
 /// This struct is used to get the details of a counterparty.
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -38,33 +40,278 @@ impl GetCounterPartiesResponse {
 
 #[server(GetCounterParties)]
 pub async fn get_counter_parties() -> Result<GetCounterPartiesResponse, ServerFnError> {
-    use crate::components::common::functions::wrapper::get_cookie_value;
-    use crate::components::common::functions::wrapper::JabraCookie;
+    use crate::components::common::functions::wrapper::JabraSession;
     use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod};
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::cache::{reference_ttl, TtlCache};
+    use std::sync::OnceLock;
+
+    static CACHE: OnceLock<TtlCache<GetCounterPartiesResponse>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| TtlCache::new("counterparty"));
 
-    let cookie = get_cookie_value("JabraOPv1_2023").await;
-    let jwt_cookie = JabraCookie::decrypt(cookie.unwrap()).unwrap_or_default();
-    let bearer = format!("Bearer {}", jwt_cookie.access_token);
+    let session = JabraSession::extract().await?;
     let url = std::env::var("DIRECTUSURL").unwrap();
     let path = format!("{}/items/counterparty", url);
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        reqwest::header::HeaderValue::from_str(&bearer).unwrap(),
-    );
+    let headers = session.auth_headers();
+
+    let response = cache
+        .get_or_fetch(session.role(), reference_ttl(), || {
+            call_and_parse::<super::common_models::BlankRequest, GetCounterPartiesResponse>(
+                Option::None,
+                path,
+                headers,
+                HttpMethod::GET,
+                Upstream::Directus,
+            )
+        })
+        .await;
+    match response {
+        Ok(res) => Ok(res),
+        Err(e) => {
+            tracing::error!(server_fn = "get_counter_parties", error = %e, "server function failed");
+            Err(e.into())
+        }
+    }
+}
+
+/// The fields of a counterparty an admin can change on the counterparties page.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct CounterPartyFields {
+    pub ticker: String,
+    pub name: String,
+    pub short_name: Option<String>,
+    pub is_exchange: bool,
+    /// Time of day, in UTC, that trades with the counterparty expire at, as `HH:MM:SS`.
+    #[serde(default)]
+    pub default_expiry: String,
+    /// Inactive counterparties are kept for the trades booked with them, but can't be quoted.
+    /// Rows read before the `is_active` column exists count as active.
+    #[serde(default = "active_by_default")]
+    pub is_active: bool,
+}
+
+pub(crate) fn active_by_default() -> bool {
+    true
+}
+
+impl CounterPartyFields {
+    /// Trims the fields, uppercases the ticker and writes the expiry as `HH:MM:SS`,
+    /// the format `create_trade_expiry_in_utc` expects. `taken_tickers` are the tickers of the
+    /// other counterparties.
+    pub fn validate(self, taken_tickers: &[String]) -> Result<Self, JabraError> {
+        let ticker = self.ticker.trim().to_uppercase();
+        if ticker.is_empty()
+            || !ticker
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(validation_error(
+                "ticker",
+                "Ticker must be letters, digits or underscores.",
+            ));
+        }
+        if taken_tickers
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(&ticker))
+        {
+            return Err(validation_error(
+                "ticker",
+                &format!("{} is already used by another counterparty.", ticker),
+            ));
+        }
+        let name = self.name.trim().to_string();
+        if name.is_empty() {
+            return Err(validation_error("name", "Name is required."));
+        }
+        let default_expiry = self.default_expiry.trim();
+        let default_expiry = chrono::NaiveTime::parse_from_str(default_expiry, "%H:%M:%S")
+            .or_else(|_| chrono::NaiveTime::parse_from_str(default_expiry, "%H:%M"))
+            .map_err(|_| {
+                validation_error("default_expiry", "Default expiry must be a time as HH:MM.")
+            })?;
+        Ok(Self {
+            ticker,
+            name,
+            short_name: self
+                .short_name
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+            is_exchange: self.is_exchange,
+            default_expiry: default_expiry.format("%H:%M:%S").to_string(),
+            is_active: self.is_active,
+        })
+    }
+}
+
+fn validation_error(field: &str, message: &str) -> JabraError {
+    JabraError::Validation {
+        message: message.to_string(),
+        fields: vec![FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        }],
+    }
+}
+
+/// A counterparty as listed on the counterparties page.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct CounterPartyEntry {
+    pub id: u16,
+    #[serde(flatten)]
+    pub fields: CounterPartyFields,
+}
+
+impl CounterPartyEntry {
+    pub fn get_default_query() -> String {
+        "id,ticker,name,short_name,is_exchange,default_expiry,is_active".to_string()
+    }
+}
+
+/// This is the response struct for the Directus `counterparty` collection with every field.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CounterPartyEntriesResponse {
+    pub data: Vec<CounterPartyEntry>,
+}
+
+/// This is the response struct for a single created or updated `counterparty` item.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CounterPartyEntryResponse {
+    pub data: CounterPartyEntry,
+}
+
+/// Server function that lists every counterparty, active or not, straight from Directus. Admins only.
+
+#[server(ListCounterParties, "/api")]
+pub async fn list_counter_parties() -> Result<Vec<CounterPartyEntry>, ServerFnError> {
+    use crate::components::common::functions::wrapper::JabraSession;
+    use crate::components::common::models::permission_model::Permission;
+
+    let session = JabraSession::extract().await?;
+    session.require(Permission::Admin)?;
 
-    let response = call_and_parse::<super::common_models::BlankRequest, GetCounterPartiesResponse>(
+    match fetch_entries(session.auth_headers()).await {
+        Ok(entries) => Ok(entries),
+        Err(e) => {
+            tracing::error!(server_fn = "list_counter_parties", error = %e, "server function failed");
+            Err(e.into())
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+async fn fetch_entries(
+    headers: reqwest::header::HeaderMap,
+) -> Result<Vec<CounterPartyEntry>, JabraError> {
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod};
+
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let path = format!(
+        "{}/items/counterparty?fields={}&sort=name&limit=-1",
+        url,
+        CounterPartyEntry::get_default_query()
+    );
+    call_and_parse::<super::common_models::BlankRequest, CounterPartyEntriesResponse>(
         Option::None,
         path,
         headers,
         HttpMethod::GET,
+        Upstream::Directus,
+    )
+    .await
+    .map(|res| res.data)
+}
+
+/// Server function that creates a counterparty, or updates counterparty `id`, e.g. to deactivate it.
+/// Clears the reference cache so the builders pick the change up right away. Admins only.
+
+#[server(SaveCounterParty, "/api")]
+pub async fn save_counter_party(
+    id: Option<u16>,
+    fields: CounterPartyFields,
+) -> Result<CounterPartyEntry, ServerFnError> {
+    use crate::components::common::functions::audit;
+    use crate::components::common::functions::cache::invalidate_all;
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::wrapper::{
+        call_and_parse, client_ip, HttpMethod, JabraSession,
+    };
+    use crate::components::common::models::audit_model::{AuditAction, AuditEntry};
+    use crate::components::common::models::permission_model::Permission;
+
+    let session = JabraSession::extract().await?;
+    session.require(Permission::Admin)?;
+    let headers = session.auth_headers();
+
+    let existing = fetch_entries(headers.clone()).await?;
+    let before = id.and_then(|id| existing.iter().find(|e| e.id == id).cloned());
+    if id.is_some() && before.is_none() {
+        return Err(
+            JabraError::NotFound(format!("counterparty {}", id.unwrap_or_default())).into(),
+        );
+    }
+    let taken_tickers = existing
+        .iter()
+        .filter(|e| Some(e.id) != id)
+        .map(|e| e.fields.ticker.clone())
+        .collect::<Vec<String>>();
+    let fields = fields.validate(&taken_tickers)?;
+
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let deactivated = before.as_ref().is_some_and(|b| b.fields.is_active) && !fields.is_active;
+    let (path, method, action) = match id {
+        Some(id) => (
+            format!("{}/items/counterparty/{}", url, id),
+            HttpMethod::PATCH,
+            match deactivated {
+                true => AuditAction::DeactivateCounterparty,
+                false => AuditAction::EditCounterparty,
+            },
+        ),
+        None => (
+            format!("{}/items/counterparty", url),
+            HttpMethod::POST,
+            AuditAction::CreateCounterparty,
+        ),
+    };
+    let after = serde_json::to_value(&fields).ok();
+    let response = call_and_parse::<CounterPartyFields, CounterPartyEntryResponse>(
+        Some(fields),
+        path,
+        headers,
+        method,
+        Upstream::Directus,
     )
     .await;
+
+    audit::record(vec![AuditEntry::new(
+        session.cookie.user_id.clone(),
+        action,
+        client_ip(),
+    )
+    .item_id(
+        response
+            .as_ref()
+            .ok()
+            .map(|res| res.data.id.to_string())
+            .or(id.map(|id| id.to_string())),
+    )
+    .before(before.and_then(|b| serde_json::to_value(&b.fields).ok()))
+    .after(after)
+    .error(response.as_ref().err().map(|e| e.to_string()))])
+    .await;
     match response {
-        Ok(res) => Ok(res),
+        Ok(res) => {
+            invalidate_all();
+            Ok(res.data)
+        }
         Err(e) => {
-            log::error!("error6: {:?}", e);
-            Err(ServerFnError::new(e.to_string()))
+            tracing::warn!(server_fn = "save_counter_party", error = %e, "server function failed");
+            Err(e.into())
         }
     }
 }
@@ -79,4 +326,75 @@ mod tests {
             "party_a.id, party_a.ticker, party_a.name, party_a.short_name, party_a.is_exchange"
         );
     }
+
+    #[test]
+    fn test_validate_fields() {
+        use super::CounterPartyFields;
+        use crate::errors::JabraError;
+
+        let fields = CounterPartyFields {
+            ticker: " bitbox ".to_string(),
+            name: "Bitbox Ltd ".to_string(),
+            short_name: Some(" ".to_string()),
+            is_exchange: false,
+            default_expiry: "08:00".to_string(),
+            is_active: true,
+        };
+        let valid = fields.clone().validate(&["DERIBIT".to_string()]).unwrap();
+        assert_eq!(valid.ticker, "BITBOX");
+        assert_eq!(valid.name, "Bitbox Ltd");
+        assert_eq!(valid.short_name, None);
+        assert_eq!(valid.default_expiry, "08:00:00");
+        // Saving an already valid counterparty changes nothing
+        assert_eq!(valid.clone().validate(&[]).unwrap(), valid);
+
+        let field_of = |result: Result<CounterPartyFields, JabraError>| match result {
+            Err(JabraError::Validation { fields, .. }) => fields[0].field.clone(),
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(
+            field_of(fields.clone().validate(&["Bitbox".to_string()])),
+            "ticker"
+        );
+        assert_eq!(
+            field_of(
+                CounterPartyFields {
+                    default_expiry: "25:00".to_string(),
+                    ..fields.clone()
+                }
+                .validate(&[])
+            ),
+            "default_expiry"
+        );
+        assert_eq!(
+            field_of(
+                CounterPartyFields {
+                    name: "".to_string(),
+                    ..fields
+                }
+                .validate(&[])
+            ),
+            "name"
+        );
+    }
+
+    #[test]
+    fn test_entry_json() {
+        use super::CounterPartyEntry;
+
+        let entry = serde_json::from_str::<CounterPartyEntry>(
+            r#"{"id":3,"ticker":"BITBOX","name":"Bitbox","short_name":null,"is_exchange":false,"default_expiry":"10:00:00","is_active":true}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.id, 3);
+        assert_eq!(entry.fields.default_expiry, "10:00:00");
+        assert!(entry.fields.is_active);
+
+        let entry = serde_json::from_str::<CounterPartyEntry>(
+            r#"{"id":4,"ticker":"DERIBIT","name":"Deribit","short_name":null,"is_exchange":true}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.fields.default_expiry, "");
+        assert!(entry.fields.is_active);
+    }
 }
@@ -45,39 +45,366 @@ pub struct CurrencyConfigurationResponse {
 
 #[server]
 pub async fn fetch_currencies() -> Result<CurrencyConfigurationResponse, ServerFnError> {
-    use crate::components::common::functions::wrapper::JabraCookie;
-    use crate::components::common::functions::wrapper::{
-        call_and_parse, get_cookie_value, HttpMethod,
-    };
+    use crate::components::common::functions::wrapper::JabraSession;
+    use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod};
+    use crate::components::common::functions::upstream::Upstream;
     use crate::components::common::models::common_models::BlankRequest;
+    use crate::components::common::functions::cache::{reference_ttl, TtlCache};
+    use std::sync::OnceLock;
 
-    let cookie = get_cookie_value("JabraOPv1_2023").await;
-    let jwt_cookie = JabraCookie::decrypt(cookie.unwrap()).unwrap_or_default();
-    let bearer = format!("Bearer {}", jwt_cookie.access_token);
+    static CACHE: OnceLock<TtlCache<CurrencyConfigurationResponse>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| TtlCache::new("supported_ccy"));
+
+    let session = JabraSession::extract().await?;
     let url = std::env::var("DIRECTUSURL").unwrap();
     let path = format!(
         "{}/items/supported_ccy?fields={}",
         url,
         Currency::get_default_query()
     );
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        reqwest::header::HeaderValue::from_str(&bearer).unwrap(),
+    let headers = session.auth_headers();
+
+    let response = cache
+        .get_or_fetch(session.role(), reference_ttl(), || {
+            call_and_parse::<BlankRequest, CurrencyConfigurationResponse>(
+                Option::None,
+                path,
+                headers,
+                HttpMethod::GET,
+                Upstream::Directus,
+            )
+        })
+        .await;
+    match response {
+        Ok(res) => Ok(res),
+        Err(e) => {
+            tracing::error!(server_fn = "fetch_currencies", error = %e, "server function failed");
+            Err(e.into())
+        }
+    }
+}
+
+/// Tick size, lot size and contract multiplier of a currency, its `instrument_option` item.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct InstrumentOptionSettings {
+    /// `None` until the currency has an `instrument_option` item.
+    pub id: Option<u32>,
+    pub contract_multiplier: u16,
+    /// The tick size.
+    pub min_price_increment: f64,
+    /// The lot size.
+    pub min_contract_increment: f64,
+}
+
+/// A currency as edited on the pairs and currencies admin page.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct CurrencySettings {
+    pub id: u16,
+    pub ticker: String,
+    pub name: String,
+    pub is_active: bool,
+    pub display_scale: u8,
+    pub sign: Option<String>,
+    pub instrument_option: Option<InstrumentOptionSettings>,
+}
+
+impl CurrencySettings {
+    pub fn get_default_query() -> String {
+        format!(
+            "{}, instrument_option.id, instrument_option.contract_multiplier, instrument_option.min_price_increment, instrument_option.min_contract_increment",
+            Currency::get_default_query()
+        )
+    }
+}
+
+/// Checks the settings of a currency before they are saved. Tick and lot sizes must be supported
+/// powers of ten, and the display scale must show every decimal of the tick size.
+pub fn validate_currency_settings(
+    display_scale: u8,
+    instrument_option: &InstrumentOptionSettings,
+) -> Result<(), crate::errors::JabraError> {
+    use crate::components::common::functions::precision::{
+        convert_to_decimal, get_precision, is_supported_step, MAX_STEP_DECIMALS,
+    };
+    use crate::errors::{FieldError, JabraError};
+
+    let mut fields = Vec::new();
+    let mut invalid = |field: &str, message: String| {
+        fields.push(FieldError {
+            field: field.to_string(),
+            message,
+        })
+    };
+    let step_message = format!(
+        "must be a power of ten between 0.{}1 and 10000",
+        "0".repeat(MAX_STEP_DECIMALS as usize - 1)
+    );
+    let tick_size = instrument_option.min_price_increment;
+    if !is_supported_step(convert_to_decimal(tick_size)) {
+        invalid("tick_size", format!("Tick size {}", step_message));
+    } else if (display_scale as usize) < get_precision(tick_size) {
+        invalid(
+            "display_scale",
+            format!(
+                "Display scale must show the {} decimals of the tick size",
+                get_precision(tick_size)
+            ),
+        );
+    }
+    if !is_supported_step(convert_to_decimal(instrument_option.min_contract_increment)) {
+        invalid("lot_size", format!("Lot size {}", step_message));
+    }
+    if instrument_option.contract_multiplier == 0 {
+        invalid(
+            "contract_multiplier",
+            "Contract multiplier must be at least 1".to_string(),
+        );
+    }
+    if display_scale as u32 > MAX_STEP_DECIMALS {
+        invalid(
+            "display_scale",
+            format!("Display scale must be at most {}", MAX_STEP_DECIMALS),
+        );
+    }
+    match fields.is_empty() {
+        true => Ok(()),
+        false => Err(JabraError::Validation {
+            message: "The currency settings are invalid.".to_string(),
+            fields,
+        }),
+    }
+}
+
+/// The `instrument_option` item to save for `current`. A client may only name the item the currency
+/// already has, and one that does not name it still updates that item instead of adding a second one.
+pub fn owned_instrument_option_id(
+    current: &CurrencySettings,
+    requested: Option<u32>,
+) -> Result<Option<u32>, crate::errors::JabraError> {
+    let owned = current
+        .instrument_option
+        .as_ref()
+        .and_then(|option| option.id);
+    match requested {
+        Some(id) if owned != Some(id) => Err(crate::errors::JabraError::Validation {
+            message: format!(
+                "Instrument option {} does not belong to {}.",
+                id, current.ticker
+            ),
+            fields: vec![],
+        }),
+        _ => Ok(owned),
+    }
+}
+
+/// This is the response struct for the Directus `supported_ccy` collection with the instrument settings.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencySettingsResponse {
+    pub data: Vec<CurrencySettings>,
+}
+
+/// This is the response struct for a single `supported_ccy` item with the instrument settings.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencySettingsItemResponse {
+    pub data: CurrencySettings,
+}
+
+/// Server function that lists every currency with its instrument settings, straight from Directus.
+/// Admins only.
+
+#[server(ListCurrencySettings, "/api")]
+pub async fn list_currency_settings() -> Result<Vec<CurrencySettings>, ServerFnError> {
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod, JabraSession};
+    use crate::components::common::models::common_models::BlankRequest;
+    use crate::components::common::models::permission_model::Permission;
+
+    let session = JabraSession::extract().await?;
+    session.require(Permission::Admin)?;
+
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let path = format!(
+        "{}/items/supported_ccy?fields={}&sort=id&limit=-1",
+        url,
+        CurrencySettings::get_default_query()
     );
+    let response = call_and_parse::<BlankRequest, CurrencySettingsResponse>(
+        Option::None,
+        path,
+        session.auth_headers(),
+        HttpMethod::GET,
+        Upstream::Directus,
+    )
+    .await;
+    match response {
+        Ok(res) => Ok(res.data),
+        Err(e) => {
+            tracing::error!(server_fn = "list_currency_settings", error = %e, "server function failed");
+            Err(e.into())
+        }
+    }
+}
+
+/// Server function that saves the display scale and instrument settings of currency `id`,
+/// creating its `instrument_option` item if it has none. Admins only.
+/// Rows of `instrument_specs` still take precedence for their instrument type.
+
+#[server(SaveCurrencySettings, "/api")]
+pub async fn save_currency_settings(
+    id: u16,
+    display_scale: u8,
+    instrument_option: InstrumentOptionSettings,
+) -> Result<bool, ServerFnError> {
+    use crate::components::common::functions::audit;
+    use crate::components::common::functions::cache::invalidate_all;
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::wrapper::{
+        call, call_and_parse, client_ip, HttpMethod, JabraSession,
+    };
+    use crate::components::common::models::audit_model::{AuditAction, AuditEntry};
+    use crate::components::common::models::common_models::BlankRequest;
+    use crate::components::common::models::permission_model::Permission;
+    use serde_json::json;
 
-    let response = call_and_parse::<BlankRequest, CurrencyConfigurationResponse>(
+    let session = JabraSession::extract().await?;
+    session.require(Permission::Admin)?;
+    validate_currency_settings(display_scale, &instrument_option)?;
+    let headers = session.auth_headers();
+
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let path = format!("{}/items/supported_ccy/{}", url, id);
+    let current = call_and_parse::<BlankRequest, CurrencySettingsItemResponse>(
         Option::None,
+        format!("{}?fields={}", path, CurrencySettings::get_default_query()),
+        headers.clone(),
+        HttpMethod::GET,
+        Upstream::Directus,
+    )
+    .await?
+    .data;
+    let option_id = owned_instrument_option_id(&current, instrument_option.id)?;
+
+    // Directus updates the related item in place when its id is given, and creates it otherwise
+    let mut option = json!({
+        "ccy_id": id,
+        "contract_multiplier": instrument_option.contract_multiplier,
+        "min_price_increment": instrument_option.min_price_increment,
+        "min_contract_increment": instrument_option.min_contract_increment,
+    });
+    if let Some(option_id) = option_id {
+        option["id"] = json!(option_id);
+    }
+    let request = json!({
+        "display_scale": display_scale,
+        "instrument_option": option,
+    });
+
+    let response = call::<serde_json::Value>(
+        Some(request.clone()),
         path,
         headers,
-        HttpMethod::GET,
+        HttpMethod::PATCH,
+        Upstream::Directus,
+    )
+    .await;
+    audit::record(vec![AuditEntry::new(
+        session.cookie.user_id.clone(),
+        AuditAction::EditCurrency,
+        client_ip(),
     )
+    .item_id(Some(id.to_string()))
+    .before(Some(json!({
+        "display_scale": current.display_scale,
+        "instrument_option": current.instrument_option,
+    })))
+    .after(Some(request))
+    .error(response.as_ref().err().map(|e| e.to_string()))])
     .await;
     match response {
-        Ok(res) => Ok(res),
+        Ok(res) => {
+            invalidate_all();
+            Ok(res)
+        }
         Err(e) => {
-            log::error!("error: {:?}", e);
-            Err(ServerFnError::new(e.to_string()))
+            tracing::warn!(server_fn = "save_currency_settings", error = %e, "server function failed");
+            Err(e.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::JabraError;
+
+    fn settings(tick_size: f64, lot_size: f64) -> InstrumentOptionSettings {
+        InstrumentOptionSettings {
+            id: Some(1),
+            contract_multiplier: 1,
+            min_price_increment: tick_size,
+            min_contract_increment: lot_size,
+        }
+    }
+
+    fn invalid_fields(result: Result<(), JabraError>) -> Vec<String> {
+        match result {
+            Err(JabraError::Validation { fields, .. }) => {
+                fields.into_iter().map(|f| f.field).collect()
+            }
+            other => panic!("unexpected {:?}", other),
         }
     }
+
+    #[test]
+    fn test_validate_currency_settings() {
+        assert_eq!(validate_currency_settings(2, &settings(0.01, 0.1)), Ok(()));
+        assert_eq!(validate_currency_settings(8, &settings(1e-8, 1.0)), Ok(()));
+        assert_eq!(
+            invalid_fields(validate_currency_settings(2, &settings(0.05, 0.1))),
+            vec!["tick_size"]
+        );
+        assert_eq!(
+            invalid_fields(validate_currency_settings(2, &settings(-0.01, 0.0))),
+            vec!["tick_size", "lot_size"]
+        );
+        assert_eq!(
+            invalid_fields(validate_currency_settings(2, &settings(0.0001, 0.1))),
+            vec!["display_scale"]
+        );
+        assert_eq!(
+            invalid_fields(validate_currency_settings(
+                9,
+                &InstrumentOptionSettings {
+                    contract_multiplier: 0,
+                    ..settings(1.0, 1.0)
+                }
+            )),
+            vec!["contract_multiplier", "display_scale"]
+        );
+    }
+
+    #[test]
+    fn test_owned_instrument_option_id() {
+        let current = CurrencySettings {
+            ticker: "BTC".to_string(),
+            instrument_option: Some(settings(0.01, 0.1)),
+            ..Default::default()
+        };
+        assert_eq!(owned_instrument_option_id(&current, Some(1)), Ok(Some(1)));
+        assert_eq!(owned_instrument_option_id(&current, None), Ok(Some(1)));
+        assert_eq!(
+            owned_instrument_option_id(&current, Some(2))
+                .unwrap_err()
+                .to_string(),
+            "Instrument option 2 does not belong to BTC."
+        );
+
+        let without_option = CurrencySettings::default();
+        assert_eq!(owned_instrument_option_id(&without_option, None), Ok(None));
+        assert!(owned_instrument_option_id(&without_option, Some(1)).is_err());
+    }
 }
@@ -1,3 +1,4 @@
+use leptos::*;
 use serde::{Deserialize, Serialize};
 
 use super::currency_model::Currency;
@@ -31,3 +32,170 @@ mod test {
         assert_eq!(query, "pair_id.id, pair_id.name, pair_id.is_active");
     }
 }
+
+/// Checks that `pair` may be switched to `is_active`. A pair can only be quoted, and so only be
+/// activated, while both of its currencies are active.
+pub fn validate_pair_activation(
+    pair: &CurrencyPair,
+    is_active: bool,
+) -> Result<(), crate::errors::JabraError> {
+    use crate::errors::JabraError;
+
+    let inactive = [&pair.base, &pair.quote]
+        .into_iter()
+        .filter(|currency| !currency.is_active)
+        .map(|currency| currency.ticker.clone())
+        .collect::<Vec<String>>();
+    if !is_active || inactive.is_empty() {
+        return Ok(());
+    }
+    Err(JabraError::Validation {
+        message: format!(
+            "{} can't be activated while {} is inactive.",
+            pair.name,
+            inactive.join(" and ")
+        ),
+        fields: vec![],
+    })
+}
+
+/// This is the response struct for the Directus `supported_pair` collection.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencyPairsResponse {
+    pub data: Vec<CurrencyPair>,
+}
+
+/// This is the response struct for a single `supported_pair` item.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencyPairItemResponse {
+    pub data: CurrencyPair,
+}
+
+/// Fields of a pair for the pairs and currencies admin page.
+const ADMIN_PAIR_FIELDS: &str =
+    "id,name,is_active,base.id,base.ticker,base.name,base.is_active,base.display_scale,base.sign,quote.id,quote.ticker,quote.name,quote.is_active,quote.display_scale,quote.sign";
+
+/// Server function that lists every supported pair, active or not, straight from Directus. Admins only.
+
+#[server(ListSupportedPairs, "/api")]
+pub async fn list_supported_pairs() -> Result<Vec<CurrencyPair>, ServerFnError> {
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod, JabraSession};
+    use crate::components::common::models::common_models::BlankRequest;
+    use crate::components::common::models::permission_model::Permission;
+
+    let session = JabraSession::extract().await?;
+    session.require(Permission::Admin)?;
+
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let path = format!(
+        "{}/items/supported_pair?fields={}&sort=id&limit=-1",
+        url, ADMIN_PAIR_FIELDS
+    );
+    let response = call_and_parse::<BlankRequest, CurrencyPairsResponse>(
+        Option::None,
+        path,
+        session.auth_headers(),
+        HttpMethod::GET,
+        Upstream::Directus,
+    )
+    .await;
+    match response {
+        Ok(res) => Ok(res.data),
+        Err(e) => {
+            tracing::error!(server_fn = "list_supported_pairs", error = %e, "server function failed");
+            Err(e.into())
+        }
+    }
+}
+
+/// Server function that activates or deactivates pair `id`, so the builders offer it or not.
+/// Clears the reference cache so the change shows right away. Admins only.
+
+#[server(SetPairActive, "/api")]
+pub async fn set_pair_active(id: u16, is_active: bool) -> Result<bool, ServerFnError> {
+    use crate::components::common::functions::audit;
+    use crate::components::common::functions::cache::invalidate_all;
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::wrapper::{
+        call, call_and_parse, client_ip, HttpMethod, JabraSession,
+    };
+    use crate::components::common::models::audit_model::{AuditAction, AuditEntry};
+    use crate::components::common::models::common_models::BlankRequest;
+    use crate::components::common::models::permission_model::Permission;
+    use serde_json::json;
+
+    let session = JabraSession::extract().await?;
+    session.require(Permission::Admin)?;
+    let headers = session.auth_headers();
+
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let path = format!("{}/items/supported_pair/{}", url, id);
+    let pair = call_and_parse::<BlankRequest, CurrencyPairItemResponse>(
+        Option::None,
+        format!("{}?fields={}", path, ADMIN_PAIR_FIELDS),
+        headers.clone(),
+        HttpMethod::GET,
+        Upstream::Directus,
+    )
+    .await?
+    .data;
+    validate_pair_activation(&pair, is_active)?;
+
+    let response = call::<serde_json::Value>(
+        Some(json!({ "is_active": is_active })),
+        path,
+        headers,
+        HttpMethod::PATCH,
+        Upstream::Directus,
+    )
+    .await;
+    audit::record(vec![AuditEntry::new(
+        session.cookie.user_id.clone(),
+        AuditAction::EditPair,
+        client_ip(),
+    )
+    .item_id(Some(id.to_string()))
+    .before(Some(json!({ "is_active": pair.is_active })))
+    .after(Some(json!({ "is_active": is_active })))
+    .error(response.as_ref().err().map(|e| e.to_string()))])
+    .await;
+    match response {
+        Ok(res) => {
+            invalidate_all();
+            Ok(res)
+        }
+        Err(e) => {
+            tracing::warn!(server_fn = "set_pair_active", error = %e, "server function failed");
+            Err(e.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_pair_activation() {
+        let mut pair = CurrencyPair {
+            name: "BTC/USD".to_string(),
+            ..Default::default()
+        };
+        pair.base.ticker = "BTC".to_string();
+        pair.quote.ticker = "USD".to_string();
+        pair.quote.is_active = true;
+
+        assert!(validate_pair_activation(&pair, false).is_ok());
+        assert_eq!(
+            validate_pair_activation(&pair, true)
+                .unwrap_err()
+                .to_string(),
+            "BTC/USD can't be activated while BTC is inactive."
+        );
+        pair.base.is_active = true;
+        assert!(validate_pair_activation(&pair, true).is_ok());
+    }
+}
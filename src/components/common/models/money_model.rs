@@ -0,0 +1,166 @@
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+
+use crate::components::common::functions::precision::{convert_to_decimal, snap, RoundType};
+
+/// Implements the parts [`Price`] and [`Quantity`] share. Both are exact decimals that are
+/// serialized as JSON numbers, so the Directus schema stays as it is. A value snapped to a tick
+/// has few enough digits to survive that conversion unchanged.
+macro_rules! decimal_newtype {
+    ($name:ident) => {
+        impl $name {
+            pub const ZERO: Self = Self(Decimal::ZERO);
+
+            pub fn new(value: Decimal) -> Self {
+                Self(value)
+            }
+
+            /// Converts an `f64` from a signal or an upstream response through its shortest
+            /// decimal representation, e.g. `0.1` stays `0.1`.
+            pub fn from_f64(value: f64) -> Self {
+                Self(convert_to_decimal(value))
+            }
+
+            /// Parses builder input, e.g. `"64000.5"`. Empty or malformed input is `None`.
+            pub fn parse(input: &str) -> Option<Self> {
+                Decimal::from_str(input.trim()).ok().map(Self)
+            }
+
+            pub fn value(&self) -> Decimal {
+                self.0
+            }
+
+            /// For views and pricing requests that still work on `f64`.
+            pub fn to_f64(&self) -> f64 {
+                self.0.to_f64().unwrap_or_default()
+            }
+
+            pub fn is_zero(&self) -> bool {
+                self.0.is_zero()
+            }
+
+            pub fn abs(&self) -> Self {
+                Self(self.0.abs())
+            }
+        }
+
+        impl From<Decimal> for $name {
+            fn from(value: Decimal) -> Self {
+                Self(value)
+            }
+        }
+
+        impl std::ops::Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_f64(self.to_f64())
+            }
+        }
+
+        // Accepts numbers as well as strings, which is how Directus returns decimal fields
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                <Decimal as Deserialize>::deserialize(deserializer).map(Self)
+            }
+        }
+    };
+}
+
+/// A price, premium or strike in the quote currency of an instrument.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Price(Decimal);
+
+decimal_newtype!(Price);
+
+impl Price {
+    /// Snaps `value` to a multiple of the instrument's tick size.
+    pub fn snap(value: Decimal, tick_size: Decimal, round_type: RoundType) -> Self {
+        Self(snap(value, tick_size, round_type))
+    }
+
+    pub fn is_on_tick(&self, tick_size: Decimal) -> bool {
+        tick_size.is_zero() || (self.0 % tick_size).is_zero()
+    }
+}
+
+/// An amount of an instrument, in units of its base currency.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Quantity(Decimal);
+
+decimal_newtype!(Quantity);
+
+impl Quantity {
+    /// Snaps `value` to a multiple of the instrument's lot size.
+    pub fn snap(value: Decimal, lot_size: Decimal, round_type: RoundType) -> Self {
+        Self(snap(value, lot_size, round_type))
+    }
+
+    pub fn is_on_lot(&self, lot_size: Decimal) -> bool {
+        lot_size.is_zero() || (self.0 % lot_size).is_zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_snap() {
+        let price = Price::snap(dec("64000.567"), dec("0.01"), RoundType::Floor);
+        assert_eq!(price, Price::new(dec("64000.56")));
+        assert!(price.is_on_tick(dec("0.01")));
+        assert!(!price.is_on_tick(dec("0.1")));
+
+        let quantity = Quantity::snap(dec("1.3"), dec("0.2"), RoundType::Floor);
+        assert_eq!(quantity.to_string(), "1.2");
+        assert_eq!(
+            Quantity::snap(dec("1.3"), dec("0.2"), RoundType::Ceiling).to_string(),
+            "1.4"
+        );
+        assert!(quantity.is_on_lot(dec("0.2")));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Price::parse(" 64000.5 "), Some(Price::new(dec("64000.5"))));
+        assert_eq!(Price::parse(""), None);
+        assert_eq!(Quantity::parse("abc"), None);
+        assert_eq!(Quantity::from_f64(0.1).value(), dec("0.1"));
+    }
+
+    #[test]
+    fn test_serde() {
+        let price = Price::new(dec("1234.1234"));
+        assert_eq!(serde_json::to_string(&price).unwrap(), "1234.1234");
+        assert_eq!(serde_json::from_str::<Price>("1234.1234").unwrap(), price);
+        assert_eq!(
+            serde_json::from_str::<Price>("\"1234.1234\"").unwrap(),
+            price
+        );
+        assert_eq!(
+            serde_json::to_string(&-Quantity::new(dec("0.5"))).unwrap(),
+            "-0.5"
+        );
+    }
+}
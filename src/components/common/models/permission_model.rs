@@ -0,0 +1,212 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+/// Portal actions gated by the user's Directus role.
+/// The set is resolved once at login and carried in the session cookie.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Permission {
+    #[serde(rename = "quote:create")]
+    CreateQuote,
+    #[serde(rename = "quote:approve")]
+    ApproveQuote,
+    #[serde(rename = "quote:edit")]
+    EditQuote,
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl Permission {
+    /// Resolves the portal permissions from the user's Directus role and its collection permissions.
+    /// Admin roles get everything. Approving needs update access to `quote_status`,
+    /// editing needs update access to the quote terms.
+    pub fn from_directus(role: &DirectusRole, permissions: &[DirectusPermission]) -> Vec<Permission> {
+        if role.admin_access {
+            return vec![
+                Permission::CreateQuote,
+                Permission::ApproveQuote,
+                Permission::EditQuote,
+                Permission::Admin,
+            ];
+        }
+        let quote_permission = |action: &str| {
+            permissions
+                .iter()
+                .find(|p| p.collection == "quotes_option" && p.action == action)
+        };
+
+        let mut resolved = Vec::new();
+        if quote_permission("create").is_some() {
+            resolved.push(Permission::CreateQuote);
+        }
+        if let Some(update) = quote_permission("update") {
+            if update.allows_field("quote_status") {
+                resolved.push(Permission::ApproveQuote);
+            }
+            if update.allows_field("amount") {
+                resolved.push(Permission::EditQuote);
+            }
+        }
+        resolved
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DirectusRole {
+    pub id: String,
+    pub name: String,
+    pub admin_access: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DirectusMe {
+    pub role: Option<DirectusRole>,
+}
+
+/// This is the response struct for `/users/me` with the role fields expanded.
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DirectusMeResponse {
+    pub data: DirectusMe,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DirectusPermission {
+    pub collection: String,
+    pub action: String,
+    pub fields: Option<Vec<String>>,
+}
+
+impl DirectusPermission {
+    pub fn allows_field(&self, field: &str) -> bool {
+        match &self.fields {
+            Some(fields) => fields.iter().any(|f| f == "*" || f == field),
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DirectusPermissionsResponse {
+    pub data: Vec<DirectusPermission>,
+}
+
+/// The Directus role of a user and the portal permissions it grants.
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RoleAccess {
+    pub role_id: Option<String>,
+    pub permissions: Vec<Permission>,
+}
+
+/// Loads the role and permissions of the user owning `access_token` from Directus.
+#[cfg(feature = "ssr")]
+pub async fn fetch_permissions(
+    access_token: &str,
+) -> Result<RoleAccess, crate::errors::JabraError> {
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod};
+
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        "Authorization",
+        reqwest::header::HeaderValue::from_str(&format!("Bearer {}", access_token)).unwrap(),
+    );
+
+    let me = call_and_parse::<(), DirectusMeResponse>(
+        None,
+        format!(
+            "{}/users/me?fields=role.id,role.name,role.admin_access",
+            url
+        ),
+        headers.clone(),
+        HttpMethod::GET,
+        Upstream::Directus,
+    )
+    .await?;
+    let role = match me.data.role {
+        Some(role) => role,
+        None => return Ok(RoleAccess::default()),
+    };
+    if role.admin_access {
+        return Ok(RoleAccess {
+            permissions: Permission::from_directus(&role, &[]),
+            role_id: Some(role.id),
+        });
+    }
+
+    let permissions = call_and_parse::<(), DirectusPermissionsResponse>(
+        None,
+        format!(
+            "{}/permissions?filter[role][_eq]={}&fields=collection,action,fields&limit=-1",
+            url, role.id
+        ),
+        headers,
+        HttpMethod::GET,
+        Upstream::Directus,
+    )
+    .await?;
+    Ok(RoleAccess {
+        permissions: Permission::from_directus(&role, &permissions.data),
+        role_id: Some(role.id),
+    })
+}
+
+/// Server function that returns the permissions carried by the current session.
+
+#[server(GetPermissions)]
+pub async fn get_permissions() -> Result<Vec<Permission>, ServerFnError> {
+    use crate::components::common::functions::wrapper::JabraSession;
+
+    match JabraSession::extract().await {
+        Ok(session) => Ok(session.cookie.permissions),
+        Err(_) => Ok(vec![]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permission(action: &str, fields: &[&str]) -> DirectusPermission {
+        DirectusPermission {
+            collection: "quotes_option".to_string(),
+            action: action.to_string(),
+            fields: Some(fields.iter().map(|f| f.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn test_admin_role_gets_everything() {
+        let role = DirectusRole {
+            admin_access: true,
+            ..Default::default()
+        };
+        assert_eq!(Permission::from_directus(&role, &[]).len(), 4);
+    }
+
+    #[test]
+    fn test_trader_role() {
+        let role = DirectusRole::default();
+        let permissions = vec![
+            permission("read", &["*"]),
+            permission("create", &["*"]),
+            permission("update", &["amount", "px_in_base_ccy", "px_in_quote_ccy"]),
+        ];
+        assert_eq!(
+            Permission::from_directus(&role, &permissions),
+            vec![Permission::CreateQuote, Permission::EditQuote]
+        );
+    }
+
+    #[test]
+    fn test_approver_role() {
+        let role = DirectusRole::default();
+        let permissions = vec![permission("update", &["quote_status"])];
+        assert_eq!(
+            Permission::from_directus(&role, &permissions),
+            vec![Permission::ApproveQuote]
+        );
+    }
+}
@@ -1,5 +1,3 @@
-use crate::components::common::functions::wrapper;
-
 use super::{
     counterparty_model::CounterParty, currency_model::Currency, currencypair_model::CurrencyPair,
     user_model::User,
@@ -157,13 +155,11 @@ pub async fn get_quotes_option(
     quote_status: String,
 ) -> Result<std::collections::HashMap<String, Vec<QuoteOption>>, ServerFnError> {
     use super::common_models::BlankRequest;
-    use crate::components::common::functions::wrapper::get_cookie_value;
-    use crate::components::common::functions::wrapper::JabraCookie;
+    use crate::components::common::functions::wrapper::JabraSession;
     use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod};
+    use crate::components::common::functions::upstream::Upstream;
     use std::collections::HashMap;
-    let cookie = get_cookie_value("JabraOPv1_2023").await;
-    let jwt_cookie = JabraCookie::decrypt(cookie.unwrap()).unwrap_or_default();
-    let bearer = format!("Bearer {}", jwt_cookie.access_token);
+    let session = JabraSession::extract().await?;
     let url = std::env::var("DIRECTUSURL").unwrap();
     // let path = format!("{}/items/quotes_option?filter[quote_status][_eq]={}&filter[modified_date][_between]=[{}, {}]&fields={}", url, quote_status, QuoteOption::get_query());
     let path = format!(
@@ -172,17 +168,14 @@ pub async fn get_quotes_option(
         quote_status,
         QuoteOption::get_query()
     );
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        reqwest::header::HeaderValue::from_str(&bearer).unwrap(),
-    );
+    let headers = session.auth_headers();
 
     let response = call_and_parse::<BlankRequest, GetQuoteOptionResponse>(
         Option::None,
         path,
         headers,
         HttpMethod::GET,
+        Upstream::Directus,
     )
     .await;
 
@@ -238,8 +231,8 @@ pub async fn get_quotes_option(
             Ok(trade_quotes_map)
         }
         Err(e) => {
-            log::info!("error: {:?}", e);
-            Err(ServerFnError::new(e.to_string()))
+            tracing::warn!(server_fn = "get_quotes_option", error = %e, "server function failed");
+            Err(e.into())
         }
     }
 }
@@ -254,26 +247,21 @@ pub async fn get_quotes_option_under_24_hrs(
     end_date: String,
 ) -> Result<std::collections::HashMap<String, Vec<QuoteOption>>, ServerFnError> {
     use super::common_models::BlankRequest;
-    use crate::components::common::functions::wrapper::get_cookie_value;
-    use crate::components::common::functions::wrapper::JabraCookie;
+    use crate::components::common::functions::wrapper::JabraSession;
     use crate::components::common::functions::wrapper::{call_and_parse, HttpMethod};
+    use crate::components::common::functions::upstream::Upstream;
     use std::collections::HashMap;
-    let cookie = get_cookie_value("JabraOPv1_2023").await;
-    let jwt_cookie = JabraCookie::decrypt(cookie.unwrap()).unwrap_or_default();
-    let bearer = format!("Bearer {}", jwt_cookie.access_token);
+    let session = JabraSession::extract().await?;
     let url = std::env::var("DIRECTUSURL").unwrap();
     let path = format!("{}/items/quotes_option?filter[quote_status][_eq]={}&filter[modified_date][_between]=[{}, {}]&fields={}", url, quote_status, start_date, end_date, QuoteOption::get_query());
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        reqwest::header::HeaderValue::from_str(&bearer).unwrap(),
-    );
+    let headers = session.auth_headers();
 
     let response = call_and_parse::<BlankRequest, GetQuoteOptionResponse>(
         Option::None,
         path,
         headers,
         HttpMethod::GET,
+        Upstream::Directus,
     )
     .await;
 
@@ -329,8 +317,8 @@ pub async fn get_quotes_option_under_24_hrs(
             Ok(trade_quotes_map)
         }
         Err(e) => {
-            log::info!("error: {:?}", e);
-            Err(ServerFnError::new(e.to_string()))
+            tracing::warn!(server_fn = "get_quotes_option_under_24_hrs", error = %e, "server function failed");
+            Err(e.into())
         }
     }
 }
@@ -355,46 +343,62 @@ pub async fn approve_reject_quotes_option(
     request: Vec<QuotesOptionForStatusChange>,
 ) -> Result<bool, ServerFnError> {
     log::info!("request: {:?}", request);
-    use crate::components::common::functions::wrapper::JabraCookie;
-    use crate::components::common::functions::wrapper::{call, get_cookie_value, HttpMethod};
-
-    let cookie = get_cookie_value("JabraOPv1_2023").await;
-    let jwt_cookie = JabraCookie::decrypt(cookie.unwrap()).unwrap_or_default();
-    let mut bearer = format!("Bearer {}", jwt_cookie.access_token);
-
-    // Check if token expires, this checking will be available only to actions and server action
-    // Other resources will still work due to 10 minutes buffer time
-    if jwt_cookie.is_expired() {
-        let refresh = wrapper::refresh_token(jwt_cookie.user_id, jwt_cookie.refresh_token).await;
-        match refresh {
-            Ok(r) => {
-                bearer = format!("Bearer {}", r.access_token);
-                wrapper::set_jabra_cookie(Some(r), "JabraOPv1_2023".to_string()).await;
-            }
-            Err(e) => {
-                log::error!("error-token: {:?}", e);
-                // directus_wrapper::set_jabra_cookie(None, "JabraOPv1_2023".to_string()).await;
-                return Err(ServerFnError::ServerError(e.to_string()));
-            }
-        }
-    }
+    use crate::components::common::functions::wrapper::JabraSession;
+    use crate::components::common::models::permission_model::Permission;
+    use crate::components::common::functions::wrapper::{call, HttpMethod};
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::audit;
+    use crate::components::common::models::audit_model::{AuditAction, AuditEntry};
+
+    let session = JabraSession::extract().await?;
+    session.require(Permission::ApproveQuote)?;
+
     let url = std::env::var("DIRECTUSURL").unwrap();
     let path = format!("{}/items/quotes_option", url);
 
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        reqwest::header::HeaderValue::from_str(&bearer).unwrap(),
-    );
-
-    let response =
-        call::<Vec<QuotesOptionForStatusChange>>(Some(request), path, headers, HttpMethod::PATCH)
-            .await;
+    let headers = session.auth_headers();
+
+    let ids = request.iter().map(|r| r.id).collect::<Vec<u32>>();
+    let before = audit::quotes_option_before(&ids, "quote_status", headers.clone()).await;
+    let audit_entries = request
+        .iter()
+        .map(|change| {
+            let action = match change.quote_status.as_str() {
+                "approved" => AuditAction::ApproveQuote,
+                "rejected" => AuditAction::RejectQuote,
+                _ => AuditAction::EditQuote,
+            };
+            audit_entry_for(&session.cookie.user_id, action, change.id, &before, change)
+        })
+        .collect::<Vec<AuditEntry>>();
+    let count_status = |status: &str| request.iter().filter(|r| r.quote_status == status).count();
+    let (approved, rejected) = (count_status("approved"), count_status("rejected"));
+
+    let response = call::<Vec<QuotesOptionForStatusChange>>(
+        Some(request),
+        path,
+        headers,
+        HttpMethod::PATCH,
+        Upstream::Directus,
+    )
+    .await;
+    let error = response.as_ref().err().map(|e| e.to_string());
+    audit::record(
+        audit_entries
+            .into_iter()
+            .map(|entry| entry.error(error.clone()))
+            .collect(),
+    )
+    .await;
     match response {
-        Ok(res) => Ok(res),
+        Ok(res) => {
+            crate::metrics::record_quotes(crate::metrics::QuoteEvent::Approved, approved);
+            crate::metrics::record_quotes(crate::metrics::QuoteEvent::Rejected, rejected);
+            Ok(res)
+        }
         Err(e) => {
-            log::info!("error-: {:?}", e);
-            Err(ServerFnError::new(e.to_string()))
+            tracing::warn!(server_fn = "approve_reject_quotes_option", error = %e, "server function failed");
+            Err(e.into())
         }
     }
     // Err(ServerFnError::ServerError("Transaction failed".to_string()))
@@ -410,47 +414,63 @@ pub async fn edit_quotes_option(
     request: Vec<QuotesOptionsForModification>,
 ) -> Result<bool, ServerFnError> {
     log::info!("request: {:?}", request);
-    use crate::components::common::functions::wrapper::JabraCookie;
-    use crate::components::common::functions::wrapper::{call, get_cookie_value, HttpMethod};
-
-    let cookie = get_cookie_value("JabraOPv1_2023").await;
-    let jwt_cookie = JabraCookie::decrypt(cookie.unwrap()).unwrap_or_default();
-    let mut bearer = format!("Bearer {}", jwt_cookie.access_token);
-
-    // Check if token expires, this checking will be available only to actions and server action
-    // Other resources will still work due to 10 minutes buffer time
-    if jwt_cookie.is_expired() {
-        let refresh = wrapper::refresh_token(jwt_cookie.user_id, jwt_cookie.refresh_token).await;
-        match refresh {
-            Ok(r) => {
-                bearer = format!("Bearer {}", r.access_token);
-                wrapper::set_jabra_cookie(Some(r), "JabraOPv1_2023".to_string()).await;
-            }
-            Err(e) => {
-                log::error!("error-token: {:?}", e);
-                // directus_wrapper::set_jabra_cookie(None, "JabraOPv1_2023".to_string()).await;
-                return Err(ServerFnError::ServerError(e.to_string()));
-            }
-        }
-    }
+    use crate::components::common::functions::wrapper::JabraSession;
+    use crate::components::common::models::permission_model::Permission;
+    use crate::components::common::functions::wrapper::{call, HttpMethod};
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::audit;
+    use crate::components::common::models::audit_model::{AuditAction, AuditEntry};
+
+    let session = JabraSession::extract().await?;
+    session.require(Permission::EditQuote)?;
+
     // log::debug!("request: {:?}", request.deserialize());
     let url = std::env::var("DIRECTUSURL").unwrap();
     let path = format!("{}/items/quotes_option", url);
 
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        reqwest::header::HeaderValue::from_str(&bearer).unwrap(),
-    );
+    let headers = session.auth_headers();
 
-    let response =
-        call::<Vec<QuotesOptionsForModification>>(Some(request), path, headers, HttpMethod::PATCH)
-            .await;
+    let ids = request.iter().map(|r| r.id).collect::<Vec<u32>>();
+    let before = audit::quotes_option_before(
+        &ids,
+        "amount,counterparty_id,px_in_base_ccy,px_in_quote_ccy,quote_expiry,payout_ccy,party_a,party_b,gtc",
+        headers.clone(),
+    )
+    .await;
+    let audit_entries = request
+        .iter()
+        .map(|change| {
+            audit_entry_for(
+                &session.cookie.user_id,
+                AuditAction::EditQuote,
+                change.id,
+                &before,
+                change,
+            )
+        })
+        .collect::<Vec<AuditEntry>>();
+
+    let response = call::<Vec<QuotesOptionsForModification>>(
+        Some(request),
+        path,
+        headers,
+        HttpMethod::PATCH,
+        Upstream::Directus,
+    )
+    .await;
+    let error = response.as_ref().err().map(|e| e.to_string());
+    audit::record(
+        audit_entries
+            .into_iter()
+            .map(|entry| entry.error(error.clone()))
+            .collect(),
+    )
+    .await;
     match response {
         Ok(res) => Ok(res),
         Err(e) => {
-            log::info!("error-: {:?}", e);
-            Err(ServerFnError::new(e.to_string()))
+            tracing::warn!(server_fn = "edit_quotes_option", error = %e, "server function failed");
+            Err(e.into())
         }
     }
     // // Err(ServerFnError::ServerError("Transaction failed".to_string()))
@@ -458,6 +478,32 @@ pub async fn edit_quotes_option(
     // Ok(true)
 }
 
+/// Builds the audit entry for a change to one `quotes_option` row, taking the group id
+/// from the row as it was before the change.
+#[cfg(feature = "ssr")]
+fn audit_entry_for<T: Serialize>(
+    actor: &str,
+    action: crate::components::common::models::audit_model::AuditAction,
+    id: u32,
+    before: &std::collections::HashMap<u32, serde_json::Value>,
+    after: &T,
+) -> crate::components::common::models::audit_model::AuditEntry {
+    use crate::components::common::functions::wrapper::client_ip;
+    use crate::components::common::models::audit_model::AuditEntry;
+
+    let before = before.get(&id).cloned();
+    let group_id = before
+        .as_ref()
+        .and_then(|row| row.get("group_id"))
+        .and_then(|group_id| group_id.as_str())
+        .map(|group_id| group_id.to_string());
+    AuditEntry::new(actor.to_string(), action, client_ip())
+        .group_id(group_id)
+        .item_id(Some(id.to_string()))
+        .before(before)
+        .after(serde_json::to_value(after).ok())
+}
+
 mod tests {
     #[test]
     fn test_get_query() {
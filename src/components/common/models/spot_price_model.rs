@@ -0,0 +1,181 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use super::common_models::CurrencyPair;
+
+/// Source of a spot price typed in by the trader.
+pub const MANUAL_SOURCE: &str = "manual";
+
+/// Whether the price moved from `from` to `to` by more than `threshold_bps` basis points.
+pub fn moved_beyond(from: f64, to: f64, threshold_bps: f64) -> bool {
+    if from <= 0.0 {
+        return true;
+    }
+    (to - from).abs() / from * 10_000.0 > threshold_bps
+}
+
+/// A spot price with where and when it was observed.
+/// Builders must not price off it once `stale_after` has passed.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct SpotPrice {
+    /// The pair as `BASE-QUOTE`, e.g. `BTC-USD`.
+    pub pair: String,
+    pub price: f64,
+    /// The provider, e.g. `coinbase`, `median(coinbase,bitstamp)` or `manual`.
+    pub source: String,
+    /// When the price was observed, in unix milliseconds.
+    pub timestamp: i64,
+    /// Unix milliseconds after which the price is stale.
+    pub stale_after: i64,
+}
+
+impl SpotPrice {
+    pub fn new(pair: String, price: f64, source: String, timestamp: i64, max_age_ms: i64) -> Self {
+        Self {
+            pair,
+            price,
+            source,
+            timestamp,
+            stale_after: timestamp + max_age_ms,
+        }
+    }
+
+    /// A price typed in by the trader, fresh for `max_age_ms` from now.
+    pub fn manual(pair: String, price: f64, max_age_ms: i64) -> Self {
+        Self::new(
+            pair,
+            price,
+            MANUAL_SOURCE.to_string(),
+            chrono::Utc::now().timestamp_millis(),
+            max_age_ms,
+        )
+    }
+
+    pub fn is_manual(&self) -> bool {
+        self.source == MANUAL_SOURCE
+    }
+
+    /// Checks this price, which something was priced off, against a fresh `tick`.
+    /// Within `threshold_bps` it stays and is as fresh as the tick, beyond it `None` asks for a reprice.
+    pub fn confirm(&self, tick: &SpotPrice, threshold_bps: f64) -> Option<SpotPrice> {
+        if moved_beyond(self.price, tick.price, threshold_bps) {
+            return None;
+        }
+        Some(SpotPrice {
+            price: self.price,
+            ..tick.clone()
+        })
+    }
+
+    pub fn max_age_ms(&self) -> i64 {
+        self.stale_after - self.timestamp
+    }
+
+    pub fn age_ms(&self, now_ms: i64) -> i64 {
+        (now_ms - self.timestamp).max(0)
+    }
+
+    pub fn is_stale_at(&self, now_ms: i64) -> bool {
+        self.timestamp == 0 || self.price <= 0.0 || now_ms > self.stale_after
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.is_stale_at(chrono::Utc::now().timestamp_millis())
+    }
+}
+
+/// Server function that gets the spot price of `currency_pair` from the configured providers.
+/// Fails instead of returning a zero price when no provider has a fresh price.
+
+#[server(GetSpotPrice, "/api")]
+pub async fn get_spot_price(currency_pair: CurrencyPair) -> Result<SpotPrice, ServerFnError> {
+    use crate::components::common::functions::spot_price::spot_price_source;
+    use crate::components::common::functions::wrapper::JabraSession;
+    use crate::errors::JabraError;
+
+    JabraSession::extract().await?;
+    if currency_pair.base.ticker.is_empty() || currency_pair.quote.ticker.is_empty() {
+        return Err(JabraError::NotFound("No currency pair selected".to_string()).into());
+    }
+
+    match spot_price_source().spot_price(&currency_pair).await {
+        Ok(price) if price.is_stale() => {
+            Err(JabraError::StaleSpotPrice { pair: price.pair }.into())
+        }
+        Ok(price) => Ok(price),
+        Err(e) => {
+            tracing::error!(server_fn = "get_spot_price", error = %e, "server function failed");
+            Err(e.into())
+        }
+    }
+}
+
+/// Server function that gets how far spot may move, in basis points, before a staged quote is repriced.
+/// Set by `SPOT_REPRICE_THRESHOLD_BPS`, 25 by default.
+
+#[server(GetRepriceThresholdBps, "/api")]
+pub async fn get_reprice_threshold_bps() -> Result<f64, ServerFnError> {
+    use crate::components::common::functions::wrapper::JabraSession;
+
+    JabraSession::extract().await?;
+    Ok(std::env::var("SPOT_REPRICE_THRESHOLD_BPS")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|bps| *bps >= 0.0)
+        .unwrap_or(25.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staleness() {
+        let price = SpotPrice::new(
+            "BTC-USD".to_string(),
+            64_000.5,
+            "coinbase".to_string(),
+            1_000,
+            30_000,
+        );
+        assert_eq!(price.max_age_ms(), 30_000);
+        assert_eq!(price.age_ms(11_000), 10_000);
+        assert!(!price.is_stale_at(31_000));
+        assert!(price.is_stale_at(31_001));
+        // A missing price is never usable
+        assert!(SpotPrice::default().is_stale_at(0));
+    }
+
+    #[test]
+    fn test_moved_beyond() {
+        assert!(!moved_beyond(100.0, 100.25, 25.0));
+        assert!(moved_beyond(100.0, 100.26, 25.0));
+        assert!(moved_beyond(100.0, 99.7, 25.0));
+        assert!(moved_beyond(0.0, 100.0, 25.0));
+    }
+
+    #[test]
+    fn test_confirm() {
+        let staged = SpotPrice::new(
+            "BTC-USD".to_string(),
+            100.0,
+            "coinbase".to_string(),
+            1_000,
+            30_000,
+        );
+        let tick = SpotPrice::new(
+            "BTC-USD".to_string(),
+            100.1,
+            "coinbase".to_string(),
+            50_000,
+            30_000,
+        );
+        let confirmed = staged.confirm(&tick, 25.0).unwrap();
+        // The staged spot stays, only its age is renewed
+        assert_eq!(confirmed.price, 100.0);
+        assert_eq!(confirmed.timestamp, 50_000);
+        assert!(!confirmed.is_stale_at(60_000));
+        assert_eq!(staged.confirm(&tick, 5.0), None);
+    }
+}
@@ -0,0 +1,201 @@
+use leptos::*;
+
+use crate::{
+    components::common::models::counterparty_model::{
+        list_counter_parties, save_counter_party, CounterPartyEntry, CounterPartyFields,
+    },
+    errors::JabraError,
+};
+
+const INPUT_CLASS: &str =
+    "input input-sm text-xs input-bordered w-full max-w-xs rounded hover:shadow-md";
+
+/// Expiry offered for a new counterparty.
+const DEFAULT_EXPIRY: &str = "10:00:00";
+
+fn new_counterparty() -> CounterPartyFields {
+    CounterPartyFields {
+        default_expiry: DEFAULT_EXPIRY.to_string(),
+        is_active: true,
+        ..Default::default()
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn Counterparties() -> impl IntoView {
+    let refresh = RwSignal::new(0_u32);
+    let counterparties_resource = create_resource(refresh, move |_| list_counter_parties());
+
+    // The counterparty in the form, `None` while creating a new one
+    let editing_id = RwSignal::new(None::<u16>);
+    let form = RwSignal::new(new_counterparty());
+
+    let save_action = create_action(|(id, fields): &(Option<u16>, CounterPartyFields)| {
+        let (id, fields) = (*id, fields.clone());
+        async move { save_counter_party(id, fields).await }
+    });
+    let is_pending = save_action.pending();
+    let value = save_action.value();
+
+    create_effect(move |_| {
+        if let Some(Ok(_)) = value.get() {
+            editing_id.set(None);
+            form.set(new_counterparty());
+            refresh.update(|r| *r += 1);
+        }
+    });
+
+    let on_edit = Callback::new(move |entry: CounterPartyEntry| {
+        editing_id.set(Some(entry.id));
+        form.set(entry.fields);
+    });
+    let on_toggle_active = Callback::new(move |entry: CounterPartyEntry| {
+        let fields = CounterPartyFields {
+            is_active: !entry.fields.is_active,
+            ..entry.fields
+        };
+        save_action.dispatch((Some(entry.id), fields));
+    });
+
+    let on_cancel = move |_| {
+        editing_id.set(None);
+        form.set(new_counterparty());
+    };
+
+    view! {
+        <div class="p-6">
+            <span class="text-lg text-success font-normal pl-1">Counterparties</span>
+            <div class="rounded-lg bg-base-300 mt-2 p-4 shadow-lg">
+                <div class="text-xs text-success pb-2">
+                    {move || match editing_id.get() {
+                        Some(_) => format!("EDIT {}", form.with(|f| f.ticker.clone())),
+                        None => "NEW COUNTERPARTY".to_string(),
+                    }}
+                </div>
+                <div class="flex flex-row flex-wrap gap-4 items-end">
+                    <div>
+                        <label class="label"><span class="text-xs text-success">Ticker</span></label>
+                        <input class=INPUT_CLASS type="text" placeholder="BITBOX"
+                            prop:value=move || form.with(|f| f.ticker.clone())
+                            on:input=move |ev| form.update(|f| f.ticker = event_target_value(&ev))/>
+                    </div>
+                    <div>
+                        <label class="label"><span class="text-xs text-success">Name</span></label>
+                        <input class=INPUT_CLASS type="text"
+                            prop:value=move || form.with(|f| f.name.clone())
+                            on:input=move |ev| form.update(|f| f.name = event_target_value(&ev))/>
+                    </div>
+                    <div>
+                        <label class="label"><span class="text-xs text-success">Short Name</span></label>
+                        <input class=INPUT_CLASS type="text"
+                            prop:value=move || form.with(|f| f.short_name.clone().unwrap_or_default())
+                            on:input=move |ev| form.update(|f| f.short_name = Some(event_target_value(&ev)))/>
+                    </div>
+                    <div>
+                        <label class="label"><span class="text-xs text-success">Default Expiry (UTC)</span></label>
+                        <input class=INPUT_CLASS type="time" step="1"
+                            prop:value=move || form.with(|f| f.default_expiry.clone())
+                            on:input=move |ev| form.update(|f| f.default_expiry = event_target_value(&ev))/>
+                    </div>
+                    <label class="label cursor-pointer gap-2">
+                        <span class="text-xs text-success">Exchange</span>
+                        <input type="checkbox" class="checkbox checkbox-sm checkbox-success"
+                            prop:checked=move || form.with(|f| f.is_exchange)
+                            on:change=move |ev| form.update(|f| f.is_exchange = event_target_checked(&ev))/>
+                    </label>
+                    <label class="label cursor-pointer gap-2">
+                        <span class="text-xs text-success">Active</span>
+                        <input type="checkbox" class="checkbox checkbox-sm checkbox-success"
+                            prop:checked=move || form.with(|f| f.is_active)
+                            on:change=move |ev| form.update(|f| f.is_active = event_target_checked(&ev))/>
+                    </label>
+                    <button class="btn btn-sm btn-success" disabled=is_pending
+                        on:click=move |_| save_action.dispatch((editing_id.get_untracked(), form.get_untracked()))>
+                        {move || match is_pending() {
+                            true => view! { <span class="loading loading-spinner loading-sm"></span> }.into_view(),
+                            false => "SAVE".into_view(),
+                        }}
+                    </button>
+                    <Show when = move || editing_id.get().is_some()>
+                        <button class="btn btn-sm btn-ghost" on:click=on_cancel>CANCEL</button>
+                    </Show>
+                </div>
+                {
+                    move || match value.get() {
+                        Some(Err(e)) => view! {
+                            <p class="text-sm text-error pt-2">{JabraError::from_server_fn_error(&e).to_string()}</p>
+                        }.into_view(),
+                        _ => ().into_view(),
+                    }
+                }
+            </div>
+            <div class="rounded-md bg-base-300 mt-4 overflow-x-auto">
+                <Transition fallback = move || view! {<span class="loading loading-bars loading-sm text-success"></span>}>
+                    {
+                        move || counterparties_resource.get().map(|res| match res {
+                            Ok(entries) => view! {
+                                <CounterpartyTable entries = entries on_edit = on_edit on_toggle_active = on_toggle_active/>
+                            }.into_view(),
+                            Err(e) => view! {
+                                <p class="p-4 text-sm text-error">{JabraError::from_server_fn_error(&e).to_string()}</p>
+                            }.into_view(),
+                        })
+                    }
+                </Transition>
+            </div>
+        </div>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn CounterpartyTable(
+    entries: Vec<CounterPartyEntry>,
+    on_edit: Callback<CounterPartyEntry>,
+    on_toggle_active: Callback<CounterPartyEntry>,
+) -> impl IntoView {
+    view! {
+        <table class = "table table-zebra table-xs">
+            <thead class = "text-success font-extralight bg-base-300">
+                <tr class="border-y border-y-base-100">
+                    <th>TICKER</th>
+                    <th>NAME</th>
+                    <th>SHORT NAME</th>
+                    <th>EXCHANGE</th>
+                    <th>DEFAULT EXPIRY (UTC)</th>
+                    <th>STATUS</th>
+                    <th></th>
+                </tr>
+            </thead>
+            <tbody>
+                {entries.into_iter().map(|entry| {
+                    let edit_entry = entry.clone();
+                    let toggle_entry = entry.clone();
+                    let fields = entry.fields;
+                    view! {
+                        <tr class="hover">
+                            <td>{fields.ticker}</td>
+                            <td>{fields.name}</td>
+                            <td>{fields.short_name.unwrap_or("-".to_string())}</td>
+                            <td>{if fields.is_exchange { "Yes" } else { "No" }}</td>
+                            <td>{fields.default_expiry}</td>
+                            <td>
+                                {match fields.is_active {
+                                    true => view! {<span class="badge badge-success badge-sm">"active"</span>}.into_view(),
+                                    false => view! {<span class="badge badge-ghost badge-sm">"inactive"</span>}.into_view(),
+                                }}
+                            </td>
+                            <td class="flex gap-2">
+                                <button class="btn btn-xs btn-ghost" on:click=move |_| on_edit.call(edit_entry.clone())>EDIT</button>
+                                <button class="btn btn-xs btn-ghost" on:click=move |_| on_toggle_active.call(toggle_entry.clone())>
+                                    {if fields.is_active { "DEACTIVATE" } else { "ACTIVATE" }}
+                                </button>
+                            </td>
+                        </tr>
+                    }
+                }).collect_view()}
+            </tbody>
+        </table>
+    }
+}
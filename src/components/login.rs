@@ -1,9 +1,11 @@
 use leptos::*;
-use leptos_router::ActionForm;
+use leptos_router::{use_location, ActionForm};
 
 use crate::{
     app::{HasError, Refetcher},
     components::common::components::input::{InputPassword, InputText},
+    errors::JabraError,
+    route_guard::return_to_from_query,
 };
 
 #[allow(non_snake_case)]
@@ -26,7 +28,7 @@ pub fn Login() -> impl IntoView {
                                 custom_class="input input-sm w-full bg-white rounded hover:shadow-md text-black border-gray-800 shadow-md".to_string()
                             />
                             <label class="label">
-                                <a href="#" class="label-text-alt link link-hover">Forgot password?</a>
+                                <a href="/forgot_password" class="label-text-alt link link-hover">Forgot password?</a>
                             </label>
                         </LoginIsland>
                     </div>
@@ -55,6 +57,13 @@ pub fn LoginIsland(children: Children) -> impl IntoView {
     let login_action: Action<DirectusLogin, Result<bool, ServerFnError>> =
         create_server_action::<DirectusLogin>();
     let is_pending = login_action.pending();
+    let query = use_location().search;
+    // Once Directus asks for a one-time code the field stays visible for the retries
+    let otp_required = create_rw_signal(false);
+    let login_error = move || match login_action.value().get() {
+        Some(Err(e)) => Some(JabraError::from_server_fn_error(&e)),
+        _ => None,
+    };
 
     create_effect(move |_| {
         log::info!("Is_pending: {:?}", is_pending());
@@ -72,7 +81,12 @@ pub fn LoginIsland(children: Children) -> impl IntoView {
                         use_context::<HasError>().unwrap().0.set(true);
                     }
                 }
-                Err(_) => {
+                Err(e) => {
+                    if let JabraError::TwoFactorRequired { .. } =
+                        JabraError::from_server_fn_error(&e)
+                    {
+                        otp_required.set(true);
+                    }
                     use_context::<Refetcher>().unwrap().0.set(false);
                     use_context::<HasError>().unwrap().0.set(true);
                 }
@@ -82,7 +96,21 @@ pub fn LoginIsland(children: Children) -> impl IntoView {
 
     view! {
         <ActionForm action = login_action>
+            <input type="hidden" name="return_to" value = move || return_to_from_query(&query.get())/>
             {children()}
+            <Show when = move || otp_required.get()>
+                <InputText
+                    name="otp".to_string()
+                    label="One-time code".to_string()
+                    placeholder="123456".to_string()
+                    custom_class="input input-sm w-full bg-white rounded hover:shadow-md text-black border-gray-800 shadow-md".to_string()
+                />
+            </Show>
+            {
+                move || login_error().map(|e| view! {
+                    <p class="text-xs text-error mt-2">{e.to_string()}</p>
+                })
+            }
             {
                 move || match is_pending() {
                     true => view! {
@@ -102,50 +130,168 @@ pub fn LoginIsland(children: Children) -> impl IntoView {
 }
 
 #[server(DirectusLogin, "/api")]
-pub async fn directus_login(userid: String, password: String) -> Result<bool, ServerFnError> {
-    use super::models::{DirectusLoginRequest, DirectusLoginResponse};
-    use super::wrapper::{self, call_and_parse, HttpMethod, JabraCookie};
+pub async fn directus_login(
+    userid: String,
+    password: String,
+    return_to: String,
+    otp: Option<String>,
+) -> Result<bool, ServerFnError> {
+    use super::common::functions::rate_limit::{password_throttle, RateLimiter};
+    use super::common::functions::security::SecurityEvent;
+    use super::common::functions::upstream::Upstream;
+    use super::common::functions::wrapper::{
+        self, call, call_and_parse, client_ip, HttpMethod, JabraCookie, SESSION_COOKIE_NAME,
+    };
+    use super::common::models::permission_model::fetch_permissions;
+    use super::models::{DirectusLoginRequest, DirectusLoginResponse, DirectusLogoutRequest};
     use crate::errors::JabraError;
+    use crate::route_guard::safe_return_to;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+
+    // Failures lock the account whichever address they come from, while the address limit
+    // stops one client from spraying passwords across many accounts
+    static IP_LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    let email_throttle = password_throttle();
+    let ip_limiter = IP_LIMITER.get_or_init(|| RateLimiter::new(30, Duration::from_secs(5 * 60)));
+
+    let email = userid.trim().to_lowercase();
+    let ip = client_ip();
+    if let Err(e) = ip_limiter
+        .check(&ip)
+        .and_then(|_| email_throttle.check(&email))
+    {
+        if let JabraError::AccountLocked { retry_after_secs }
+        | JabraError::TooManyRequests { retry_after_secs } = e
+        {
+            SecurityEvent::LoginBlocked {
+                email,
+                ip,
+                locked: matches!(e, JabraError::AccountLocked { .. }),
+                retry_after_secs,
+            }
+            .log();
+        }
+        return Err(e.into());
+    }
 
     let url = std::env::var("DIRECTUSURL").unwrap();
     let path = format!("{}/auth/login", url);
-    let email = userid.clone();
-    let login_request = DirectusLoginRequest::new(userid.into(), password.into());
+    let otp = otp
+        .map(|otp| otp.trim().to_string())
+        .filter(|otp| !otp.is_empty());
+    let has_otp = otp.is_some();
+    let login_request =
+        DirectusLoginRequest::new(userid.trim().to_string(), password).with_otp(otp);
     let response = call_and_parse::<DirectusLoginRequest, DirectusLoginResponse>(
         Some(login_request),
         path,
         reqwest::header::HeaderMap::new(),
         HttpMethod::POST,
+        Upstream::Directus,
     )
     .await;
 
     match response {
         Ok(res) => {
-            // Calculate expiration time in millis, subract 2 minute to be safe
-            // Why 10 minutes? There are other api resource that are automatically when users navigate to a certain page
-            // Only those API calls in action will have the refresh token
-            // Which means during the manual submit, the refresh token is used
-            // 10 minutes will act as a buffer for those action
-
-            let expiration_time =
-                chrono::Utc::now().timestamp_millis() + res.data.expires - 600_000;
-            // log::info!("Expiration Time: {}", expiration_time);
+            email_throttle.record_success(&email);
+            SecurityEvent::LoginSucceeded {
+                email: email.clone(),
+                ip,
+            }
+            .log();
+
+            // A session without its permissions would hide every page behind the guard, so
+            // the login fails instead and the Directus session it opened is revoked again
+            let access = match fetch_permissions(&res.data.access_token).await {
+                Ok(access) => access,
+                Err(e) => {
+                    log::error!("Permission Error: {}", e.to_string());
+                    let revoked = call::<DirectusLogoutRequest>(
+                        Some(DirectusLogoutRequest::new(res.data.refresh_token)),
+                        format!("{}/auth/logout", url),
+                        reqwest::header::HeaderMap::new(),
+                        HttpMethod::POST,
+                        Upstream::Directus,
+                    )
+                    .await;
+                    if let Err(revoke_error) = revoked {
+                        log::warn!("Logout Error: {}", revoke_error.to_string());
+                    }
+                    return Err(e.into());
+                }
+            };
             let jabra_cookie = JabraCookie::new(
                 email,
                 res.data.access_token,
                 res.data.refresh_token,
-                expiration_time,
-            );
-            wrapper::set_jabra_cookie(Some(jabra_cookie), "jabra-admin-portal-v2".to_string())
-                .await;
-            leptos_axum::redirect("/quote_builder");
+                JabraCookie::expiry_from(res.data.expires),
+            )
+            .with_permissions(access.permissions)
+            .with_role(access.role_id);
+            wrapper::set_jabra_cookie(Some(jabra_cookie), SESSION_COOKIE_NAME.to_string()).await;
+            leptos_axum::redirect(&safe_return_to(&return_to));
             Ok(true)
         }
         Err(e) => {
-            log::info!("Login Error: {}", e.to_string());
-            Err(ServerFnError::ServerError(
-                JabraError::LoginError.to_string(),
-            ))
+            let (error, reason) = match e {
+                JabraError::Unauthorized(_) | JabraError::Validation { .. } => {
+                    (JabraError::LoginError, "invalid_credentials")
+                }
+                JabraError::TwoFactorRequired { .. } => (
+                    JabraError::TwoFactorRequired {
+                        invalid_code: has_otp,
+                    },
+                    "invalid_otp",
+                ),
+                e => {
+                    log::error!("Login Error: {}", e.to_string());
+                    return Err(e.into());
+                }
+            };
+            // Asking for the one-time code is part of a normal TFA login, not a failure
+            if has_otp || reason == "invalid_credentials" {
+                SecurityEvent::LoginFailed {
+                    email,
+                    ip,
+                    reason: reason.to_string(),
+                    failures: email_throttle.record_failure(&email),
+                }
+                .log();
+            }
+            Err(error.into())
+        }
+    }
+}
+
+/// Revokes the refresh token in Directus and expires the session cookie.
+/// The cookie is cleared even if Directus rejects the token, so a stale session never survives a logout.
+/// The session is read without refreshing it: an expired access token doesn't matter to revoke it.
+
+#[server(Logout, "/api")]
+pub async fn logout() -> Result<bool, ServerFnError> {
+    use super::common::functions::upstream::Upstream;
+    use super::common::functions::wrapper::{self, call, HttpMethod, JabraSession};
+    use super::models::DirectusLogoutRequest;
+
+    if let Ok(session) = JabraSession::from_cookie_header(&wrapper::request_cookie_header()) {
+        crate::spot_stream::end_streams(&session.cookie.refresh_token);
+        let url = std::env::var("DIRECTUSURL").unwrap();
+        let path = format!("{}/auth/logout", url);
+        let response = call::<DirectusLogoutRequest>(
+            Some(DirectusLogoutRequest::new(session.cookie.refresh_token)),
+            path,
+            reqwest::header::HeaderMap::new(),
+            HttpMethod::POST,
+            Upstream::Directus,
+        )
+        .await;
+        if let Err(e) = response {
+            log::warn!("Logout Error: {}", e.to_string());
         }
     }
+
+    wrapper::clear_jabra_cookie().await;
+    leptos_axum::redirect("/login");
+    Ok(true)
 }
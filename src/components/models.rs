@@ -4,11 +4,23 @@ use serde::{Deserialize, Serialize};
 pub struct DirectusLoginRequest {
     pub email: String,
     pub password: String,
+    /// One-time code, required by Directus for users with TFA enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub otp: Option<String>,
 }
 
 impl DirectusLoginRequest {
     pub fn new(email: String, password: String) -> Self {
-        Self { email, password }
+        Self {
+            email,
+            password,
+            otp: None,
+        }
+    }
+
+    pub fn with_otp(mut self, otp: Option<String>) -> Self {
+        self.otp = otp;
+        self
     }
 }
 
@@ -23,3 +35,58 @@ pub struct DirectusLoginResponseData {
     pub expires: i64,
     pub refresh_token: String,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DirectusLogoutRequest {
+    pub refresh_token: String,
+    pub mode: String,
+}
+
+impl DirectusLogoutRequest {
+    pub fn new(refresh_token: String) -> Self {
+        Self {
+            refresh_token,
+            mode: "json".to_string(),
+        }
+    }
+}
+
+/// Body of Directus `/auth/password/request`.
+/// Directus appends `?token=...` to `reset_url` in the email it sends.
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DirectusPasswordRequest {
+    pub email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DirectusPasswordReset {
+    pub token: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DirectusTfaGenerateRequest {
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct DirectusTfaSecret {
+    pub secret: String,
+    pub otpauth_url: String,
+}
+
+/// This is the response struct for `/users/me/tfa/generate`.
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct DirectusTfaGenerateResponse {
+    pub data: DirectusTfaSecret,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DirectusTfaEnableRequest {
+    pub secret: String,
+    pub otp: String,
+}
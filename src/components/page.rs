@@ -2,7 +2,7 @@ use leptos::*;
 use leptos_router::use_location;
 
 use crate::components::{
-    component_testing::ComponentTesting, content::{Content1, Content2}, login::Login, perp_aggregator::page::PerpAggregator, quote_builder::page::QuoteBuilder, sidebar::Sidebar
+    audit_log::AuditLog, component_testing::ComponentTesting, content::{Content1, Content2}, counterparties::Counterparties, login::Login, password_reset::{ForgotPassword, ResetPassword}, perp_aggregator::page::PerpAggregator, quote_builder::page::QuoteBuilder, reference_data::ReferenceData, settings::Settings, sidebar::Sidebar, supported_pairs::SupportedPairs
 };
 
 #[allow(non_snake_case)]
@@ -24,11 +24,18 @@ pub fn Page() -> impl IntoView {
                         match location.get().as_str() {
                             "/" => view!{<Content1 />},
                             "/login" => view!{<Login />},
+                            "/forgot_password" => view!{<ForgotPassword />},
+                            "/reset_password" => view!{<ResetPassword />},
                             "/quote_builder" => view!{<QuoteBuilder />},
                             "/active_quotes" => view!{<Content1 />},
                             "/positions" => view!{<Content2 />},
                             "/trade_history" => view!{<Content2 />},
+                            "/settings" => view!{<Settings />},
                             "/components" => view!{<ComponentTesting />},
+                            "/audit_log" => view!{<AuditLog />},
+                            "/reference_data" => view!{<ReferenceData />},
+                            "/counterparties" => view!{<Counterparties />},
+                            "/supported_pairs" => view!{<SupportedPairs />},
                             "/perp-aggregator" => view!{<PerpAggregator />},
 
                             _ => view!{<div></div>}.into_view(),
@@ -0,0 +1,334 @@
+use leptos::*;
+use leptos_router::{use_location, ActionForm};
+
+use crate::{
+    components::common::components::input::{InputPassword, InputText},
+    errors::{FieldError, JabraError},
+};
+
+/// Minimum length accepted for a new password.
+pub const MIN_PASSWORD_LENGTH: usize = 12;
+
+const INPUT_CLASS: &str =
+    "input input-sm w-full bg-white rounded hover:shadow-md text-black border-gray-800 shadow-md";
+
+pub fn validate_email(email: &str) -> Result<(), JabraError> {
+    let valid = match email.trim().split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !email.trim().contains(char::is_whitespace)
+        }
+        None => false,
+    };
+    match valid {
+        true => Ok(()),
+        false => Err(validation_error("email", "Enter a valid email address.")),
+    }
+}
+
+/// Checks a new password against the portal policy before it is sent to Directus.
+pub fn validate_new_password(password: &str, confirm_password: &str) -> Result<(), JabraError> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(validation_error(
+            "password",
+            &format!(
+                "Password must be at least {} characters long.",
+                MIN_PASSWORD_LENGTH
+            ),
+        ));
+    }
+    if !password.chars().any(|c| c.is_alphabetic()) || !password.chars().any(|c| c.is_numeric()) {
+        return Err(validation_error(
+            "password",
+            "Password must contain letters and numbers.",
+        ));
+    }
+    if password != confirm_password {
+        return Err(validation_error(
+            "confirm_password",
+            "Passwords do not match.",
+        ));
+    }
+    Ok(())
+}
+
+fn validation_error(field: &str, message: &str) -> JabraError {
+    JabraError::Validation {
+        message: message.to_string(),
+        fields: vec![FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        }],
+    }
+}
+
+/// Reads the reset `token` Directus appends to the link in the reset email.
+pub fn token_from_query(query: &str) -> String {
+    let params: Vec<(String, String)> = serde_urlencoded::from_str(query).unwrap_or_default();
+    params
+        .into_iter()
+        .find(|(key, _)| key == "token")
+        .map(|(_, value)| value)
+        .unwrap_or_default()
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn PasswordCard(children: Children) -> impl IntoView {
+    view! {
+        <div class="h-full flex items-center justify-center px-4">
+            <div class="card flex-shrink-0 w-full max-w-sm shadow-lg bg-base-100">
+                <div class="card-body">
+                    {children()}
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn ForgotPassword() -> impl IntoView {
+    let request_action: Action<RequestPasswordReset, Result<bool, ServerFnError>> =
+        create_server_action::<RequestPasswordReset>();
+    let is_pending = request_action.pending();
+    let value = request_action.value();
+
+    view! {
+        <PasswordCard>
+            <h2 class="card-title">Forgot password</h2>
+            <p class="text-sm">"Enter your email and we will send you a link to reset your password."</p>
+            {
+                move || match value.get() {
+                    Some(Ok(_)) => view! {
+                        <p class="text-sm text-success">"If the email belongs to an account, a reset link is on its way."</p>
+                    }.into_view(),
+                    Some(Err(e)) => view! {
+                        <p class="text-sm text-error">{JabraError::from_server_fn_error(&e).to_string()}</p>
+                    }.into_view(),
+                    None => ().into_view(),
+                }
+            }
+            <ActionForm action = request_action>
+                <InputText
+                    name="email".to_string()
+                    label="Email".to_string()
+                    custom_class=INPUT_CLASS.to_string()
+                />
+                <div class="form-control mt-6">
+                    <button type="submit" class="btn rounded btn-block btn-success" disabled=is_pending>
+                        {move || match is_pending() {
+                            true => view! { <span class="loading loading-spinner loading-sm"></span> }.into_view(),
+                            false => "SEND RESET LINK".into_view(),
+                        }}
+                    </button>
+                </div>
+            </ActionForm>
+            <label class="label">
+                <a href="/login" class="label-text-alt link link-hover">Back to login</a>
+            </label>
+        </PasswordCard>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn ResetPassword() -> impl IntoView {
+    let reset_action: Action<ConfirmPasswordReset, Result<bool, ServerFnError>> =
+        create_server_action::<ConfirmPasswordReset>();
+    let is_pending = reset_action.pending();
+    let value = reset_action.value();
+    let query = use_location().search;
+    let token = move || token_from_query(&query.get());
+
+    view! {
+        <PasswordCard>
+            <h2 class="card-title">Reset password</h2>
+            {
+                move || match (token().is_empty(), value.get()) {
+                    (_, Some(Ok(_))) => view! {
+                        <p class="text-sm text-success">"Your password has been reset. You can now log in."</p>
+                    }.into_view(),
+                    (true, _) => view! {
+                        <p class="text-sm text-error">"This reset link is invalid. Request a new one."</p>
+                    }.into_view(),
+                    (false, Some(Err(e))) => view! {
+                        <p class="text-sm text-error">{JabraError::from_server_fn_error(&e).to_string()}</p>
+                    }.into_view(),
+                    (false, None) => ().into_view(),
+                }
+            }
+            <Show when = move || !token().is_empty() && !matches!(value.get(), Some(Ok(_)))>
+                <ActionForm action = reset_action>
+                    <input type="hidden" name="token" value = token/>
+                    <InputPassword
+                        name="password".to_string()
+                        label="New password".to_string()
+                        custom_class=INPUT_CLASS.to_string()
+                    />
+                    <InputPassword
+                        name="confirm_password".to_string()
+                        label="Confirm password".to_string()
+                        custom_class=INPUT_CLASS.to_string()
+                    />
+                    <div class="form-control mt-6">
+                        <button type="submit" class="btn rounded btn-block btn-success" disabled=is_pending>
+                            {move || match is_pending() {
+                                true => view! { <span class="loading loading-spinner loading-sm"></span> }.into_view(),
+                                false => "RESET PASSWORD".into_view(),
+                            }}
+                        </button>
+                    </div>
+                </ActionForm>
+            </Show>
+            <label class="label">
+                <a href="/forgot_password" class="label-text-alt link link-hover">Request a new link</a>
+                <a href="/login" class="label-text-alt link link-hover">Back to login</a>
+            </label>
+        </PasswordCard>
+    }
+}
+
+/// Asks Directus to email a password reset link.
+/// Always succeeds for well formed emails so the form cannot be used to find out which accounts exist.
+
+#[server(RequestPasswordReset, "/api")]
+pub async fn request_password_reset(email: String) -> Result<bool, ServerFnError> {
+    use super::common::functions::rate_limit::RateLimiter;
+    use super::common::functions::upstream::Upstream;
+    use super::common::functions::wrapper::{call, client_ip, HttpMethod};
+    use super::models::DirectusPasswordRequest;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+
+    // Each email gets a few links whatever address asks for them, so rotating addresses
+    // cannot flood an inbox, and each address is capped across emails
+    static EMAIL_LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    static IP_LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    let email_limiter =
+        EMAIL_LIMITER.get_or_init(|| RateLimiter::new(3, Duration::from_secs(15 * 60)));
+    let ip_limiter = IP_LIMITER.get_or_init(|| RateLimiter::new(10, Duration::from_secs(15 * 60)));
+
+    let email = email.trim().to_lowercase();
+    validate_email(&email)?;
+    ip_limiter.check(&client_ip())?;
+    email_limiter.check(&email)?;
+
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let path = format!("{}/auth/password/request", url);
+    // The reset url must be in the Directus PASSWORD_RESET_URL_ALLOW_LIST
+    let reset_url = std::env::var("PORTAL_URL")
+        .ok()
+        .map(|portal| format!("{}/reset_password", portal.trim_end_matches('/')));
+
+    let response = call::<DirectusPasswordRequest>(
+        Some(DirectusPasswordRequest { email, reset_url }),
+        path,
+        reqwest::header::HeaderMap::new(),
+        HttpMethod::POST,
+        Upstream::Directus,
+    )
+    .await;
+    match response {
+        Ok(_) => Ok(true),
+        Err(e @ JabraError::Upstream { .. }) | Err(e @ JabraError::ReqwestError(_)) => {
+            log::error!("Password Request Error: {}", e.to_string());
+            Err(e.into())
+        }
+        Err(e) => {
+            log::info!("Password Request Error: {}", e.to_string());
+            Ok(true)
+        }
+    }
+}
+
+/// Sets a new password with the token from the reset email.
+
+#[server(ConfirmPasswordReset, "/api")]
+pub async fn confirm_password_reset(
+    token: String,
+    password: String,
+    confirm_password: String,
+) -> Result<bool, ServerFnError> {
+    use super::common::functions::rate_limit::RateLimiter;
+    use super::common::functions::upstream::Upstream;
+    use super::common::functions::wrapper::{call, client_ip, HttpMethod};
+    use super::models::DirectusPasswordReset;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+
+    static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    let limiter = LIMITER.get_or_init(|| RateLimiter::new(10, Duration::from_secs(15 * 60)));
+
+    limiter.check(&client_ip())?;
+    if token.trim().is_empty() {
+        return Err(
+            validation_error("token", "This reset link is invalid. Request a new one.").into(),
+        );
+    }
+    validate_new_password(&password, &confirm_password)?;
+
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let path = format!("{}/auth/password/reset", url);
+    let response = call::<DirectusPasswordReset>(
+        Some(DirectusPasswordReset { token, password }),
+        path,
+        reqwest::header::HeaderMap::new(),
+        HttpMethod::POST,
+        Upstream::Directus,
+    )
+    .await;
+    match response {
+        Ok(res) => Ok(res),
+        Err(JabraError::Unauthorized(_)) | Err(JabraError::Forbidden(_)) => {
+            Err(validation_error("token", "This reset link has expired. Request a new one.").into())
+        }
+        Err(e) => {
+            log::error!("Password Reset Error: {}", e.to_string());
+            Err(e.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_email() {
+        assert!(validate_email("trader@jabra.io").is_ok());
+        assert!(validate_email(" trader@jabra.io ").is_ok());
+        for email in [
+            "",
+            "trader",
+            "@jabra.io",
+            "trader@jabra",
+            "trader@.io",
+            "tra der@jabra.io",
+        ] {
+            assert!(validate_email(email).is_err(), "{}", email);
+        }
+    }
+
+    #[test]
+    fn test_validate_new_password() {
+        assert!(validate_new_password("correct horse 42", "correct horse 42").is_ok());
+        assert!(validate_new_password("short1", "short1").is_err());
+        assert!(validate_new_password("onlyletterssss", "onlyletterssss").is_err());
+        match validate_new_password("correct horse 42", "correct horse 43") {
+            Err(JabraError::Validation { fields, .. }) => {
+                assert_eq!(fields[0].field, "confirm_password")
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_token_from_query() {
+        assert_eq!(token_from_query("token=abc.def-ghi"), "abc.def-ghi");
+        assert_eq!(token_from_query("foo=bar"), "");
+    }
+}
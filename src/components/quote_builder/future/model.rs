@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::components::common::models::common_models::{
-    Currency, CurrencyPair, OptionInstrumentSpecification,
+use crate::components::common::models::{
+    common_models::{Currency, CurrencyPair, OptionInstrumentSpecification},
+    money_model::{Price, Quantity},
 };
 
 /// Struct for Futures Quote Data.
@@ -12,8 +13,8 @@ pub struct FuturesQuote {
     pub rfq_type: String,
     pub expiry: String,
     pub pair: CurrencyPair,
-    pub amount: f64,
-    pub price: f64,
+    pub amount: Quantity,
+    pub price: Price,
     pub quote_expiry: String,
     pub counterparty: String,
     pub quote_status: String,
@@ -26,8 +27,8 @@ impl FuturesQuote {
         rfq_type: String,
         expiry: String,
         pair: CurrencyPair,
-        amount: f64,
-        price: f64,
+        amount: Quantity,
+        price: Price,
         quote_expiry: String,
         counterparty: String,
         quote_status: String,
@@ -80,8 +81,8 @@ impl Default for FuturesQuote {
                     },
                 },
             },
-            amount: 3.33,
-            price: 30326.21,
+            amount: Quantity::from_f64(3.33),
+            price: Price::from_f64(30326.21),
             quote_expiry: "2023-12-12".to_string(),
             counterparty: "Bitbox".to_string(),
             quote_status: "active".to_string(),
@@ -135,8 +136,8 @@ pub struct ExtractedFuturesQuote {
     pub rfq_type: String,
     pub expiry: String,
     pub pair: String,
-    pub amount: f64,
-    pub price: f64,
+    pub amount: Quantity,
+    pub price: Price,
     pub quote_expiry: String,
     pub counterparty: String,
     pub gtc: bool,
@@ -198,18 +199,18 @@ pub fn sort(
         },
         QuoteSort::Amount => match sort_type {
             true => {
-                data_table.sort_by(|a, b| a.amount.partial_cmp(&b.amount).unwrap());
+                data_table.sort_by(|a, b| a.amount.cmp(&b.amount));
             }
             false => {
-                data_table.sort_by(|a, b| b.amount.partial_cmp(&a.amount).unwrap());
+                data_table.sort_by(|a, b| b.amount.cmp(&a.amount));
             }
         },
         QuoteSort::Price => match sort_type {
             true => {
-                data_table.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap());
+                data_table.sort_by(|a, b| a.price.cmp(&b.price));
             }
             false => {
-                data_table.sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap());
+                data_table.sort_by(|a, b| b.price.cmp(&a.price));
             }
         },
         QuoteSort::QuoteExpiry => match sort_type {
@@ -6,17 +6,21 @@ use crate::components::{
         components::modals::{ErrorModal, SuccessModalRefetch},
         functions::{
             helpers::{calculate_time_difference, change_day, extract_date_as_string, get_expiry},
-            precision::{format_with_specs, RoundType},
-            utils::fetch_unified_configuration,
+            precision::{
+                convert_to_decimal, fit_to_specs, InstrumentSpecsResponse, InstrumentType, RoundType,
+            },
+            utils::{fetch_instrument_specs, fetch_unified_configuration},
         },
         models::{
             common_models::{Currency, CurrencyPair, UnifiedCurrencyPairConfigurationResponse},
+            money_model::{Price, Quantity},
             quote_model::QuotesTab,
             quote_option_model::{ApproveTradeQuoteRequest, ApproveTradeQuoteResponse},
         },
     },
     quote_builder::future::model::{sort, FuturesQuote, QuoteSort},
 };
+use crate::errors::JabraError;
 
 use super::model::{ExtractedFuturesQuote, FuturesQuoteHistory};
 
@@ -25,7 +29,9 @@ use super::model::{ExtractedFuturesQuote, FuturesQuoteHistory};
 pub fn FuturesGeneratorActivity() -> impl IntoView {
     let currency_pair = RwSignal::new(CurrencyPair::default());
     let config_resource = create_blocking_resource(|| (), move |_| fetch_unified_configuration());
+    let specs_resource = create_blocking_resource(|| (), move |_| fetch_instrument_specs());
     let configuration = RwSignal::new(UnifiedCurrencyPairConfigurationResponse::default());
+    let specs = RwSignal::new(InstrumentSpecsResponse::default());
     view! {
         <Suspense
             fallback = move || view! {
@@ -35,11 +41,21 @@ pub fn FuturesGeneratorActivity() -> impl IntoView {
             }
         >
             {
-                config_resource.and_then(|conf| {
-                    configuration.set(conf.clone());
-                    view!{
-                        <Futures config = configuration currency_pair = currency_pair/>
-                    }.into_view()
+                move || config_resource.get().map(|res| {
+                    let loaded_specs = specs_resource.get().unwrap_or_else(|| Ok(Default::default()));
+                    match (res, loaded_specs) {
+                        (Ok(conf), Ok(loaded_specs)) => {
+                            configuration.set(conf);
+                            // Without specs the builder keeps the option specs of each currency
+                            specs.set(loaded_specs);
+                            view!{
+                                <Futures config = configuration specs = specs currency_pair = currency_pair/>
+                            }.into_view()
+                        }
+                        (Err(e), _) | (_, Err(e)) => view! {
+                            <p class="p-4 text-sm text-error">{JabraError::from_server_fn_error(&e).to_string()}</p>
+                        }.into_view(),
+                    }
                 })
             }
         </Suspense>
@@ -50,6 +66,7 @@ pub fn FuturesGeneratorActivity() -> impl IntoView {
 #[component]
 pub fn Futures(
     config: RwSignal<UnifiedCurrencyPairConfigurationResponse>,
+    specs: RwSignal<InstrumentSpecsResponse>,
     currency_pair: RwSignal<CurrencyPair>,
 ) -> impl IntoView {
     let default_pair = move || {
@@ -64,6 +81,11 @@ pub fn Futures(
     let currency = RwSignal::new(Currency::default());
 
     let rfq_type = create_rw_signal("".to_string());
+    // The base currency of `pair` with the specs of the selected type, future or perp
+    let specced_base = move |pair: &CurrencyPair| {
+        let instrument_type = InstrumentType::from_rfq_type(&rfq_type.get_untracked());
+        specs.get_untracked().apply(&pair.base, instrument_type)
+    };
     let expiry_date = create_rw_signal(Utc::now() + Duration::days(6));
     let current_date = create_rw_signal(Utc::now());
     let expiry_date_string = create_rw_signal("".to_string());
@@ -71,8 +93,11 @@ pub fn Futures(
     let expiry_date_str_value = move || extract_date_as_string(expiry_date.get());
     let current_date_str_value = move || extract_date_as_string(current_date.get());
 
-    let notional_amount = create_rw_signal(0.0);
-    let price = create_rw_signal(1.0);
+    let notional_amount = create_rw_signal(Quantity::ZERO);
+    let price = create_rw_signal(Price::from_f64(1.0));
+    // Why the last amount or price typed in was changed to fit the instrument specs
+    let amount_note = create_rw_signal(None::<String>);
+    let price_note = create_rw_signal(None::<String>);
     let quote_expiry = create_rw_signal("".to_string());
 
     let expiry_in_min = RwSignal::new(0 as u16);
@@ -139,8 +164,8 @@ pub fn Futures(
         rfq_type.set("".to_string());
         expiry_date_string.set("".to_string());
         currency_pair.set(CurrencyPair::default());
-        notional_amount.set(0.0);
-        price.set(1.0);
+        notional_amount.set(Quantity::ZERO);
+        price.set(Price::from_f64(1.0));
         quote_expiry.set("".to_string());
         expiry_in_min.set(0);
         counterparty.set("".to_string());
@@ -172,6 +197,7 @@ pub fn Futures(
                                 next_step.update(|value| *value += 0)
                             }
                             rfq_type.set(val);
+                            currency.set(specced_base(&currency_pair.get_untracked()));
                         }
                     >
                         <option prop:selected = move || rfq_type.get() == "".to_string() disabled>Select Type</option>
@@ -227,7 +253,7 @@ pub fn Futures(
                                             let val = event_target_value(&e).parse::<u16>().unwrap_or_default();
                                             let pair = config.get().get_currency_pair_by_id(val).unwrap_or_default();
                                             currency_pair.set(pair.clone());
-                                            currency.set(pair.base.clone());
+                                            currency.set(specced_base(&pair));
 
                                             if has_filled.get() == false {
                                                 next_step.set(4)
@@ -240,7 +266,7 @@ pub fn Futures(
                                     {
                                         move || {
                                             // log::info!("Config Resource loaded");
-                                            currency.set(default_pair().base);
+                                            currency.set(specced_base(&default_pair()));
                                             config.get().data.into_iter().map(|i| {
                                                 let i_clone = i.clone();
                                                 view!{
@@ -268,11 +294,17 @@ pub fn Futures(
                             } else {
                                 view! {
                                     <label class = "font-light text-sm">Amount (Notional)</label>
-                                    <input class = "input-sm text-xs text-success block w-full rounded hover:shadow-sm hover:shadow-success bg-base-100 shadow-md" type="number" name="deposit_amount" prop:value = notional_amount prop:min = move || currency.get().order_size() prop:step = move || currency.get().tick_size()
+                                    <input class = "input-sm text-xs text-success block w-full rounded hover:shadow-sm hover:shadow-success bg-base-100 shadow-md" type="number" name="deposit_amount" prop:value = move || notional_amount.get().to_string() prop:min = move || currency.get().order_size() prop:step = move || currency.get().order_size()
                                         on:change = move |e| {
-                                            let val = event_target_value(&e).parse::<f64>().unwrap_or_default();
-                                            let precise_val = format_with_specs(val, currency.get().tick_size(), currency.get().order_size(), RoundType::Floor, false);
-                                            notional_amount.set(precise_val);
+                                            let val = Quantity::parse(&event_target_value(&e)).unwrap_or_default();
+                                            let lot_size = convert_to_decimal(currency.get().order_size());
+                                            match fit_to_specs(val.value(), lot_size, Some(lot_size), RoundType::Floor) {
+                                                Ok(fitted) => {
+                                                    notional_amount.set(Quantity::new(fitted.value));
+                                                    amount_note.set(fitted.note());
+                                                }
+                                                Err(e) => amount_note.set(Some(e.to_string())),
+                                            }
 
                                             if has_filled.get() == false && next_step.get() <= 5 {
                                                 next_step.set(5)
@@ -281,6 +313,9 @@ pub fn Futures(
                                             }
                                         }
                                     />
+                                    <Show when = move || amount_note.get().is_some()>
+                                        <p class = "text-xs text-warning mt-1">{move || amount_note.get().unwrap_or_default()}</p>
+                                    </Show>
                                 }.into_view()
                             }
 
@@ -299,11 +334,17 @@ pub fn Futures(
                             } else {
                                 view! {
                                     <label class = "font-light text-sm">Price</label>
-                                    <input class = "input-sm text-xs text-success block w-full rounded hover:shadow-sm hover:shadow-success bg-base-100 shadow-md" type="number" name="price" prop:value = price prop:min = move || currency.get().order_size() prop:step = move || currency.get().tick_size()
+                                    <input class = "input-sm text-xs text-success block w-full rounded hover:shadow-sm hover:shadow-success bg-base-100 shadow-md" type="number" name="price" prop:value = move || price.get().to_string() prop:min = move || currency.get().tick_size() prop:step = move || currency.get().tick_size()
                                         on:change = move |e| {
-                                            let val = event_target_value(&e).parse::<f64>().unwrap_or_default();
-                                            let precise_val = format_with_specs(val, currency.get().tick_size(), currency.get().order_size(), RoundType::Floor, false);
-                                            price.set(precise_val);
+                                            let val = Price::parse(&event_target_value(&e)).unwrap_or_default();
+                                            let tick_size = convert_to_decimal(currency.get().tick_size());
+                                            match fit_to_specs(val.value(), tick_size, Some(tick_size), RoundType::Floor) {
+                                                Ok(fitted) => {
+                                                    price.set(Price::new(fitted.value));
+                                                    price_note.set(fitted.note());
+                                                }
+                                                Err(e) => price_note.set(Some(e.to_string())),
+                                            }
 
                                             if has_filled.get() == false && next_step.get() <= 6 {
                                                 next_step.set(6)
@@ -312,6 +353,9 @@ pub fn Futures(
                                             }
                                         }
                                     />
+                                    <Show when = move || price_note.get().is_some()>
+                                        <p class = "text-xs text-warning mt-1">{move || price_note.get().unwrap_or_default()}</p>
+                                    </Show>
                                 }.into_view()
                             }
 
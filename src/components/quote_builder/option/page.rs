@@ -1,33 +1,48 @@
 use leptos::*;
+use std::collections::{HashMap, HashSet};
 use web_sys::Event;
 
 use crate::components::common::{
+    components::spot_feed::SpotFeed,
     functions::{
         helpers::{
             create_trade_expiry_in_utc, generate_instrument_name_v2, get_expiry,
-            get_trade_expiration_datetime, get_trade_expiry_date, parse_local_datetime_to_str,
+            get_trade_expiration_datetime, get_trade_expiry_date, get_ttm_until,
+            parse_local_datetime_to_str,
         },
-        precision::{convert_to_decimal, format_with_specs, RoundType},
+        precision::{convert_to_decimal, format_with_specs, InstrumentType, RoundType},
         utils::{
-            add_quote, coin_base_spot, fetch_unified_configuration, sb_counter_parties,
+            add_quote, fetch_instrument_specs, fetch_unified_configuration, sb_counter_parties,
             sb_fetch_estimate_iv, sb_post_qoute_option,
         },
     },
-    models::common_models::{
-        AddQuoteResponse, ClosestOption, CoinBaseSpotPriceResponse, CounterPartyResponse, Currency,
-        CurrencyPair, EstimateIVRequest, EstimateIVResponse, Quote, QuoteOptionRequest,
-        QuoteOptionResponse, UnifiedCurrencyPairConfigurationResponse,
+    models::{
+        common_models::{
+            AddQuoteResponse, ClosestOption, CounterPartyResponse, Currency, CurrencyPair,
+            EstimateIVRequest, EstimateIVResponse, Quote, QuoteOptionRequest, QuoteOptionResponse,
+            UnifiedCurrencyPairConfigurationResponse,
+        },
+        money_model::{Price, Quantity},
+        spot_price_model::{get_reprice_threshold_bps, get_spot_price, SpotPrice},
     },
 };
+use crate::errors::JabraError;
 
 const MINIMUM_PREMIUM_IN_USD: f64 = 10.0;
+/// Shortest time to maturity in days the builder prices, the minimum of the TTM input.
+const MINIMUM_TTM: f64 = 0.01;
+/// How long a spot typed in by the trader can be priced off when no feed price was loaded yet.
+const MANUAL_SPOT_MAX_AGE_MS: i64 = 30_000;
+/// Used until the configured reprice threshold is loaded.
+const DEFAULT_REPRICE_THRESHOLD_BPS: f64 = 25.0;
 
 #[allow(non_snake_case)]
 #[component]
 pub fn OptionsGeneratorActivity() -> impl IntoView {
     let currency_pair = RwSignal::new(CurrencyPair::default());
     let config_resource = create_blocking_resource(|| (), move |_| fetch_unified_configuration());
-    let spot_resource = create_blocking_resource(currency_pair, move |c| coin_base_spot(c));
+    let specs_resource = create_blocking_resource(|| (), move |_| fetch_instrument_specs());
+    let spot_resource = create_blocking_resource(currency_pair, move |c| get_spot_price(c));
     let configuration = RwSignal::new(UnifiedCurrencyPairConfigurationResponse::default());
     view! {
         <Suspense
@@ -38,11 +53,20 @@ pub fn OptionsGeneratorActivity() -> impl IntoView {
         }
         >
             {
-                config_resource.and_then(|conf| {
-                    configuration.set(conf.clone());
-                    view!{
-                        <OptionsBuilderSpecs config = configuration spot_resource = spot_resource currency_pair = currency_pair/>
-                    }.into_view()
+                move || config_resource.get().map(|res| {
+                    let specs = specs_resource.get().unwrap_or_else(|| Ok(Default::default()));
+                    match (res, specs) {
+                        (Ok(conf), Ok(specs)) => {
+                            // Without specs for option the builder keeps the option specs of each currency
+                            configuration.set(specs.apply_to_configuration(&conf, InstrumentType::Option));
+                            view!{
+                                <OptionsBuilderSpecs config = configuration spot_resource = spot_resource currency_pair = currency_pair/>
+                            }.into_view()
+                        }
+                        (Err(e), _) | (_, Err(e)) => view! {
+                            <p class="p-4 text-sm text-error">{JabraError::from_server_fn_error(&e).to_string()}</p>
+                        }.into_view(),
+                    }
                 })
             }
         </Suspense>
@@ -53,7 +77,7 @@ pub fn OptionsGeneratorActivity() -> impl IntoView {
 #[component]
 pub fn OptionsBuilderSpecs(
     config: RwSignal<UnifiedCurrencyPairConfigurationResponse>,
-    spot_resource: Resource<CurrencyPair, Result<CoinBaseSpotPriceResponse, ServerFnError>>,
+    spot_resource: Resource<CurrencyPair, Result<SpotPrice, ServerFnError>>,
     currency_pair: RwSignal<CurrencyPair>,
 ) -> impl IntoView {
     let default_pair = move || {
@@ -70,6 +94,8 @@ pub fn OptionsBuilderSpecs(
     let deposit_amount = RwSignal::new(1.0);
     let option_kind = RwSignal::new(String::from("Call"));
     let spot = RwSignal::new(0.0);
+    let spot_price = RwSignal::new(SpotPrice::default());
+    let spot_error = RwSignal::new(None::<String>);
     let ttm = RwSignal::new(6.85);
     let r2 = RwSignal::new(0.042);
     let off_strike = RwSignal::new(5.0);
@@ -83,6 +109,10 @@ pub fn OptionsBuilderSpecs(
     let qoute_option_response = RwSignal::new(QuoteOptionResponse::default());
     let deal_btn_disabled = RwSignal::new(true);
     let trade_quotes = RwSignal::new(Vec::<Quote>::default());
+    // Spot each staged quote group was priced off, renewed by every tick that confirms it
+    let staged_spots = RwSignal::new(HashMap::<String, SpotPrice>::new());
+    let repricing = RwSignal::new(HashSet::<String>::new());
+    let now_ms = RwSignal::new(chrono::Utc::now().timestamp_millis());
     let counterparty = RwSignal::new("".to_string());
     let expiry_in_min = RwSignal::new(0 as u16);
     let show_trade_quote_alert = create_rw_signal(false);
@@ -124,7 +154,7 @@ pub fn OptionsBuilderSpecs(
                     show_trade_quote_alert.set(true);
                     add_quote_response.update(|v| {
                         v.success = false;
-                        v.message = "Your session has ended. Please relog your account.".to_string()
+                        v.message = JabraError::from_server_fn_error(&e).to_string()
                     });
                 }
             }
@@ -197,33 +227,29 @@ pub fn OptionsBuilderSpecs(
             }
         });
 
-    //Calculate PX values, using action, amount should always be the calculated base currency amount
-    let dispatch_quote_option_action = move || {
-        qoute_option_action.dispatch(QuoteOptionRequest {
-            option_kind: option_kind.get(),
-            amount: calculated_base_currency_amount.get(),
-            strike: calculated_strike.get(),
-            ttm: ttm.get(),
-            spot: Some(spot.get()),
-            r2: Some(r2.get()),
-            r1: Some(0.0),
-            iv: Some(iv.get()),
-            side: side.get(),
-        })
+    let reprice_threshold_resource = Resource::once(move || get_reprice_threshold_bps());
+    let reprice_threshold_bps = move || {
+        reprice_threshold_resource
+            .get()
+            .and_then(|res| res.ok())
+            .unwrap_or(DEFAULT_REPRICE_THRESHOLD_BPS)
     };
 
-    let calculated_px_base = move || {
+    // Premiums are priced the same way when a quote is added and when it is repriced: floored onto
+    // the tick, then held at the minimum premium, which the add form enforces through the min and
+    // max of its price inputs
+    let px_base_on_tick = move |px: f64| {
         format_with_specs(
-            qoute_option_response().data.px_in_base_ccy,
+            px,
             currency_pair.get().base_tick_size(),
             currency_pair.get().base_tick_size(),
             RoundType::Floor,
             true,
         )
     };
-    let calculated_px_quote = move || {
+    let px_quote_on_tick = move |px: f64| {
         format_with_specs(
-            qoute_option_response().data.px_in_quote_ccy,
+            px,
             currency_pair.get().quote_tick_size(),
             currency_pair.get().quote_order_size(),
             RoundType::Floor,
@@ -237,8 +263,8 @@ pub fn OptionsBuilderSpecs(
         _ => 0.0,
     };
 
-    let base_premium_min = move |side: String| {
-        let min = (convert_to_decimal(MINIMUM_PREMIUM_IN_USD) / convert_to_decimal(spot.get()))
+    let base_premium_min = move |side: String, spot: f64| {
+        let min = (convert_to_decimal(MINIMUM_PREMIUM_IN_USD) / convert_to_decimal(spot))
             .to_string()
             .parse::<f64>()
             .unwrap();
@@ -246,7 +272,7 @@ pub fn OptionsBuilderSpecs(
             min,
             currency_pair.get().base_tick_size(),
             currency_pair.get().base_tick_size(),
-            RoundType::Default,
+            RoundType::HalfEven,
             false,
         );
         log::info!("Min: {}", min);
@@ -258,6 +284,217 @@ pub fn OptionsBuilderSpecs(
         }
     };
 
+    // Buys carry a negative minimum and sells a positive one
+    let floor_premium = move |px: f64, minimum: f64| {
+        if minimum < 0.0 {
+            px.min(minimum)
+        } else {
+            px.max(minimum)
+        }
+    };
+
+    // Prices the JABRA leg of a staged group again and mirrors the result into the counterparty leg
+    let reprice_action = create_action(
+        move |(group_id, tick, request): &(String, SpotPrice, QuoteOptionRequest)| {
+            let (group_id, tick, request) = (group_id.clone(), tick.clone(), request.clone());
+            let (side, ttm) = (request.side.clone(), request.ttm);
+            async move {
+                match sb_post_qoute_option(request).await {
+                    Ok(response) => {
+                        let jabra_id = counter_parties
+                            .get_untracked()
+                            .get_id_by_ticker("JABRA")
+                            .unwrap_or_default();
+                        let pair = currency_pair.get_untracked();
+                        let spot = format_with_specs(
+                            tick.price,
+                            pair.quote_tick_size(),
+                            pair.quote_order_size(),
+                            RoundType::Floor,
+                            false,
+                        );
+                        let px_base = Price::from_f64(floor_premium(
+                            px_base_on_tick(response.data.px_in_base_ccy),
+                            base_premium_min(side.clone(), spot),
+                        ));
+                        let px_quote = Price::from_f64(floor_premium(
+                            px_quote_on_tick(response.data.px_in_quote_ccy),
+                            quote_premium_min(side),
+                        ));
+                        let greeks = response.data.greeks;
+                        trade_quotes.update(|quotes| {
+                            for quote in quotes.iter_mut().filter(|q| q.group_id == group_id) {
+                                let is_jabra = quote.counterparty_id == jabra_id;
+                                let sign = if is_jabra { 1.0 } else { -1.0 };
+                                quote.spot = Price::from_f64(spot);
+                                quote.ttm = ttm;
+                                quote.px_in_base_ccy = if is_jabra { px_base } else { -px_base };
+                                quote.px_in_quote_ccy = if is_jabra { px_quote } else { -px_quote };
+                                quote.delta = format!("{:.2}", greeks.delta * sign).parse::<f64>().unwrap_or_default();
+                                quote.gamma = format!("{:.5}", greeks.gamma * sign).parse::<f64>().unwrap_or_default();
+                                quote.theta = format!("{:.5}", greeks.theta * sign).parse::<f64>().unwrap_or_default();
+                            }
+                        });
+                        staged_spots.update(|spots| {
+                            if spots.contains_key(&group_id) {
+                                spots.insert(group_id.clone(), SpotPrice { price: spot, ..tick });
+                            }
+                        });
+                    }
+                    // The group keeps its old spot and is flagged once that goes stale
+                    Err(e) => log::error!("Failed to reprice quote group {}: {:?}", group_id, e),
+                }
+                repricing.update(|groups| {
+                    groups.remove(&group_id);
+                });
+            }
+        },
+    );
+
+    // Every price pushed by the spot stream. The builder follows it unless the trader typed a
+    // spot that is still fresh, and staged quotes are repriced once spot moves past the threshold.
+    let on_spot_tick = Callback::new(move |tick: SpotPrice| {
+        now_ms.set(chrono::Utc::now().timestamp_millis());
+        if tick.is_stale() {
+            return;
+        }
+        let threshold_bps = reprice_threshold_bps();
+
+        let current = spot_price.get_untracked();
+        if !current.is_manual() || current.is_stale() {
+            let builder_spot = SpotPrice {
+                price: spot.get_untracked(),
+                ..current
+            };
+            match builder_spot.confirm(&tick, threshold_bps) {
+                Some(confirmed) => spot_price.set(confirmed),
+                None => {
+                    let pair = currency_pair.get_untracked();
+                    spot.set(format_with_specs(
+                        tick.price,
+                        pair.quote_tick_size(),
+                        pair.quote_order_size(),
+                        RoundType::Floor,
+                        false,
+                    ));
+                    spot_price.set(tick.clone());
+                    // A price calculated off the old spot must not be added
+                    qoute_option_response.set(QuoteOptionResponse::default());
+                    deal_btn_disabled.set(true);
+                    set_can_fetch_iv(true);
+                }
+            }
+            spot_error.set(None);
+        }
+
+        let jabra_id = counter_parties
+            .get_untracked()
+            .get_id_by_ticker("JABRA")
+            .unwrap_or_default();
+        let staged = staged_spots.get_untracked();
+        for quote in trade_quotes.get_untracked() {
+            if quote.counterparty_id != jabra_id || repricing.with_untracked(|g| g.contains(&quote.group_id)) {
+                continue;
+            }
+            let Some(staged_spot) = staged.get(&quote.group_id) else {
+                continue;
+            };
+            match staged_spot.confirm(&tick, threshold_bps) {
+                Some(confirmed) => staged_spots.update(|spots| {
+                    spots.insert(quote.group_id.clone(), confirmed);
+                }),
+                None => {
+                    repricing.update(|groups| {
+                        groups.insert(quote.group_id.clone());
+                    });
+                    reprice_action.dispatch((
+                        quote.group_id.clone(),
+                        tick.clone(),
+                        QuoteOptionRequest {
+                            option_kind: quote.option_kind.clone(),
+                            amount: quote.amount.abs().to_f64(),
+                            strike: quote.strike.to_f64(),
+                            // The quote keeps its expiry, so it matures sooner than when it was priced
+                            ttm: get_ttm_until(&quote.expiry_timestamp, chrono::Utc::now())
+                                .unwrap_or(quote.ttm)
+                                .max(MINIMUM_TTM),
+                            spot: Some(tick.price),
+                            r2: Some(quote.r2),
+                            r1: Some(quote.r1),
+                            iv: Some(quote.iv),
+                            side: quote.side.clone(),
+                        },
+                    ));
+                }
+            }
+        }
+    });
+
+    // Ages the spots on screen even while the stream is silent
+    let clock = store_value(None::<leptos_dom::helpers::IntervalHandle>);
+    create_effect(move |_| {
+        if clock.get_value().is_none() {
+            let handle = set_interval_with_handle(
+                move || now_ms.set(chrono::Utc::now().timestamp_millis()),
+                std::time::Duration::from_secs(1),
+            );
+            clock.set_value(handle.ok());
+        }
+    });
+    on_cleanup(move || {
+        if let Some(handle) = clock.get_value() {
+            handle.clear();
+        }
+    });
+
+    let stream_pair = create_memo(move |_| {
+        let pair = currency_pair.get();
+        (!pair.base.ticker.is_empty() && !pair.quote.ticker.is_empty()).then(|| pair.coinbase_name())
+    });
+
+    let is_staged_spot_stale = move |group_id: &str, now_ms: i64| {
+        staged_spots.with(|spots| spots.get(group_id).map_or(true, |s| s.is_stale_at(now_ms)))
+    };
+
+    // Refuses to price off a stale spot and loads a fresh one instead
+    let spot_is_usable = move || {
+        let price = spot_price.get_untracked();
+        if price.is_stale() {
+            spot_error.set(Some(
+                JabraError::StaleSpotPrice {
+                    pair: currency_pair.get_untracked().coinbase_name(),
+                }
+                .to_string(),
+            ));
+            spot_resource.refetch();
+            false
+        } else {
+            spot_error.set(None);
+            true
+        }
+    };
+
+    //Calculate PX values, using action, amount should always be the calculated base currency amount
+    let dispatch_quote_option_action = move || {
+        if !spot_is_usable() {
+            return;
+        }
+        qoute_option_action.dispatch(QuoteOptionRequest {
+            option_kind: option_kind.get(),
+            amount: calculated_base_currency_amount.get(),
+            strike: calculated_strike.get(),
+            ttm: ttm.get(),
+            spot: Some(spot.get()),
+            r2: Some(r2.get()),
+            r1: Some(0.0),
+            iv: Some(iv.get()),
+            side: side.get(),
+        })
+    };
+
+    let calculated_px_base = move || px_base_on_tick(qoute_option_response().data.px_in_base_ccy);
+    let calculated_px_quote = move || px_quote_on_tick(qoute_option_response().data.px_in_quote_ccy);
+
     let delta = move || {
         let value = format!("{:.2}", qoute_option_response().data.greeks.delta);
         //Prevent from getting -0.00 value
@@ -288,6 +525,9 @@ pub fn OptionsBuilderSpecs(
 
     //Trade Qoute
     let on_add_qoute = move || {
+        if !spot_is_usable() {
+            return;
+        }
         let counterparty_id = counterparty.get().parse::<u16>().unwrap_or_default();
         let pair_id = currency_pair.get().id;
         let ccy_id = currency.get().id;
@@ -300,18 +540,28 @@ pub fn OptionsBuilderSpecs(
             .get_id_by_ticker("JABRA")
             .unwrap_or_default();
         let party_b = counterparty_id;
-        let precise_mbca = format_with_specs(
-            calculated_base_currency_amount(),
-            currency_pair().base_tick_size(),
-            currency_pair().base_order_size(),
+        // Builder values enter the quote as exact decimals on the instrument's tick and lot size
+        let lot_size = convert_to_decimal(currency_pair().base_order_size());
+        let quote_tick_size = convert_to_decimal(currency_pair().quote_tick_size());
+        let precise_mbca = Quantity::snap(
+            convert_to_decimal(calculated_base_currency_amount()),
+            lot_size,
             RoundType::Floor,
-            false,
-        );
+        )
+        .max(Quantity::new(lot_size));
         let calculated_deposit_amount = if side.get() == "Buy" {
             precise_mbca
         } else {
             -precise_mbca
         };
+        let quote_spot = Price::snap(convert_to_decimal(spot.get()), quote_tick_size, RoundType::Floor);
+        let quote_strike = Price::snap(
+            convert_to_decimal(calculated_strike()),
+            quote_tick_size,
+            RoundType::Floor,
+        );
+        let px_base = Price::from_f64(calculated_px_base());
+        let px_quote = Price::from_f64(calculated_px_quote());
         let trade_expiry_utc = trade_expiry.get();
         let expiry_date_time = get_expiry(expiry_in_min.get());
         let default_trade_time = counter_parties
@@ -334,19 +584,19 @@ pub fn OptionsBuilderSpecs(
             r2: r2.get(),
             r1: 0.0,
             offstrike_percentage: calculated_offstrike,
-            spot: spot.get(),
+            spot: quote_spot,
             // strike: strike(),
-            strike: calculated_strike(),
+            strike: quote_strike,
             iv: iv.get(),
-            px_in_base_ccy: calculated_px_base(),
-            px_in_quote_ccy: calculated_px_quote(),
+            px_in_base_ccy: px_base,
+            px_in_quote_ccy: px_quote,
             side: side.get(),
             quote_status: "active".to_string(),
             quote_origin: "JabraAdminGUI".to_string(),
             instrument_name: generate_instrument_name_v2(
                 currency_pair.get().base.ticker,
                 trade_expiry_utc.clone(),
-                calculated_strike(),
+                quote_strike.to_f64(),
                 option_kind.get(),
             ),
             quote_expiry: expiry_date_time.clone(),
@@ -371,18 +621,18 @@ pub fn OptionsBuilderSpecs(
             counterparty_id: counterparty_id,
             pair_id: pair_id,
             ccy_id: ccy_id,
-            amount: calculated_deposit_amount * -1.0, //flip amount
+            amount: -calculated_deposit_amount, //flip amount
             option_kind: option_kind.get(),
             ttm: ttm.get(),
             r2: r2.get(),
             r1: 0.0,
             offstrike_percentage: calculated_offstrike,
-            spot: spot.get(),
+            spot: quote_spot,
             // strike: strike(),
-            strike: calculated_strike(),
+            strike: quote_strike,
             iv: iv.get(),
-            px_in_base_ccy: calculated_px_base() * -1.0,
-            px_in_quote_ccy: calculated_px_quote() * -1.0,
+            px_in_base_ccy: -px_base,
+            px_in_quote_ccy: -px_quote,
             side: flip_side.to_string(),
             quote_status: "active".to_string(),
             quote_origin: "JabraAdminGUI".to_string(),
@@ -390,7 +640,7 @@ pub fn OptionsBuilderSpecs(
                 currency_pair.get().base.ticker,
                 trade_expiry_utc.clone(),
                 // strike(),
-                calculated_strike(),
+                quote_strike.to_f64(),
                 option_kind.get(),
             ),
             quote_expiry: expiry_date_time.clone(),
@@ -407,28 +657,51 @@ pub fn OptionsBuilderSpecs(
         };
 
         trade_quotes.update(|v| v.push(trader_quote));
+        staged_spots.update(|v| {
+            v.insert(group_id.clone(), spot_price.get_untracked());
+        });
         qoute_option_response.set(QuoteOptionResponse::default());
         deal_btn_disabled.set(true);
-        spot_resource.refetch();
         log::info!("Trade Quotes: {:?}", trade_quotes());
     };
 
     let trade_quote_dispatch = move || {
+        let now = chrono::Utc::now().timestamp_millis();
+        let has_stale_spot = trade_quotes
+            .get_untracked()
+            .iter()
+            .any(|q| is_staged_spot_stale(&q.group_id, now));
+        if has_stale_spot {
+            show_trade_quote_alert.set(true);
+            add_quote_response.update(|v| {
+                v.success = false;
+                v.message = JabraError::StaleSpotPrice {
+                    pair: currency_pair.get_untracked().coinbase_name(),
+                }
+                .to_string()
+            });
+            return;
+        }
         add_quote_action.dispatch(trade_quotes.get());
 
         //Reset Trade Quotes
         trade_quotes.set(Vec::<Quote>::default());
+        staged_spots.set(HashMap::new());
     };
 
     let on_remove_qoute = move |group_id: String| {
         trade_quotes.update(|v| {
             v.retain(|x| x.group_id != group_id);
         });
+        staged_spots.update(|v| {
+            v.remove(&group_id);
+        });
     };
 
     //Derived Signals
     let on_clear_quote = move || {
         trade_quotes.set(Vec::<Quote>::default());
+        staged_spots.set(HashMap::new());
     };
     let quote_total_base_currency_price = move || {
         let jabra_id = counter_parties()
@@ -438,9 +711,10 @@ pub fn OptionsBuilderSpecs(
             .get()
             .iter()
             .filter(|x| x.counterparty_id == jabra_id)
-            .fold(0.0, |acc, x| acc + x.px_in_base_ccy);
+            .map(|x| x.px_in_base_ccy.value())
+            .sum::<rust_decimal::Decimal>();
         format_with_specs(
-            bt,
+            Price::new(bt).to_f64(),
             currency_pair().base_tick_size(),
             currency_pair().base_tick_size(),
             RoundType::Floor,
@@ -455,9 +729,10 @@ pub fn OptionsBuilderSpecs(
             .get()
             .iter()
             .filter(|x| x.counterparty_id == jabra_id)
-            .fold(0.0, |acc, x| acc + x.px_in_quote_ccy);
+            .map(|x| x.px_in_quote_ccy.value())
+            .sum::<rust_decimal::Decimal>();
         format_with_specs(
-            qt,
+            Price::new(qt).to_f64(),
             currency_pair().quote_tick_size(),
             currency_pair().quote_tick_size(),
             RoundType::Floor,
@@ -628,21 +903,38 @@ pub fn OptionsBuilderSpecs(
                             let val = event_target_value(&e).parse::<f64>().unwrap_or_default();
                             let precise_val = format_with_specs(val, currency_pair.get().quote_tick_size(), currency_pair.get().quote_order_size(), RoundType::Floor, false);
                             spot.set(precise_val);
+                            let max_age_ms = match spot_price.get_untracked().max_age_ms() {
+                                age if age > 0 => age,
+                                _ => MANUAL_SPOT_MAX_AGE_MS,
+                            };
+                            spot_price.set(SpotPrice::manual(currency_pair.get().coinbase_name(), precise_val, max_age_ms));
                         }
                     />
+                    <div class = "flex justify-between text-xs font-extralight">
+                        <span>{move || spot_price.get().source}</span>
+                        <span class = "text-error">{move || spot_error.get()}</span>
+                    </div>
                     <Transition>
                         {
                             move || {
-                                spot_resource.and_then(|s| {
-                                    // log::info!("Spot Resource loaded");
-                                    let val = s.data.amount.parse::<f64>().unwrap_or_default();
-                                    let precise_val = format_with_specs(val, currency_pair.get().quote.tick_size(), currency_pair.get().quote.order_size(), RoundType::Floor, false);
-                                    spot.set(precise_val);
-                                    set_can_fetch_iv(true);
+                                spot_resource.get().map(|res| match res {
+                                    Ok(s) => {
+                                        let precise_val = format_with_specs(s.price, currency_pair.get().quote.tick_size(), currency_pair.get().quote.order_size(), RoundType::Floor, false);
+                                        spot.set(precise_val);
+                                        spot_price.set(s);
+                                        spot_error.set(None);
+                                        set_can_fetch_iv(true);
+                                    }
+                                    Err(e) => spot_error.set(Some(JabraError::from_server_fn_error(&e).to_string())),
                                 })
                             }
                         }
                     </Transition>
+                    {
+                        move || stream_pair.get().map(|pair| view! {
+                            <SpotFeed pair = pair on_price = on_spot_tick/>
+                        })
+                    }
                     <div class = "flex flex-grow justify-between my-2  font-extralight">
                         <label class = "block  font-light text-sm">Percentage Off Strike</label>
                         <span class="indicator-item badge badge-success badge-sm">{move || off_strike()}</span>
@@ -786,7 +1078,7 @@ pub fn OptionsBuilderSpecs(
                                                 move || match side.get().to_uppercase().as_str() {
                                                     "BUY" => view! {
                                                         <input class = "width-inherit text-md input-sm rounded bg-base-100 text-success border-gray-800 shadow-md hover:shadow-sm hover:shadow-info" type = "number" prop:value = move || calculated_px_base()
-                                                            prop:max = move || base_premium_min(side.get()) prop:step = move || currency_pair.get().base.tick_size()
+                                                            prop:max = move || base_premium_min(side.get(), spot.get()) prop:step = move || currency_pair.get().base.tick_size()
                                                             on:change = move |event| {
                                                                 let val: f64 = event_target_value(&event).parse().unwrap();
                                                                 qoute_option_response.update(|v| v.data.px_in_base_ccy = val);
@@ -795,7 +1087,7 @@ pub fn OptionsBuilderSpecs(
                                                     }.into_view(),
                                                     "SELL" => view! {
                                                         <input class = "width-inherit text-md input-sm rounded bg-base-100 text-success border-gray-800 shadow-md hover:shadow-sm hover:shadow-info" type = "number" prop:value = move || calculated_px_base()
-                                                            prop:min = move || base_premium_min(side.get()) prop:step = move || currency_pair.get().base.tick_size()
+                                                            prop:min = move || base_premium_min(side.get(), spot.get()) prop:step = move || currency_pair.get().base.tick_size()
                                                             on:change = move |event| {
                                                                 let val: f64 = event_target_value(&event).parse().unwrap();
                                                                 qoute_option_response.update(|v| v.data.px_in_base_ccy = val);
@@ -866,6 +1158,49 @@ pub fn OptionsBuilderSpecs(
                                 </div>
                             </div>
                         </form>
+
+                        <Show when = show_builder>
+                            <div class = "border-t border-t-base-200 px-4 py-2">
+                                <span class = "text-sm font-semibold text-success">STAGED QUOTES</span>
+                                <table class = "table table-xs">
+                                    <thead>
+                                        <tr>
+                                            <th>INSTRUMENT</th>
+                                            <th>SIDE</th>
+                                            <th>SPOT</th>
+                                            <th>PREMIUM</th>
+                                            <th>SPOT AGE</th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {
+                                            move || {
+                                                let jabra_id = counter_parties().get_id_by_ticker("JABRA").unwrap_or_default();
+                                                let now = now_ms.get();
+                                                trade_quotes.get().into_iter().filter(|q| q.counterparty_id == jabra_id).map(|quote| {
+                                                    let is_stale = is_staged_spot_stale(&quote.group_id, now);
+                                                    let age = staged_spots.with(|s| s.get(&quote.group_id).map(|p| p.age_ms(now) / 1_000)).unwrap_or_default();
+                                                    let is_repricing = repricing.with(|g| g.contains(&quote.group_id));
+                                                    view! {
+                                                        <tr class = {if is_stale {"text-error"} else {"hover"}}>
+                                                            <td>{quote.instrument_name}</td>
+                                                            <td>{quote.side}</td>
+                                                            <td>{quote.spot.to_string()}</td>
+                                                            <td>{quote.px_in_quote_ccy.to_string()}</td>
+                                                            <td>
+                                                                {format!("{}s", age)}
+                                                                {is_stale.then(|| view! {<span class = "badge badge-error badge-xs ml-1">STALE</span>})}
+                                                                {is_repricing.then(|| view! {<span class = "loading loading-spinner loading-xs ml-1"></span>})}
+                                                            </td>
+                                                        </tr>
+                                                    }
+                                                }).collect_view()
+                                            }
+                                        }
+                                    </tbody>
+                                </table>
+                            </div>
+                        </Show>
                     </div>
             </div>
         </div>
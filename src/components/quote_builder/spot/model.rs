@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::components::common::models::common_models::{
-    Currency, CurrencyPair, OptionInstrumentSpecification,
+use crate::components::common::models::{
+    common_models::{Currency, CurrencyPair, OptionInstrumentSpecification},
+    money_model::{Price, Quantity},
 };
 
 /// Struct for Spot Quote Data.
@@ -10,8 +11,8 @@ use crate::components::common::models::common_models::{
 pub struct SpotQuote {
     pub group_id: String,
     pub pair: CurrencyPair,
-    pub amount: f64,
-    pub price: f64,
+    pub amount: Quantity,
+    pub price: Price,
     pub quote_expiry: String,
     pub counterparty: String,
     pub quote_status: String,
@@ -22,8 +23,8 @@ impl SpotQuote {
     pub fn new(
         group_id: String,
         pair: CurrencyPair,
-        amount: f64,
-        price: f64,
+        amount: Quantity,
+        price: Price,
         quote_expiry: String,
         counterparty: String,
         quote_status: String,
@@ -72,8 +73,8 @@ impl Default for SpotQuote {
                     },
                 },
             },
-            amount: 3.33,
-            price: 30326.21,
+            amount: Quantity::from_f64(3.33),
+            price: Price::from_f64(30326.21),
             quote_expiry: "2023-12-12".to_string(),
             counterparty: "Bitbox".to_string(),
             quote_status: "active".to_string(),
@@ -123,8 +124,8 @@ impl SpotQuoteHistory {
 pub struct ExtractedSpotQuote {
     pub group_id: String,
     pub pair: String,
-    pub amount: f64,
-    pub price: f64,
+    pub amount: Quantity,
+    pub price: Price,
     pub quote_expiry: String,
     pub counterparty: String,
     pub gtc: bool,
@@ -168,18 +169,18 @@ pub fn sort(
         },
         SpotSort::Amount => match sort_type {
             true => {
-                data_table.sort_by(|a, b| a.amount.partial_cmp(&b.amount).unwrap());
+                data_table.sort_by(|a, b| a.amount.cmp(&b.amount));
             }
             false => {
-                data_table.sort_by(|a, b| b.amount.partial_cmp(&a.amount).unwrap());
+                data_table.sort_by(|a, b| b.amount.cmp(&a.amount));
             }
         },
         SpotSort::Price => match sort_type {
             true => {
-                data_table.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap());
+                data_table.sort_by(|a, b| a.price.cmp(&b.price));
             }
             false => {
-                data_table.sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap());
+                data_table.sort_by(|a, b| b.price.cmp(&a.price));
             }
         },
         SpotSort::QuoteExpiry => match sort_type {
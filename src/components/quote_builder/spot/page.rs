@@ -5,17 +5,19 @@ use crate::components::{
         components::modals::{ErrorModal, SuccessModalRefetch},
         functions::{
             helpers::{calculate_time_difference, get_expiry},
-            precision::{format_with_specs, RoundType},
-            utils::fetch_unified_configuration,
+            precision::{convert_to_decimal, fit_to_specs, InstrumentType, RoundType},
+            utils::{fetch_instrument_specs, fetch_unified_configuration},
         },
         models::{
             common_models::{Currency, CurrencyPair, UnifiedCurrencyPairConfigurationResponse},
+            money_model::{Price, Quantity},
             quote_model::QuotesTab,
             quote_option_model::{ApproveTradeQuoteRequest, ApproveTradeQuoteResponse},
         },
     },
     quote_builder::spot::model::{sort, SpotQuote, SpotSort},
 };
+use crate::errors::JabraError;
 
 use super::model::{ExtractedSpotQuote, SpotQuoteHistory};
 
@@ -24,6 +26,7 @@ use super::model::{ExtractedSpotQuote, SpotQuoteHistory};
 pub fn SpotGeneratorActivity() -> impl IntoView {
     let currency_pair = RwSignal::new(CurrencyPair::default());
     let config_resource = create_blocking_resource(|| (), move |_| fetch_unified_configuration());
+    let specs_resource = create_blocking_resource(|| (), move |_| fetch_instrument_specs());
     let configuration = RwSignal::new(UnifiedCurrencyPairConfigurationResponse::default());
     view! {
         <Suspense
@@ -34,11 +37,20 @@ pub fn SpotGeneratorActivity() -> impl IntoView {
             }
         >
             {
-                config_resource.and_then(|conf| {
-                    configuration.set(conf.clone());
-                    view!{
-                        <Spot config = configuration currency_pair = currency_pair/>
-                    }.into_view()
+                move || config_resource.get().map(|res| {
+                    let specs = specs_resource.get().unwrap_or_else(|| Ok(Default::default()));
+                    match (res, specs) {
+                        (Ok(conf), Ok(specs)) => {
+                            // Without specs for spot the builder keeps the option specs of each currency
+                            configuration.set(specs.apply_to_configuration(&conf, InstrumentType::Spot));
+                            view!{
+                                <Spot config = configuration currency_pair = currency_pair/>
+                            }.into_view()
+                        }
+                        (Err(e), _) | (_, Err(e)) => view! {
+                            <p class="p-4 text-sm text-error">{JabraError::from_server_fn_error(&e).to_string()}</p>
+                        }.into_view(),
+                    }
                 })
             }
         </Suspense>
@@ -62,8 +74,11 @@ pub fn Spot(
 
     let currency = RwSignal::new(Currency::default());
 
-    let notional_amount = create_rw_signal(0.0);
-    let price = create_rw_signal(1.0);
+    let notional_amount = create_rw_signal(Quantity::ZERO);
+    let price = create_rw_signal(Price::from_f64(1.0));
+    // Why the last amount or price typed in was changed to fit the instrument specs
+    let amount_note = create_rw_signal(None::<String>);
+    let price_note = create_rw_signal(None::<String>);
 
     let expiry_in_min = RwSignal::new(0 as u16);
     let gtc_signal = create_rw_signal(false);
@@ -108,8 +123,8 @@ pub fn Spot(
 
     let reset_form = move || {
         currency_pair.set(CurrencyPair::default());
-        notional_amount.set(0.0);
-        price.set(1.0);
+        notional_amount.set(Quantity::ZERO);
+        price.set(Price::from_f64(1.0));
         expiry_in_min.set(0);
         counterparty.set("".to_string());
         next_step.set(1);
@@ -167,11 +182,17 @@ pub fn Spot(
                             } else {
                                 view! {
                                     <label class = "font-light text-sm">Amount (Notional)</label>
-                                    <input class = "input-sm text-xs text-success block w-full rounded hover:shadow-sm hover:shadow-success bg-base-100 shadow-md" type="number" name="deposit_amount" prop:value = notional_amount prop:min = move || currency.get().order_size() prop:step = move || currency.get().tick_size()
+                                    <input class = "input-sm text-xs text-success block w-full rounded hover:shadow-sm hover:shadow-success bg-base-100 shadow-md" type="number" name="deposit_amount" prop:value = move || notional_amount.get().to_string() prop:min = move || currency.get().order_size() prop:step = move || currency.get().order_size()
                                         on:change = move |e| {
-                                            let val = event_target_value(&e).parse::<f64>().unwrap_or_default();
-                                            let precise_val = format_with_specs(val, currency.get().tick_size(), currency.get().order_size(), RoundType::Floor, false);
-                                            notional_amount.set(precise_val);
+                                            let val = Quantity::parse(&event_target_value(&e)).unwrap_or_default();
+                                            let lot_size = convert_to_decimal(currency.get().order_size());
+                                            match fit_to_specs(val.value(), lot_size, Some(lot_size), RoundType::Floor) {
+                                                Ok(fitted) => {
+                                                    notional_amount.set(Quantity::new(fitted.value));
+                                                    amount_note.set(fitted.note());
+                                                }
+                                                Err(e) => amount_note.set(Some(e.to_string())),
+                                            }
 
                                             if has_filled.get() == false && next_step.get() <= 3 {
                                                 next_step.set(3)
@@ -180,6 +201,9 @@ pub fn Spot(
                                             }
                                         }
                                     />
+                                    <Show when = move || amount_note.get().is_some()>
+                                        <p class = "text-xs text-warning mt-1">{move || amount_note.get().unwrap_or_default()}</p>
+                                    </Show>
                                 }.into_view()
                             }
 
@@ -198,11 +222,17 @@ pub fn Spot(
                             } else {
                                 view! {
                                     <label class = "font-light text-sm">Price</label>
-                                    <input class = "input-sm text-xs text-success block w-full rounded hover:shadow-sm hover:shadow-success bg-base-100 shadow-md" type="number" name="price" prop:value = price prop:min = move || currency.get().order_size() prop:step = move || currency.get().tick_size()
+                                    <input class = "input-sm text-xs text-success block w-full rounded hover:shadow-sm hover:shadow-success bg-base-100 shadow-md" type="number" name="price" prop:value = move || price.get().to_string() prop:min = move || currency.get().tick_size() prop:step = move || currency.get().tick_size()
                                         on:change = move |e| {
-                                            let val = event_target_value(&e).parse::<f64>().unwrap_or_default();
-                                            let precise_val = format_with_specs(val, currency.get().tick_size(), currency.get().order_size(), RoundType::Floor, false);
-                                            price.set(precise_val);
+                                            let val = Price::parse(&event_target_value(&e)).unwrap_or_default();
+                                            let tick_size = convert_to_decimal(currency.get().tick_size());
+                                            match fit_to_specs(val.value(), tick_size, Some(tick_size), RoundType::Floor) {
+                                                Ok(fitted) => {
+                                                    price.set(Price::new(fitted.value));
+                                                    price_note.set(fitted.note());
+                                                }
+                                                Err(e) => price_note.set(Some(e.to_string())),
+                                            }
 
                                             if has_filled.get() == false && next_step.get() <= 4 {
                                                 next_step.set(4)
@@ -211,6 +241,9 @@ pub fn Spot(
                                             }
                                         }
                                     />
+                                    <Show when = move || price_note.get().is_some()>
+                                        <p class = "text-xs text-warning mt-1">{move || price_note.get().unwrap_or_default()}</p>
+                                    </Show>
                                 }.into_view()
                             }
 
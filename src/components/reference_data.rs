@@ -0,0 +1,48 @@
+use leptos::*;
+use leptos_router::ActionForm;
+
+use crate::{components::common::functions::utils::InvalidateReferenceCache, errors::JabraError};
+
+#[allow(non_snake_case)]
+#[component]
+pub fn ReferenceData() -> impl IntoView {
+    let invalidate_action: Action<InvalidateReferenceCache, Result<bool, ServerFnError>> =
+        create_server_action::<InvalidateReferenceCache>();
+    let is_pending = invalidate_action.pending();
+    let value = invalidate_action.value();
+
+    view! {
+        <div class="p-6">
+            <span class="text-lg text-success font-normal pl-1">Reference Data</span>
+            <div class="card w-full max-w-lg rounded-lg bg-base-300 mt-2 shadow-lg">
+                <div class="card-body">
+                    <p class="text-sm">
+                        "Supported pairs, currencies and counterparties are cached by the portal server. "
+                        "Refresh the cache after changing them in Directus so traders see the change right away."
+                    </p>
+                    {
+                        move || match value.get() {
+                            Some(Ok(_)) => view! {
+                                <p class="text-sm text-success">"The cache was cleared. The next request loads fresh data."</p>
+                            }.into_view(),
+                            Some(Err(e)) => view! {
+                                <p class="text-sm text-error">{JabraError::from_server_fn_error(&e).to_string()}</p>
+                            }.into_view(),
+                            None => ().into_view(),
+                        }
+                    }
+                    <ActionForm action = invalidate_action>
+                        <div class="form-control mt-4">
+                            <button type="submit" class="btn btn-sm btn-success" disabled=is_pending>
+                                {move || match is_pending() {
+                                    true => view! { <span class="loading loading-spinner loading-sm"></span> }.into_view(),
+                                    false => "REFRESH CACHE".into_view(),
+                                }}
+                            </button>
+                        </div>
+                    </ActionForm>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,209 @@
+use leptos::*;
+use leptos_router::ActionForm;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::common::components::input::{InputPassword, InputText},
+    errors::JabraError,
+};
+
+const INPUT_CLASS: &str =
+    "input input-sm w-full bg-white rounded hover:shadow-md text-black border-gray-800 shadow-md";
+
+/// A freshly generated TFA secret, ready to be scanned into an authenticator app.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct TfaSetup {
+    pub secret: String,
+    pub otpauth_url: String,
+    /// The `otpauth_url` rendered as an SVG QR code.
+    pub qr_svg: String,
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn Settings() -> impl IntoView {
+    view! {
+        <div class="h-full flex items-center justify-center px-4">
+            <div class="card flex-shrink-0 w-full max-w-md shadow-lg bg-base-100">
+                <div class="card-body">
+                    <h2 class="card-title">Two-factor authentication</h2>
+                    <EnableTfa />
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn EnableTfa() -> impl IntoView {
+    let generate_action: Action<GenerateTfaSecret, Result<TfaSetup, ServerFnError>> =
+        create_server_action::<GenerateTfaSecret>();
+    let enable_action: Action<EnableTfaSecret, Result<bool, ServerFnError>> =
+        create_server_action::<EnableTfaSecret>();
+    let generated = generate_action.value();
+    let enabled = enable_action.value();
+
+    let error_message = move |value: Option<Result<_, ServerFnError>>| {
+        value.and_then(|r| r.err()).map(|e| {
+            view! {
+                <p class="text-xs text-error">{JabraError::from_server_fn_error(&e).to_string()}</p>
+            }
+        })
+    };
+
+    view! {
+        {
+            move || match (generated.get(), enabled.get()) {
+                (_, Some(Ok(_))) => view! {
+                    <p class="text-sm text-success">"Two-factor authentication is enabled. You will be asked for a code at your next login."</p>
+                }.into_view(),
+                (Some(Ok(setup)), _) => view! {
+                    <p class="text-sm">"Scan the code with your authenticator app, then enter the code it shows to finish."</p>
+                    <div class="flex justify-center bg-white p-2 rounded" inner_html=setup.qr_svg.clone()></div>
+                    <p class="text-xs break-all">"Secret: " <span class="font-mono">{setup.secret.clone()}</span></p>
+                    <ActionForm action = enable_action>
+                        <input type="hidden" name="secret" value=setup.secret.clone()/>
+                        <InputText
+                            name="otp".to_string()
+                            label="One-time code".to_string()
+                            placeholder="123456".to_string()
+                            custom_class=INPUT_CLASS.to_string()
+                        />
+                        {move || error_message(enabled.get())}
+                        <div class="form-control mt-6">
+                            <button type="submit" class="btn rounded btn-block btn-success">ENABLE</button>
+                        </div>
+                    </ActionForm>
+                }.into_view(),
+                _ => view! {
+                    <p class="text-sm">"Confirm your password to generate a new secret."</p>
+                    <ActionForm action = generate_action>
+                        <InputPassword
+                            name="password".to_string()
+                            label="Password".to_string()
+                            custom_class=INPUT_CLASS.to_string()
+                        />
+                        {move || error_message(generated.get().map(|r| r.map(|_| true)))}
+                        <div class="form-control mt-6">
+                            <button type="submit" class="btn rounded btn-block btn-success">GENERATE SECRET</button>
+                        </div>
+                    </ActionForm>
+                }.into_view(),
+            }
+        }
+    }
+}
+
+/// Generates a TFA secret for the current user. Directus asks for the password again before doing so.
+
+#[server(GenerateTfaSecret, "/api")]
+pub async fn generate_tfa_secret(password: String) -> Result<TfaSetup, ServerFnError> {
+    use crate::components::common::functions::rate_limit::password_throttle;
+    use crate::components::common::functions::security::SecurityEvent;
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::wrapper::{
+        call_and_parse, client_ip, HttpMethod, JabraSession,
+    };
+    use crate::components::models::{DirectusTfaGenerateRequest, DirectusTfaGenerateResponse};
+    use qrcode::{render::svg, QrCode};
+
+    let session = JabraSession::extract().await?;
+    // Wrong passwords here count against the same budget as failed logins
+    let throttle = password_throttle();
+    throttle.check(&session.cookie.user_id)?;
+
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let path = format!("{}/users/me/tfa/generate", url);
+    let response = call_and_parse::<DirectusTfaGenerateRequest, DirectusTfaGenerateResponse>(
+        Some(DirectusTfaGenerateRequest { password }),
+        path,
+        session.auth_headers(),
+        HttpMethod::POST,
+        Upstream::Directus,
+    )
+    .await;
+    let secret = match response {
+        Ok(res) => {
+            throttle.record_success(&session.cookie.user_id);
+            res.data
+        }
+        Err(JabraError::Unauthorized(_)) => {
+            SecurityEvent::LoginFailed {
+                email: session.cookie.user_id.clone(),
+                ip: client_ip(),
+                reason: "invalid_password_tfa_generate".to_string(),
+                failures: throttle.record_failure(&session.cookie.user_id),
+            }
+            .log();
+            return Err(JabraError::LoginError.into());
+        }
+        Err(e) => {
+            log::error!("TFA Generate Error: {}", e.to_string());
+            return Err(e.into());
+        }
+    };
+
+    let qr_svg = match QrCode::new(secret.otpauth_url.as_bytes()) {
+        Ok(code) => code.render::<svg::Color>().min_dimensions(200, 200).build(),
+        Err(e) => {
+            log::error!("TFA QR Error: {}", e.to_string());
+            String::new()
+        }
+    };
+    Ok(TfaSetup {
+        secret: secret.secret,
+        otpauth_url: secret.otpauth_url,
+        qr_svg,
+    })
+}
+
+/// Enables TFA with a secret from [`generate_tfa_secret`] once the user proves the app is set up.
+
+#[server(EnableTfaSecret, "/api")]
+pub async fn enable_tfa_secret(secret: String, otp: String) -> Result<bool, ServerFnError> {
+    use crate::components::common::functions::audit;
+    use crate::components::common::functions::upstream::Upstream;
+    use crate::components::common::functions::wrapper::{
+        call, client_ip, HttpMethod, JabraSession,
+    };
+    use crate::components::common::models::audit_model::{AuditAction, AuditEntry};
+    use crate::components::models::DirectusTfaEnableRequest;
+
+    let session = JabraSession::extract().await?;
+
+    let otp = otp.trim().to_string();
+    if otp.len() != 6 || !otp.chars().all(|c| c.is_ascii_digit()) {
+        return Err(JabraError::TwoFactorRequired { invalid_code: true }.into());
+    }
+
+    let url = std::env::var("DIRECTUSURL").unwrap();
+    let path = format!("{}/users/me/tfa/enable", url);
+    let response = call::<DirectusTfaEnableRequest>(
+        Some(DirectusTfaEnableRequest { secret, otp }),
+        path,
+        session.auth_headers(),
+        HttpMethod::POST,
+        Upstream::Directus,
+    )
+    .await;
+    // The secret itself never goes into the audit log
+    audit::record(vec![AuditEntry::new(
+        session.cookie.user_id.clone(),
+        AuditAction::EnableTfa,
+        client_ip(),
+    )
+    .error(response.as_ref().err().map(|e| e.to_string()))])
+    .await;
+    match response {
+        Ok(res) => Ok(res),
+        Err(JabraError::TwoFactorRequired { .. }) => {
+            Err(JabraError::TwoFactorRequired { invalid_code: true }.into())
+        }
+        Err(e) => {
+            log::error!("TFA Enable Error: {}", e.to_string());
+            Err(e.into())
+        }
+    }
+}
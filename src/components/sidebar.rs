@@ -1,7 +1,13 @@
 use leptos::*;
-use leptos_router::use_location;
+use leptos_router::{use_location, ActionForm};
 
-use crate::components::common::components::common_icons::Icon;
+use crate::{
+    app::{CheckCookie, Refetcher, UserPermissions},
+    components::{
+        common::{components::common_icons::Icon, models::permission_model::Permission},
+        login::Logout,
+    },
+};
 
 const SELECTED_CLASS: &str = "font-semibold justify-start capitalize border border-success text-success rounded-xl w-full flex flex-row gap-2 items-center p-2 hover:bg-base-100";
 const UNSELECTED_CLASS: &str =
@@ -14,6 +20,13 @@ const UNSELECTED_CLASS_MOBILE: &str = "flex justify-center rounded-xl p-2 hover:
 #[component]
 pub fn Sidebar() -> impl IntoView {
     let location = use_location().pathname;
+    let check_cookie = use_context::<CheckCookie>().expect("Failed to find CheckCookie");
+    let is_logged_in = move || matches!(check_cookie.0.get(), Some(Ok(true)));
+    let user_permissions =
+        use_context::<UserPermissions>().expect("Failed to find UserPermissions");
+    let has_permission = move |permission: Permission| {
+        matches!(user_permissions.0.get(), Some(Ok(permissions)) if permissions.contains(&permission))
+    };
 
     let toogle = RwSignal::new(true);
     create_effect(move |_| {
@@ -22,6 +35,8 @@ pub fn Sidebar() -> impl IntoView {
 
     {
         move || {
+            let can_create_quote = has_permission(Permission::CreateQuote);
+            let is_admin = has_permission(Permission::Admin);
             view! {
                 <div class="flex flex-col min-h-full justify-between">
                     <div class = "p-1 m-1">
@@ -46,12 +61,14 @@ pub fn Sidebar() -> impl IntoView {
                             title = "QUOTES".to_string()
                             icon_title = "Quotes".to_string()
                         >
-                            <Anchor
-                                location = location.get()
-                                anchor_url = "/quote_builder".to_string()
-                                title = "Quote Builder".to_string()
-                                sub_anchor = true
-                            />
+                            <Show when = move || can_create_quote>
+                                <Anchor
+                                    location = location.get()
+                                    anchor_url = "/quote_builder".to_string()
+                                    title = "Quote Builder".to_string()
+                                    sub_anchor = true
+                                />
+                            </Show>
                             <Anchor
                                 location = location.get()
                                 anchor_url = "/active_quotes".to_string()
@@ -82,22 +99,67 @@ pub fn Sidebar() -> impl IntoView {
                         <div class="divider divider-neutral mt-0 mb-0"></div>
                         <div class = "text-xs p-2 hidden px924:block">ACCOUNT</div>
 
-                        <Anchor
-                            location = location.get()
-                            anchor_url = "/login".to_string()
-                            title = "Login".to_string()
-                            icon_title = "Login".to_string()
-                        />
+                        {
+                            move || match is_logged_in() {
+                                true => view! {
+                                    <Anchor
+                                        location = location.get()
+                                        anchor_url = "/settings".to_string()
+                                        title = "Settings".to_string()
+                                        icon_title = "Settings".to_string()
+                                    />
+                                    <LogoutButton />
+                                }.into_view(),
+                                false => view! {
+                                    <Anchor
+                                        location = location.get()
+                                        anchor_url = "/login".to_string()
+                                        title = "Login".to_string()
+                                        icon_title = "Login".to_string()
+                                    />
+                                }.into_view(),
+                            }
+                        }
 
-                        <div class="divider divider-neutral mt-0 mb-0"></div>
-                        <div class = "text-xs p-2 hidden px924:block">PLAYGROUND</div>
+                        <Show when = move || is_admin>
+                            <div class="divider divider-neutral mt-0 mb-0"></div>
+                            <div class = "text-xs p-2 hidden px924:block">PLAYGROUND</div>
 
-                        <Anchor
-                            location = location.get()
-                            anchor_url = "/components".to_string()
-                            title = "Components".to_string()
-                            icon_title = "Component".to_string()
-                        />
+                            <Anchor
+                                location = location.get()
+                                anchor_url = "/components".to_string()
+                                title = "Components".to_string()
+                                icon_title = "Component".to_string()
+                            />
+
+                            <div class="divider divider-neutral mt-0 mb-0"></div>
+                            <div class = "text-xs p-2 hidden px924:block">ADMIN</div>
+
+                            <Anchor
+                                location = location.get()
+                                anchor_url = "/audit_log".to_string()
+                                title = "Audit Log".to_string()
+                                icon_title = "Trades".to_string()
+                            />
+                            <Anchor
+                                location = location.get()
+                                anchor_url = "/reference_data".to_string()
+                                title = "Reference Data".to_string()
+                                icon_title = "Settings".to_string()
+                            />
+                            <Anchor
+                                location = location.get()
+                                anchor_url = "/counterparties".to_string()
+                                title = "Counterparties".to_string()
+                                icon_title = "Settings".to_string()
+                            />
+                            <Anchor
+                                location = location.get()
+                                anchor_url = "/supported_pairs".to_string()
+                                title = "Pairs & Currencies".to_string()
+                                icon_title = "Settings".to_string()
+                            />
+                        </Show>
                     </div>
 
                     <div class="p-2 flex justify-center">
@@ -174,6 +236,44 @@ pub fn Anchor(
     }
 }
 
+#[allow(non_snake_case)]
+#[component]
+pub fn LogoutButton() -> impl IntoView {
+    let logout_action: Action<Logout, Result<bool, ServerFnError>> =
+        create_server_action::<Logout>();
+
+    create_effect(move |_| {
+        if let Some(Ok(true)) = logout_action.value().get() {
+            use_context::<Refetcher>().unwrap().0.set(false);
+        }
+    });
+
+    view! {
+        <ActionForm action = logout_action>
+            <div class="items-center hidden px924:block">
+                <button type="submit" class = UNSELECTED_CLASS>
+                    <Icon
+                        title = "Logout".to_string()
+                        size = "w-5 h-5".to_string()
+                    />
+                    <span class="text-center">Logout</span>
+                </button>
+            </div>
+
+            // ------------------ MOBILE ------------------
+
+            <div class = "flex flex-col gap-2 px924:hidden">
+                <button type="submit" class = UNSELECTED_CLASS_MOBILE>
+                    <Icon
+                        title = "Logout".to_string()
+                        size = "w-6 h-6".to_string()
+                    />
+                </button>
+            </div>
+        </ActionForm>
+    }
+}
+
 #[allow(non_snake_case)]
 #[component]
 pub fn MenuWithSubMenu(
@@ -0,0 +1,261 @@
+use leptos::*;
+
+use crate::{
+    components::common::{
+        functions::{precision::InstrumentSpecs, utils::fetch_instrument_specs},
+        models::{
+            currency_model::{
+                list_currency_settings, save_currency_settings, validate_currency_settings,
+                CurrencySettings, InstrumentOptionSettings,
+            },
+            currencypair_model::{list_supported_pairs, set_pair_active, CurrencyPair},
+        },
+    },
+    errors::JabraError,
+};
+
+const INPUT_CLASS: &str =
+    "input input-xs text-xs input-bordered w-24 rounded hover:shadow-md";
+
+#[allow(non_snake_case)]
+#[component]
+pub fn SupportedPairs() -> impl IntoView {
+    let refresh = RwSignal::new(0_u32);
+    let pairs_resource = create_resource(refresh, move |_| list_supported_pairs());
+    let currencies_resource = create_resource(refresh, move |_| list_currency_settings());
+    let specs_resource = create_resource(refresh, move |_| fetch_instrument_specs());
+
+    let pair_action = create_action(|(id, is_active): &(u16, bool)| {
+        let (id, is_active) = (*id, *is_active);
+        async move { set_pair_active(id, is_active).await }
+    });
+    let currency_action = create_action(
+        |(id, display_scale, instrument_option): &(u16, u8, InstrumentOptionSettings)| {
+            let (id, display_scale, instrument_option) =
+                (*id, *display_scale, instrument_option.clone());
+            async move { save_currency_settings(id, display_scale, instrument_option).await }
+        },
+    );
+    // Errors of the last change, checked before saving where possible
+    let error = RwSignal::new(None::<String>);
+
+    let on_result = move |result: Option<Result<bool, ServerFnError>>| match result {
+        Some(Ok(_)) => {
+            error.set(None);
+            refresh.update(|r| *r += 1);
+        }
+        Some(Err(e)) => error.set(Some(JabraError::from_server_fn_error(&e).to_string())),
+        None => (),
+    };
+    create_effect(move |_| on_result(pair_action.value().get()));
+    create_effect(move |_| on_result(currency_action.value().get()));
+
+    let on_toggle_pair = Callback::new(move |pair: CurrencyPair| {
+        pair_action.dispatch((pair.id, !pair.is_active));
+    });
+    let on_save_currency = Callback::new(
+        move |(id, display_scale, instrument_option): (u16, u8, InstrumentOptionSettings)| {
+            match validate_currency_settings(display_scale, &instrument_option) {
+                Ok(()) => currency_action.dispatch((id, display_scale, instrument_option)),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        },
+    );
+
+    view! {
+        <div class="p-6">
+            <span class="text-lg text-success font-normal pl-1">Pairs and Currencies</span>
+            <Show when = move || error.get().is_some()>
+                <p class="text-sm text-error pt-2">{move || error.get().unwrap_or_default()}</p>
+            </Show>
+            <div class="text-xs text-success pt-4 pl-1">PAIRS</div>
+            <div class="rounded-md bg-base-300 mt-2 overflow-x-auto">
+                <Transition fallback = move || view! {<span class="loading loading-bars loading-sm text-success"></span>}>
+                    {
+                        move || pairs_resource.get().map(|res| match res {
+                            Ok(pairs) => view! { <PairTable pairs = pairs on_toggle = on_toggle_pair/> }.into_view(),
+                            Err(e) => view! {
+                                <p class="p-4 text-sm text-error">{JabraError::from_server_fn_error(&e).to_string()}</p>
+                            }.into_view(),
+                        })
+                    }
+                </Transition>
+            </div>
+            <div class="text-xs text-success pt-4 pl-1">CURRENCIES</div>
+            <p class="text-xs pt-1 pl-1">
+                "Tick and lot sizes apply to options and to every instrument type without its own instrument specs. "
+                "Instrument specs take precedence for their type and are listed under OVERRIDES."
+            </p>
+            <div class="rounded-md bg-base-300 mt-2 overflow-x-auto">
+                <Transition fallback = move || view! {<span class="loading loading-bars loading-sm text-success"></span>}>
+                    {
+                        move || currencies_resource.get().map(|res| match res {
+                            Ok(currencies) => {
+                                // Without the specs the overrides are left out, the settings stay editable
+                                let specs = specs_resource.get().and_then(|res| res.ok()).unwrap_or_default();
+                                view! {
+                                    <table class = "table table-zebra table-xs">
+                                        <thead class = "text-success font-extralight bg-base-300">
+                                            <tr class="border-y border-y-base-100">
+                                                <th>TICKER</th>
+                                                <th>NAME</th>
+                                                <th>STATUS</th>
+                                                <th>DISPLAY SCALE</th>
+                                                <th>TICK SIZE</th>
+                                                <th>LOT SIZE</th>
+                                                <th>CONTRACT MULTIPLIER</th>
+                                                <th>OVERRIDES</th>
+                                                <th></th>
+                                            </tr>
+                                        </thead>
+                                        <tbody>
+                                            {currencies.into_iter().map(|currency| {
+                                                let overrides = specs
+                                                    .data
+                                                    .iter()
+                                                    .filter(|s| s.ccy_id.ticker == currency.ticker)
+                                                    .cloned()
+                                                    .collect::<Vec<_>>();
+                                                view! {
+                                                    <CurrencyRow currency = currency overrides = overrides on_save = on_save_currency/>
+                                                }
+                                            }).collect_view()}
+                                        </tbody>
+                                    </table>
+                                }.into_view()
+                            }
+                            Err(e) => view! {
+                                <p class="p-4 text-sm text-error">{JabraError::from_server_fn_error(&e).to_string()}</p>
+                            }.into_view(),
+                        })
+                    }
+                </Transition>
+            </div>
+        </div>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn PairTable(pairs: Vec<CurrencyPair>, on_toggle: Callback<CurrencyPair>) -> impl IntoView {
+    view! {
+        <table class = "table table-zebra table-xs">
+            <thead class = "text-success font-extralight bg-base-300">
+                <tr class="border-y border-y-base-100">
+                    <th>PAIR</th>
+                    <th>BASE</th>
+                    <th>QUOTE</th>
+                    <th>STATUS</th>
+                    <th></th>
+                </tr>
+            </thead>
+            <tbody>
+                {pairs.into_iter().map(|pair| {
+                    let is_active = pair.is_active;
+                    let toggle_pair = pair.clone();
+                    view! {
+                        <tr class="hover">
+                            <td>{pair.name}</td>
+                            <td>{pair.base.ticker}</td>
+                            <td>{pair.quote.ticker}</td>
+                            <td>
+                                {match is_active {
+                                    true => view! {<span class="badge badge-success badge-sm">"active"</span>}.into_view(),
+                                    false => view! {<span class="badge badge-ghost badge-sm">"inactive"</span>}.into_view(),
+                                }}
+                            </td>
+                            <td>
+                                <button class="btn btn-xs btn-ghost" on:click=move |_| on_toggle.call(toggle_pair.clone())>
+                                    {if is_active { "DEACTIVATE" } else { "ACTIVATE" }}
+                                </button>
+                            </td>
+                        </tr>
+                    }
+                }).collect_view()}
+            </tbody>
+        </table>
+    }
+}
+
+/// One editable currency. Currencies without an `instrument_option` start from the builder defaults.
+/// `overrides` are the instrument specs of the currency, shown read only since they win over its settings.
+
+#[allow(non_snake_case)]
+#[component]
+pub fn CurrencyRow(
+    currency: CurrencySettings,
+    overrides: Vec<InstrumentSpecs>,
+    on_save: Callback<(u16, u8, InstrumentOptionSettings)>,
+) -> impl IntoView {
+    let option = currency
+        .instrument_option
+        .clone()
+        .unwrap_or(InstrumentOptionSettings {
+            id: None,
+            contract_multiplier: 1,
+            min_price_increment: 0.01,
+            min_contract_increment: 0.01,
+        });
+    let display_scale = RwSignal::new(currency.display_scale.to_string());
+    let tick_size = RwSignal::new(option.min_price_increment.to_string());
+    let lot_size = RwSignal::new(option.min_contract_increment.to_string());
+    let contract_multiplier = RwSignal::new(option.contract_multiplier.to_string());
+
+    let id = currency.id;
+    let on_click = move |_| {
+        // Unparsable input is sent as zero, which the validation rejects with a message
+        let instrument_option = InstrumentOptionSettings {
+            id: option.id,
+            contract_multiplier: contract_multiplier.get_untracked().trim().parse().unwrap_or(0),
+            min_price_increment: tick_size.get_untracked().trim().parse().unwrap_or(0.0),
+            min_contract_increment: lot_size.get_untracked().trim().parse().unwrap_or(0.0),
+        };
+        let display_scale = display_scale.get_untracked().trim().parse().unwrap_or(u8::MAX);
+        on_save.call((id, display_scale, instrument_option));
+    };
+
+    view! {
+        <tr class="hover">
+            <td>{currency.ticker}</td>
+            <td>{currency.name}</td>
+            <td>
+                {match currency.is_active {
+                    true => view! {<span class="badge badge-success badge-sm">"active"</span>}.into_view(),
+                    false => view! {<span class="badge badge-ghost badge-sm">"inactive"</span>}.into_view(),
+                }}
+            </td>
+            <td>
+                <input class=INPUT_CLASS type="number" min="0" step="1"
+                    prop:value=display_scale on:input=move |ev| display_scale.set(event_target_value(&ev))/>
+            </td>
+            <td>
+                <input class=INPUT_CLASS type="text"
+                    prop:value=tick_size on:input=move |ev| tick_size.set(event_target_value(&ev))/>
+            </td>
+            <td>
+                <input class=INPUT_CLASS type="text"
+                    prop:value=lot_size on:input=move |ev| lot_size.set(event_target_value(&ev))/>
+            </td>
+            <td>
+                <input class=INPUT_CLASS type="number" min="1" step="1"
+                    prop:value=contract_multiplier on:input=move |ev| contract_multiplier.set(event_target_value(&ev))/>
+            </td>
+            <td class="text-xs">
+                {match overrides.is_empty() {
+                    true => "-".into_view(),
+                    false => overrides.into_iter().map(|specs| view! {
+                        <div>
+                            {format!(
+                                "{}: tick {}, lot {}",
+                                specs.instrument_type, specs.min_price_increment, specs.min_contract_increment
+                            )}
+                        </div>
+                    }).collect_view(),
+                }}
+            </td>
+            <td>
+                <button class="btn btn-xs btn-success" on:click=on_click>SAVE</button>
+            </td>
+        </tr>
+    }
+}
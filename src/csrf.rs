@@ -0,0 +1,140 @@
+//! Cross-site request protection for server functions.
+//! Every `POST /api/*` must carry an `Origin` (or, failing that, a `Referer`) that matches
+//! the host serving the portal or one of the comma separated `ALLOWED_ORIGINS`.
+
+/// Returns the `host[:port]` part of an origin or referer url.
+pub fn origin_host(url: &str) -> Option<&str> {
+    let (_, rest) = url.split_once("://")?;
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    (!host.is_empty()).then_some(host)
+}
+
+/// Checks the origin of a request against the host it was sent to and the configured origins.
+/// A referer is reduced to its `scheme://host[:port]` first.
+pub fn is_allowed_origin(origin: &str, host: Option<&str>, allowed_origins: &[String]) -> bool {
+    let (scheme, origin_host) = match (origin.split_once("://"), origin_host(origin)) {
+        (Some((scheme, _)), Some(origin_host)) => (scheme, origin_host),
+        _ => return false,
+    };
+    let origin = format!("{}://{}", scheme, origin_host);
+    if allowed_origins
+        .iter()
+        .any(|allowed| allowed.trim_end_matches('/').eq_ignore_ascii_case(&origin))
+    {
+        return true;
+    }
+    host.is_some_and(|host| origin_host.eq_ignore_ascii_case(host))
+}
+
+#[cfg(feature = "ssr")]
+fn allowed_origins() -> Vec<String> {
+    std::env::var("ALLOWED_ORIGINS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|origin| origin.trim().to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect()
+}
+
+/// Rejects server function calls that do not come from the portal itself.
+#[cfg(feature = "ssr")]
+pub async fn csrf_guard(
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use crate::errors::JabraError;
+    use axum::response::IntoResponse;
+    use leptos::server_fn::error::ServerFnErrorSerde;
+    use leptos::ServerFnError;
+
+    if req.method() != http::Method::POST || !req.uri().path().starts_with("/api/") {
+        return next.run(req).await;
+    }
+
+    let headers = req.headers();
+    let header = |name: http::header::HeaderName| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    };
+    let host = header(http::header::HOST);
+    let origin = header(http::header::ORIGIN).or_else(|| header(http::header::REFERER));
+
+    let allowed = match origin {
+        Some(origin) => is_allowed_origin(&origin, host.as_deref(), &allowed_origins()),
+        None => false,
+    };
+    if allowed {
+        return next.run(req).await;
+    }
+
+    log::warn!(
+        "Blocked cross-site request to {} from {:?}",
+        req.uri().path(),
+        header(http::header::ORIGIN)
+    );
+    let error = JabraError::Forbidden("Cross-site request blocked".to_string());
+    let body = ServerFnError::ServerError(serde_json::to_string(&error).unwrap_or_default())
+        .ser()
+        .unwrap_or_default();
+    (http::StatusCode::FORBIDDEN, body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin_host() {
+        assert_eq!(
+            origin_host("https://portal.jabra.io"),
+            Some("portal.jabra.io")
+        );
+        assert_eq!(
+            origin_host("http://127.0.0.1:3000/quote_builder?x=1"),
+            Some("127.0.0.1:3000")
+        );
+        assert_eq!(origin_host("null"), None);
+    }
+
+    #[test]
+    fn test_same_origin() {
+        assert!(is_allowed_origin(
+            "https://portal.jabra.io",
+            Some("portal.jabra.io"),
+            &[]
+        ));
+        assert!(is_allowed_origin(
+            "http://127.0.0.1:3000/active_quotes",
+            Some("127.0.0.1:3000"),
+            &[]
+        ));
+        assert!(!is_allowed_origin(
+            "https://evil.example",
+            Some("portal.jabra.io"),
+            &[]
+        ));
+        assert!(!is_allowed_origin("null", Some("portal.jabra.io"), &[]));
+    }
+
+    #[test]
+    fn test_configured_origins() {
+        let allowed = vec!["https://portal.jabra.io/".to_string()];
+        assert!(is_allowed_origin(
+            "https://portal.jabra.io",
+            Some("10.0.0.12:3000"),
+            &allowed
+        ));
+        assert!(is_allowed_origin(
+            "https://portal.jabra.io/quote_builder",
+            Some("10.0.0.12:3000"),
+            &allowed
+        ));
+        assert!(!is_allowed_origin(
+            "https://evil.example",
+            Some("10.0.0.12:3000"),
+            &allowed
+        ));
+    }
+}
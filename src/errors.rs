@@ -1,7 +1,15 @@
 use leptos::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A single field rejected by an upstream validation.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum JabraError {
     #[serde(rename = "CookieFetchError")]
     CookieFetchError,
@@ -13,22 +21,233 @@ pub enum JabraError {
     SerializationError(String),
     #[serde(rename = "ReqwestError")]
     ReqwestError(String),
-    #[serde(rename = "APIResponseError")]
-    APIResponseError(String),
+    #[serde(rename = "Unauthorized")]
+    Unauthorized(String),
+    #[serde(rename = "Forbidden")]
+    Forbidden(String),
+    #[serde(rename = "NotFound")]
+    NotFound(String),
+    #[serde(rename = "Validation")]
+    Validation {
+        message: String,
+        fields: Vec<FieldError>,
+    },
+    #[serde(rename = "TwoFactorRequired")]
+    TwoFactorRequired { invalid_code: bool },
+    #[serde(rename = "TooManyRequests")]
+    TooManyRequests { retry_after_secs: u64 },
+    #[serde(rename = "AccountLocked")]
+    AccountLocked { retry_after_secs: u64 },
+    #[serde(rename = "StaleSpotPrice")]
+    StaleSpotPrice { pair: String },
+    #[serde(rename = "Upstream")]
+    Upstream {
+        service: String,
+        status: u16,
+        code: Option<String>,
+        body: String,
+    },
+}
+
+impl std::fmt::Display for JabraError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JabraError::CookieFetchError => write!(f, "Cookie not found"),
+            JabraError::LoginError => write!(f, "Username or Password does not matched"),
+            JabraError::NoDataFoundError => write!(f, "Data does not load correctly"),
+            JabraError::SerializationError(e) => write!(f, "{}", e),
+            JabraError::ReqwestError(e) => write!(f, "{}", e),
+            JabraError::Unauthorized(_) => {
+                write!(f, "Your session has ended. Please relog your account.")
+            }
+            JabraError::Forbidden(_) => {
+                write!(f, "You do not have permission to perform this action.")
+            }
+            JabraError::NotFound(message) => write!(f, "Not found: {}", message),
+            JabraError::Validation { message, fields } => {
+                if fields.is_empty() {
+                    write!(f, "{}", message)
+                } else {
+                    let fields = fields
+                        .iter()
+                        .map(|e| format!("{}: {}", e.field, e.message))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    write!(f, "{} ({})", message, fields)
+                }
+            }
+            JabraError::TwoFactorRequired {
+                invalid_code: false,
+            } => {
+                write!(f, "Enter the one-time code from your authenticator app.")
+            }
+            JabraError::TwoFactorRequired { invalid_code: true } => {
+                write!(f, "The one-time code is invalid or has expired.")
+            }
+            JabraError::TooManyRequests { retry_after_secs } => write!(
+                f,
+                "Too many attempts. Please try again in {} seconds.",
+                retry_after_secs
+            ),
+            JabraError::AccountLocked { retry_after_secs } => write!(
+                f,
+                "Too many failed logins. Your account is locked for {} minutes.",
+                retry_after_secs.div_ceil(60)
+            ),
+            JabraError::StaleSpotPrice { pair } => write!(
+                f,
+                "No up to date spot price for {}. Refresh the spot price and try again.",
+                pair
+            ),
+            JabraError::Upstream {
+                service, status, ..
+            } => write!(
+                f,
+                "The {} service is unavailable (status {}). Please try again.",
+                service, status
+            ),
+        }
+    }
 }
-impl ToString for JabraError {
-    fn to_string(&self) -> String {
+
+impl JabraError {
+    /// HTTP status returned to the browser when a server function fails with this error.
+    pub fn status_code(&self) -> http::StatusCode {
+        use http::StatusCode;
         match self {
-            JabraError::CookieFetchError => "Cookie not found".to_string(),
-            JabraError::LoginError => "Username or Password does not matched".to_string(),
-            JabraError::NoDataFoundError => "Data does not load correctly".to_string(),
-            JabraError::SerializationError(e) => e.to_string(),
-            JabraError::ReqwestError(e) => e.to_string(),
-            JabraError::APIResponseError(message) => message.to_string(),
+            JabraError::CookieFetchError
+            | JabraError::LoginError
+            | JabraError::TwoFactorRequired { .. }
+            | JabraError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            JabraError::Forbidden(_) => StatusCode::FORBIDDEN,
+            JabraError::NotFound(_) | JabraError::NoDataFoundError => StatusCode::NOT_FOUND,
+            JabraError::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            JabraError::TooManyRequests { .. } | JabraError::AccountLocked { .. } => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+            JabraError::SerializationError(_) | JabraError::ReqwestError(_) => {
+                StatusCode::BAD_GATEWAY
+            }
+            JabraError::StaleSpotPrice { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            JabraError::Upstream { status, .. } => match *status {
+                503 => StatusCode::SERVICE_UNAVAILABLE,
+                504 => StatusCode::GATEWAY_TIMEOUT,
+                _ => StatusCode::BAD_GATEWAY,
+            },
+        }
+    }
+
+    /// Builds the error for a non-2xx upstream response.
+    /// Directus bodies (`{"errors": [{"message", "extensions": {"code", "field"}}]}`) are
+    /// mapped onto the structured variants, anything else is kept as [`JabraError::Upstream`].
+    pub fn from_upstream_response(service: &str, status: u16, body: &str) -> Self {
+        let errors = serde_json::from_str::<DirectusErrorResponse>(body)
+            .map(|r| r.errors)
+            .unwrap_or_default();
+        let message = errors
+            .first()
+            .map(|e| e.message.clone())
+            .unwrap_or_else(|| body.to_string());
+        let code = errors.first().and_then(|e| e.extensions.code.clone());
+
+        match (status, code.as_deref()) {
+            (_, Some("INVALID_OTP")) => JabraError::TwoFactorRequired {
+                invalid_code: false,
+            },
+            (401, _)
+            | (_, Some("INVALID_CREDENTIALS"))
+            | (_, Some("INVALID_TOKEN"))
+            | (_, Some("TOKEN_EXPIRED")) => JabraError::Unauthorized(message),
+            (403, _) | (_, Some("FORBIDDEN")) => JabraError::Forbidden(message),
+            (404, _) | (_, Some("ROUTE_NOT_FOUND")) => JabraError::NotFound(message),
+            (400, Some("FAILED_VALIDATION"))
+            | (400, Some("INVALID_PAYLOAD"))
+            | (400, Some("RECORD_NOT_UNIQUE"))
+            | (422, _) => JabraError::Validation {
+                message,
+                fields: errors
+                    .iter()
+                    .filter_map(|e| {
+                        e.extensions.field.clone().map(|field| FieldError {
+                            field,
+                            message: e.message.clone(),
+                        })
+                    })
+                    .collect(),
+            },
+            _ => JabraError::Upstream {
+                service: service.to_string(),
+                status,
+                code,
+                body: body.to_string(),
+            },
+        }
+    }
+
+    /// The error as it may be sent to the browser. Upstream response bodies can carry SQL,
+    /// stack traces or other users' data, so they only go to the server log, where the upstream
+    /// call already wrote them.
+    pub fn for_client(self) -> Self {
+        match self {
+            JabraError::Upstream {
+                service,
+                status,
+                code,
+                ..
+            } => JabraError::Upstream {
+                service,
+                status,
+                code,
+                body: String::new(),
+            },
+            error => error,
+        }
+    }
+
+    /// Converts the error for a server function result.
+    /// Sets the response status and encodes the error, stripped by [`JabraError::for_client`],
+    /// so the client can rebuild it with [`JabraError::from_server_fn_error`].
+    #[cfg(feature = "ssr")]
+    pub fn into_server_fn_error(self) -> ServerFnError {
+        if let Some(response) = use_context::<leptos_axum::ResponseOptions>() {
+            response.set_status(self.status_code());
+        }
+        let error = self.for_client();
+        ServerFnError::ServerError(
+            serde_json::to_string(&error).unwrap_or_else(|_| error.to_string()),
+        )
+    }
+
+    /// Rebuilds the error from a failed server function call.
+    /// Errors that were not raised by [`JabraError::into_server_fn_error`] become a [`JabraError::ReqwestError`].
+    pub fn from_server_fn_error(error: &ServerFnError) -> Self {
+        match error {
+            ServerFnError::ServerError(message) => serde_json::from_str(message)
+                .unwrap_or_else(|_| JabraError::ReqwestError(message.clone())),
+            e => JabraError::ReqwestError(e.to_string()),
         }
     }
 }
 
+#[derive(Debug, Default, Deserialize)]
+struct DirectusErrorResponse {
+    #[serde(default)]
+    errors: Vec<DirectusError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DirectusError {
+    message: String,
+    #[serde(default)]
+    extensions: DirectusErrorExtensions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DirectusErrorExtensions {
+    code: Option<String>,
+    field: Option<String>,
+}
+
 // Create From implementation for reqwest::Error, since it does not allow Clone
 impl From<reqwest::Error> for JabraError {
     fn from(error: reqwest::Error) -> Self {
@@ -41,3 +260,106 @@ impl From<SerializationError> for JabraError {
         JabraError::SerializationError(error.to_string())
     }
 }
+
+#[cfg(feature = "ssr")]
+impl From<JabraError> for ServerFnError {
+    fn from(error: JabraError) -> Self {
+        error.into_server_fn_error()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directus_validation_error() {
+        let body = r#"{"errors":[{"message":"Value has to be unique.","extensions":{"code":"RECORD_NOT_UNIQUE","field":"ticker"}}]}"#;
+        let error = JabraError::from_upstream_response("directus", 400, body);
+        assert_eq!(
+            error,
+            JabraError::Validation {
+                message: "Value has to be unique.".to_string(),
+                fields: vec![FieldError {
+                    field: "ticker".to_string(),
+                    message: "Value has to be unique.".to_string(),
+                }],
+            }
+        );
+        assert_eq!(error.status_code(), http::StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn test_directus_auth_errors() {
+        let body =
+            r#"{"errors":[{"message":"Token expired.","extensions":{"code":"TOKEN_EXPIRED"}}]}"#;
+        assert_eq!(
+            JabraError::from_upstream_response("directus", 401, body),
+            JabraError::Unauthorized("Token expired.".to_string())
+        );
+        let body = r#"{"errors":[{"message":"You don't have permission to access this.","extensions":{"code":"FORBIDDEN"}}]}"#;
+        assert_eq!(
+            JabraError::from_upstream_response("directus", 403, body).status_code(),
+            http::StatusCode::FORBIDDEN
+        );
+    }
+
+    #[test]
+    fn test_directus_otp_error() {
+        let body =
+            r#"{"errors":[{"message":"Invalid user OTP.","extensions":{"code":"INVALID_OTP"}}]}"#;
+        assert_eq!(
+            JabraError::from_upstream_response("directus", 401, body),
+            JabraError::TwoFactorRequired {
+                invalid_code: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_non_directus_body_is_kept() {
+        let error = JabraError::from_upstream_response("coinbase", 503, "upstream connect error");
+        assert_eq!(
+            error,
+            JabraError::Upstream {
+                service: "coinbase".to_string(),
+                status: 503,
+                code: None,
+                body: "upstream connect error".to_string(),
+            }
+        );
+        assert_eq!(error.status_code(), http::StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn test_upstream_body_is_not_sent_to_the_client() {
+        let error = JabraError::from_upstream_response(
+            "directus",
+            500,
+            "error: relation \"directus_users\" does not exist at Query.run",
+        );
+        assert_eq!(
+            error.for_client(),
+            JabraError::Upstream {
+                service: "directus".to_string(),
+                status: 500,
+                code: None,
+                body: String::new(),
+            }
+        );
+        let forbidden = JabraError::Forbidden("nope".to_string());
+        assert_eq!(forbidden.clone().for_client(), forbidden);
+    }
+
+    #[test]
+    fn test_server_fn_error_round_trip() {
+        let error = JabraError::Forbidden("nope".to_string());
+        let encoded = ServerFnError::ServerError(serde_json::to_string(&error).unwrap());
+        assert_eq!(JabraError::from_server_fn_error(&encoded), error);
+        let plain = ServerFnError::ServerError("boom".to_string());
+        assert_eq!(
+            JabraError::from_server_fn_error(&plain),
+            JabraError::ReqwestError("boom".to_string())
+        );
+    }
+}
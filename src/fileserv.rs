@@ -12,8 +12,14 @@ cfg_if! { if #[cfg(feature = "ssr")] {
     use tower_http::services::ServeDir;
     use leptos::*;
     use crate::app::App;
+    use crate::route_guard::is_server_route;
 
     pub async fn file_and_error_handler(uri: Uri, State(options): State<LeptosOptions>, req: Request<Body>) -> AxumResponse {
+        // Probes and scrapers hitting a server route with the wrong method or a trailing slash
+        // get a plain 404, not the rendered app
+        if is_server_route(uri.path()) {
+            return StatusCode::NOT_FOUND.into_response();
+        }
         let root = options.site_root.clone();
         let res = get_static_file(uri.clone(), &root).await.unwrap();
 
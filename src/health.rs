@@ -0,0 +1,204 @@
+//! Liveness and readiness probes for container orchestration.
+//! `GET /healthz` answers as long as the process serves requests.
+//! `GET /readyz` also checks that the upstreams can be reached, and answers 503 when Directus,
+//! the pricing gateway or every spot price source is down. Losing only some spot price sources
+//! leaves the service ready but reported as degraded.
+
+use serde::{Deserialize, Serialize};
+
+/// Result of probing one upstream.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DependencyStatus {
+    pub name: String,
+    pub ok: bool,
+    pub latency_ms: u64,
+    /// HTTP status of the probe, when the upstream answered at all.
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// Body of `GET /readyz`.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    /// Set when at least one dependency is down without making the service unready.
+    pub degraded: bool,
+    pub dependencies: Vec<DependencyStatus>,
+}
+
+impl ReadinessReport {
+    /// Every `required` dependency must be up, while the spot price sources only need one of
+    /// them to answer since prices are taken from whichever sources respond.
+    pub fn new(required: Vec<DependencyStatus>, spot_sources: Vec<DependencyStatus>) -> Self {
+        let ready = required.iter().all(|d| d.ok) && spot_sources.iter().any(|d| d.ok);
+        let dependencies = required.into_iter().chain(spot_sources).collect::<Vec<_>>();
+        Self {
+            ready,
+            degraded: ready && dependencies.iter().any(|d| !d.ok),
+            dependencies,
+        }
+    }
+}
+
+/// An upstream counts as reachable when it answers with anything but a server error,
+/// e.g. a 404 from a gateway without a root route still proves it is up.
+pub fn is_reachable(status: u16) -> bool {
+    status < 500
+}
+
+#[cfg(feature = "ssr")]
+pub use server::*;
+
+#[cfg(feature = "ssr")]
+mod server {
+    use super::{is_reachable, DependencyStatus, ReadinessReport};
+    use crate::components::common::functions::spot_price::{bitstamp_url, configured_sources};
+    use crate::components::common::functions::upstream::{http_client, Upstream};
+    use axum::response::{IntoResponse, Json, Response};
+    use std::time::{Duration, Instant};
+
+    /// Timeout of each readiness probe, `READYZ_TIMEOUT_MS` (2 seconds by default).
+    fn probe_timeout() -> Duration {
+        Duration::from_millis(
+            std::env::var("READYZ_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2_000),
+        )
+    }
+
+    /// The url probed for each upstream. `None` when its base url is not configured.
+    fn probe_url(upstream: Upstream) -> Option<String> {
+        let (base, path) = match upstream {
+            Upstream::Directus => (std::env::var("DIRECTUSURL").ok(), "/server/ping"),
+            Upstream::PricingGateway => (std::env::var("JABRAAPIGATEWAY").ok(), ""),
+            Upstream::Coinbase => (std::env::var("COINBASE_V2").ok(), "/time"),
+            Upstream::Bitstamp => (Some(bitstamp_url()), "/trading-pairs-info/"),
+        };
+        base.map(|base| format!("{}{}", base.trim_end_matches('/'), path))
+    }
+
+    async fn probe(upstream: Upstream, timeout: Duration) -> DependencyStatus {
+        let start = Instant::now();
+        let result = match probe_url(upstream) {
+            Some(url) => http_client()
+                .get(url)
+                .timeout(timeout)
+                .send()
+                .await
+                .map(|res| res.status().as_u16())
+                .map_err(|e| e.to_string()),
+            None => Err("not configured".to_string()),
+        };
+        let latency_ms = start.elapsed().as_millis() as u64;
+        let (ok, status, error) = match result {
+            Ok(status) if is_reachable(status) => (true, Some(status), None),
+            Ok(status) => (
+                false,
+                Some(status),
+                Some(format!("responded with {}", status)),
+            ),
+            Err(e) => (false, None, Some(e)),
+        };
+        if !ok {
+            log::warn!(
+                "Readiness probe for {} failed: {:?}",
+                upstream.name(),
+                error
+            );
+        }
+        DependencyStatus {
+            name: upstream.name().to_string(),
+            ok,
+            latency_ms,
+            status,
+            error,
+        }
+    }
+
+    /// `GET /healthz`
+    pub async fn healthz() -> Response {
+        Json(serde_json::json!({ "status": "ok" })).into_response()
+    }
+
+    /// `GET /readyz`, probing Directus, the pricing gateway and the spot price sources concurrently.
+    pub async fn readyz() -> Response {
+        let timeout = probe_timeout();
+        let probe_all = |upstreams: Vec<Upstream>| {
+            futures::future::join_all(
+                upstreams
+                    .into_iter()
+                    .map(move |upstream| probe(upstream, timeout)),
+            )
+        };
+        let (required, spot_sources) = futures::future::join(
+            probe_all(vec![Upstream::Directus, Upstream::PricingGateway]),
+            probe_all(configured_sources()),
+        )
+        .await;
+        let report = ReadinessReport::new(required, spot_sources);
+        let status = match report.ready {
+            true => http::StatusCode::OK,
+            false => http::StatusCode::SERVICE_UNAVAILABLE,
+        };
+        (status, Json(report)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(name: &str, ok: bool) -> DependencyStatus {
+        DependencyStatus {
+            name: name.to_string(),
+            ok,
+            latency_ms: 12,
+            status: Some(if ok { 200 } else { 502 }),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_readiness_report() {
+        let report = ReadinessReport::new(
+            vec![dependency("directus", true)],
+            vec![dependency("coinbase", true)],
+        );
+        assert!(report.ready);
+        assert!(!report.degraded);
+        assert_eq!(report.dependencies.len(), 2);
+        assert!(
+            !ReadinessReport::new(
+                vec![dependency("directus", false)],
+                vec![dependency("coinbase", true)]
+            )
+            .ready
+        );
+    }
+
+    #[test]
+    fn test_readiness_report_spot_sources() {
+        let partial = ReadinessReport::new(
+            vec![dependency("directus", true), dependency("gateway", true)],
+            vec![dependency("coinbase", false), dependency("bitstamp", true)],
+        );
+        assert!(partial.ready);
+        assert!(partial.degraded);
+        let none = ReadinessReport::new(
+            vec![dependency("directus", true), dependency("gateway", true)],
+            vec![dependency("coinbase", false), dependency("bitstamp", false)],
+        );
+        assert!(!none.ready);
+        assert!(!none.degraded);
+    }
+
+    #[test]
+    fn test_is_reachable() {
+        assert!(is_reachable(200));
+        assert!(is_reachable(404));
+        assert!(!is_reachable(502));
+    }
+}
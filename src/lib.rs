@@ -1,11 +1,17 @@
 use cfg_if::cfg_if;
 pub mod app;
 pub mod components;
+pub mod csrf;
 pub mod error_template;
 pub mod errors;
 pub mod fileserv;
+pub mod metrics;
 pub mod functions;
+pub mod health;
 pub mod providers;
+pub mod route_guard;
+pub mod spot_stream;
+pub mod telemetry;
 
 cfg_if! { if #[cfg(feature = "hydrate")] {
     use leptos::*;
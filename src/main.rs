@@ -1,13 +1,28 @@
 #[cfg(feature = "ssr")]
 #[tokio::main]
 async fn main() {
-    use axum::{routing::post, Router};
+    use axum::{
+        routing::{get, post},
+        Router,
+    };
     use jabra_admin_portal_v2::app::*;
+    use jabra_admin_portal_v2::csrf::csrf_guard;
     use jabra_admin_portal_v2::fileserv::file_and_error_handler;
+    use jabra_admin_portal_v2::health::{healthz, readyz};
+    use jabra_admin_portal_v2::metrics::{metrics, metrics_handler, server_fn_metrics};
+    use jabra_admin_portal_v2::route_guard::session_guard;
+    use jabra_admin_portal_v2::spot_stream::{spot_stream, SPOT_STREAM_ROUTE};
+    use jabra_admin_portal_v2::telemetry::{init_tracing, request_id_layer};
     use leptos::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
 
-    simple_logger::init_with_level(log::Level::Debug).expect("couldn't initialize logging");
+    init_tracing();
+
+    // Build the pooled upstream client once, before the first request needs it
+    jabra_admin_portal_v2::components::common::functions::upstream::http_client();
+    // Fail fast on a missing or malformed SESSION_KEYS / JABRAKEY
+    jabra_admin_portal_v2::components::common::functions::seal::session_keys();
+    metrics();
 
     // Setting get_configuration(None) means we'll be using cargo-leptos's env values
     // For deployment these variables are:
@@ -22,8 +37,16 @@ async fn main() {
     // build our application with a route
     let app = Router::new()
         .route("/api/*fn_name", post(leptos_axum::handle_server_fns))
+        .route("/metrics", get(metrics_handler))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route(SPOT_STREAM_ROUTE, get(spot_stream))
         .leptos_routes(&leptos_options, routes, App)
         .fallback(file_and_error_handler)
+        .layer(axum::middleware::from_fn(server_fn_metrics))
+        .layer(axum::middleware::from_fn(session_guard))
+        .layer(axum::middleware::from_fn(csrf_guard))
+        .layer(axum::middleware::from_fn(request_id_layer))
         .with_state(leptos_options);
 
     // run our app with hyper
@@ -32,7 +55,13 @@ async fn main() {
     let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
         .await
         .unwrap();
-    axum::serve(listener, app).await.unwrap();
+    // The socket address is what `client_ip` falls back to when the peer is not a trusted proxy
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await
+    .unwrap();
     // axum::Server::bind(&addr)
     //     .serve(app.into_make_service())
     //     .await
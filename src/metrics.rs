@@ -0,0 +1,278 @@
+//! Prometheus metrics for the portal server, exposed on `GET /metrics`.
+//! Set `METRICS_TOKEN` to require `Authorization: Bearer {token}` from the scraper.
+
+use std::collections::HashSet;
+
+/// Name of the server function behind an `/api/` path, the endpoint exactly as registered.
+/// Trailing digits are kept, they may belong to the name itself as in `get_quotes_v2`.
+pub fn server_fn_name(path: &str) -> Option<&str> {
+    let name = path.strip_prefix("/api/")?;
+    (!name.is_empty()).then_some(name)
+}
+
+/// Label of calls to paths that are not a registered server function.
+pub const UNKNOWN_SERVER_FN: &str = "unknown";
+
+/// Label for a call to `path`, the server function name when `path` is one of the `registered`
+/// server function paths and `unknown` otherwise, so made up paths cannot add label values.
+pub fn server_fn_label<'a>(path: &'a str, registered: &HashSet<&str>) -> &'a str {
+    match registered.contains(path) {
+        true => server_fn_name(path).unwrap_or(UNKNOWN_SERVER_FN),
+        false => UNKNOWN_SERVER_FN,
+    }
+}
+
+/// Quote life cycle events counted in `portal_quotes_total`.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteEvent {
+    Submitted,
+    Approved,
+    Rejected,
+}
+
+impl QuoteEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuoteEvent::Submitted => "submitted",
+            QuoteEvent::Approved => "approved",
+            QuoteEvent::Rejected => "rejected",
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use server::*;
+
+#[cfg(feature = "ssr")]
+mod server {
+    use super::{server_fn_label, QuoteEvent};
+    use crate::components::common::functions::upstream::Upstream;
+    use prometheus::{
+        Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+    };
+    use std::collections::HashSet;
+    use std::sync::OnceLock;
+
+    pub struct Metrics {
+        registry: Registry,
+        server_fn_calls: IntCounterVec,
+        server_fn_duration: HistogramVec,
+        upstream_requests: IntCounterVec,
+        token_refreshes: IntCounterVec,
+        quotes: IntCounterVec,
+    }
+
+    impl Metrics {
+        fn new() -> Self {
+            let registry = Registry::new_custom(Some("portal".to_string()), None)
+                .expect("couldn't create the metrics registry");
+            let server_fn_calls = IntCounterVec::new(
+                Opts::new("server_fn_calls_total", "Server function calls"),
+                &["name", "outcome"],
+            )
+            .unwrap();
+            let server_fn_duration = HistogramVec::new(
+                HistogramOpts::new(
+                    "server_fn_duration_seconds",
+                    "Server function latency in seconds",
+                )
+                .buckets(vec![
+                    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+                ]),
+                &["name"],
+            )
+            .unwrap();
+            let upstream_requests = IntCounterVec::new(
+                Opts::new(
+                    "upstream_requests_total",
+                    "Requests to upstream services, outcome is `error` for transport errors and non 2xx responses",
+                ),
+                &["upstream", "outcome"],
+            )
+            .unwrap();
+            let token_refreshes = IntCounterVec::new(
+                Opts::new("token_refreshes_total", "Directus token refreshes"),
+                &["outcome"],
+            )
+            .unwrap();
+            let quotes = IntCounterVec::new(
+                Opts::new("quotes_total", "Quotes submitted, approved and rejected"),
+                &["event"],
+            )
+            .unwrap();
+
+            for collector in [
+                Box::new(server_fn_calls.clone()) as Box<dyn prometheus::core::Collector>,
+                Box::new(server_fn_duration.clone()),
+                Box::new(upstream_requests.clone()),
+                Box::new(token_refreshes.clone()),
+                Box::new(quotes.clone()),
+            ] {
+                registry
+                    .register(collector)
+                    .expect("couldn't register a metric");
+            }
+            // Export zeroes from the start so rates can be computed right away
+            for upstream in [
+                Upstream::Directus,
+                Upstream::PricingGateway,
+                Upstream::Coinbase,
+                Upstream::Bitstamp,
+            ] {
+                for outcome in ["ok", "error"] {
+                    upstream_requests.with_label_values(&[upstream.name(), outcome]);
+                }
+            }
+            for event in [
+                QuoteEvent::Submitted,
+                QuoteEvent::Approved,
+                QuoteEvent::Rejected,
+            ] {
+                quotes.with_label_values(&[event.as_str()]);
+            }
+
+            Self {
+                registry,
+                server_fn_calls,
+                server_fn_duration,
+                upstream_requests,
+                token_refreshes,
+                quotes,
+            }
+        }
+
+        /// The metrics in the Prometheus text format.
+        pub fn render(&self) -> String {
+            let mut buffer = Vec::new();
+            if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+                log::error!("Metrics Error: {}", e);
+            }
+            String::from_utf8(buffer).unwrap_or_default()
+        }
+    }
+
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+    pub fn metrics() -> &'static Metrics {
+        METRICS.get_or_init(Metrics::new)
+    }
+
+    pub fn record_upstream(upstream: Upstream, ok: bool) {
+        let outcome = if ok { "ok" } else { "error" };
+        metrics()
+            .upstream_requests
+            .with_label_values(&[upstream.name(), outcome])
+            .inc();
+    }
+
+    pub fn record_token_refresh(ok: bool) {
+        let outcome = if ok { "ok" } else { "error" };
+        metrics()
+            .token_refreshes
+            .with_label_values(&[outcome])
+            .inc();
+    }
+
+    pub fn record_quotes(event: QuoteEvent, count: usize) {
+        metrics()
+            .quotes
+            .with_label_values(&[event.as_str()])
+            .inc_by(count as u64);
+    }
+
+    /// Paths of the server functions registered with `#[server]`.
+    fn registered_server_fns() -> &'static HashSet<&'static str> {
+        static PATHS: OnceLock<HashSet<&'static str>> = OnceLock::new();
+        PATHS.get_or_init(|| {
+            leptos::server_fn::axum::server_fn_paths()
+                .map(|(path, _)| path)
+                .collect()
+        })
+    }
+
+    /// Counts and times every server function call.
+    /// Calls to paths that are not a registered server function are counted as `unknown`.
+    pub async fn server_fn_metrics(
+        req: axum::extract::Request,
+        next: axum::middleware::Next,
+    ) -> axum::response::Response {
+        if !req.uri().path().starts_with("/api/") {
+            return next.run(req).await;
+        }
+        let name = server_fn_label(req.uri().path(), registered_server_fns()).to_string();
+        let start = std::time::Instant::now();
+        let response = next.run(req).await;
+
+        let outcome = if response.status().is_success() {
+            "ok"
+        } else {
+            "error"
+        };
+        let metrics = metrics();
+        metrics
+            .server_fn_calls
+            .with_label_values(&[&name, outcome])
+            .inc();
+        metrics
+            .server_fn_duration
+            .with_label_values(&[&name])
+            .observe(start.elapsed().as_secs_f64());
+        response
+    }
+
+    /// `GET /metrics`
+    pub async fn metrics_handler(headers: http::HeaderMap) -> axum::response::Response {
+        use axum::response::IntoResponse;
+
+        if let Ok(token) = std::env::var("METRICS_TOKEN") {
+            let authorized = headers
+                .get(http::header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v == format!("Bearer {}", token));
+            if !authorized {
+                return http::StatusCode::UNAUTHORIZED.into_response();
+            }
+        }
+        (
+            [(http::header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+            metrics().render(),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_fn_name() {
+        assert_eq!(server_fn_name("/api/add_quote"), Some("add_quote"));
+        assert_eq!(server_fn_name("/api/get_quotes_v2"), Some("get_quotes_v2"));
+        assert_eq!(
+            server_fn_name("/api/get_quotes_option_under_24_hrs"),
+            Some("get_quotes_option_under_24_hrs")
+        );
+        assert_eq!(server_fn_name("/api/"), None);
+        assert_eq!(server_fn_name("/quote_builder"), None);
+    }
+
+    #[test]
+    fn test_server_fn_label() {
+        let registered = HashSet::from(["/api/get_quotes_v2", "/api/logout"]);
+        assert_eq!(
+            server_fn_label("/api/get_quotes_v2", &registered),
+            "get_quotes_v2"
+        );
+        assert_eq!(server_fn_label("/api/logout", &registered), "logout");
+        assert_eq!(
+            server_fn_label("/api/get_quotes_v", &registered),
+            UNKNOWN_SERVER_FN
+        );
+        assert_eq!(
+            server_fn_label("/api/random_1234", &registered),
+            UNKNOWN_SERVER_FN
+        );
+    }
+}
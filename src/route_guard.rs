@@ -0,0 +1,298 @@
+use crate::app::{CheckCookie, UserPermissions};
+use crate::components::common::functions::wrapper::KeepSessionAlive;
+use crate::components::common::models::permission_model::Permission;
+use leptos::*;
+use leptos_router::{use_location, use_navigate, NavigateOptions};
+
+/// Pages that can be opened without a session.
+pub const PUBLIC_ROUTES: &[&str] = &["/login", "/forgot_password", "/reset_password"];
+
+/// Pages that need a permission on top of a session.
+pub const ROUTE_PERMISSIONS: &[(&str, Permission)] = &[
+    ("/components", Permission::Admin),
+    ("/audit_log", Permission::Admin),
+    ("/reference_data", Permission::Admin),
+    ("/counterparties", Permission::Admin),
+    ("/supported_pairs", Permission::Admin),
+];
+
+/// Routes answered by plain Axum handlers rather than pages, e.g. for monitoring.
+/// They bring their own access control, so the session guard leaves them alone.
+pub const SERVER_ROUTES: &[&str] = &["/metrics", "/healthz", "/readyz", "/spot_stream"];
+
+/// Where a freshly logged-in user lands when there is no usable `return_to`.
+pub const DEFAULT_LANDING_ROUTE: &str = "/quote_builder";
+
+pub fn is_public_route(path: &str) -> bool {
+    PUBLIC_ROUTES.contains(&path.trim_end_matches('/'))
+}
+
+pub fn is_server_route(path: &str) -> bool {
+    SERVER_ROUTES.contains(&path.trim_end_matches('/'))
+}
+
+pub fn required_permission(path: &str) -> Option<Permission> {
+    let path = path.trim_end_matches('/');
+    ROUTE_PERMISSIONS
+        .iter()
+        .find(|(route, _)| *route == path)
+        .map(|(_, permission)| *permission)
+}
+
+/// Login url that brings the user back to `return_to` after signing in.
+pub fn login_redirect(return_to: &str) -> String {
+    match serde_urlencoded::to_string([("return_to", return_to)]) {
+        Ok(query) if !return_to.is_empty() && return_to != "/" => format!("/login?{}", query),
+        _ => "/login".to_string(),
+    }
+}
+
+/// Validates a `return_to` target. Only local, non-public paths are accepted so the
+/// login page cannot be used as an open redirect.
+pub fn safe_return_to(return_to: &str) -> String {
+    let path = return_to.split('?').next().unwrap_or_default();
+    if return_to.starts_with('/')
+        && !return_to.starts_with("//")
+        && !return_to.contains('\\')
+        && !is_public_route(path)
+    {
+        return_to.to_string()
+    } else {
+        DEFAULT_LANDING_ROUTE.to_string()
+    }
+}
+
+/// Reads `return_to` from a raw query string.
+pub fn return_to_from_query(query: &str) -> String {
+    let params: Vec<(String, String)> = serde_urlencoded::from_str(query).unwrap_or_default();
+    let return_to = params
+        .into_iter()
+        .find(|(key, _)| key == "return_to")
+        .map(|(_, value)| value)
+        .unwrap_or_default();
+    safe_return_to(&return_to)
+}
+
+/// Decides where a page request should be sent instead of being rendered.
+/// `permissions` is `None` when there is no session. Returns `None` when the request may proceed.
+/// Only the login page turns a session away; the other public pages, such as a password reset
+/// link, stay usable while logged in.
+pub fn guard_redirect(
+    path: &str,
+    query: Option<&str>,
+    permissions: Option<&[Permission]>,
+) -> Option<String> {
+    match (is_public_route(path), permissions) {
+        (true, Some(_)) if path.trim_end_matches('/') == "/login" => {
+            Some(return_to_from_query(query.unwrap_or_default()))
+        }
+        (true, _) => None,
+        (false, None) => {
+            let return_to = match query {
+                Some(q) if !q.is_empty() => format!("{}?{}", path, q),
+                _ => path.to_string(),
+            };
+            Some(login_redirect(&return_to))
+        }
+        (false, Some(permissions)) => match required_permission(path) {
+            Some(required) if !permissions.contains(&required) => {
+                Some(DEFAULT_LANDING_ROUTE.to_string())
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Client-side half of the guard. Server-rendered page loads are handled by
+/// [`session_guard`]; this covers in-app navigation and sessions that end while the page is open.
+
+#[allow(non_snake_case)]
+#[component]
+pub fn SessionGuard(children: Children) -> impl IntoView {
+    let check_cookie = use_context::<CheckCookie>().expect("Failed to find CheckCookie");
+    let user_permissions =
+        use_context::<UserPermissions>().expect("Failed to find UserPermissions");
+    let location = use_location();
+
+    create_effect(move |_| {
+        let has_session = match check_cookie.0.get() {
+            Some(Ok(has_session)) => has_session,
+            Some(Err(_)) => false,
+            None => return,
+        };
+        let permissions = match user_permissions.0.get() {
+            Some(Ok(permissions)) => permissions,
+            Some(Err(_)) => vec![],
+            None => return,
+        };
+        let path = location.pathname.get();
+        let query = location.search.get();
+        let session = has_session.then_some(permissions.as_slice());
+        if let Some(to) = guard_redirect(&path, Some(query.as_str()), session) {
+            let navigate = use_navigate();
+            navigate(
+                &to,
+                NavigateOptions {
+                    replace: true,
+                    ..Default::default()
+                },
+            );
+        }
+    });
+
+    children()
+}
+
+/// Shortest wait between two keep-alive calls, so a clock skew cannot turn the timer into a busy loop.
+const MIN_KEEP_ALIVE_DELAY_MS: i64 = 5_000;
+
+/// Refreshes the session shortly before the access token expires, so an idle tab does not
+/// find its session ended on the next action. Server functions still refresh on demand.
+
+#[allow(non_snake_case)]
+#[component]
+pub fn SessionKeepAlive() -> impl IntoView {
+    let check_cookie = use_context::<CheckCookie>().expect("Failed to find CheckCookie");
+    let keep_alive = create_server_action::<KeepSessionAlive>();
+    let timer = store_value(None::<leptos_dom::helpers::TimeoutHandle>);
+
+    create_effect(move |_| {
+        if let Some(Ok(true)) = check_cookie.0.get() {
+            keep_alive.dispatch(KeepSessionAlive {});
+        }
+    });
+
+    create_effect(move |_| {
+        if let Some(handle) = timer.get_value() {
+            handle.clear();
+            timer.set_value(None);
+        }
+        // An error means the session is gone; the guard takes it from there
+        if let Some(Ok(due_in)) = keep_alive.value().get() {
+            let delay =
+                std::time::Duration::from_millis(due_in.max(MIN_KEEP_ALIVE_DELAY_MS) as u64);
+            let handle =
+                set_timeout_with_handle(move || keep_alive.dispatch(KeepSessionAlive {}), delay);
+            timer.set_value(handle.ok());
+        }
+    });
+
+    on_cleanup(move || {
+        if let Some(handle) = timer.get_value() {
+            handle.clear();
+        }
+    });
+}
+
+#[cfg(feature = "ssr")]
+pub async fn session_guard(
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use crate::components::common::functions::wrapper::JabraSession;
+    use axum::response::{IntoResponse, Redirect};
+
+    let path = req.uri().path();
+    let is_page = req.method() == http::Method::GET
+        && !path.starts_with("/api/")
+        && !path.starts_with("/pkg/")
+        && !is_server_route(path)
+        && !path.rsplit('/').next().unwrap_or_default().contains('.');
+    if !is_page {
+        return next.run(req).await;
+    }
+
+    let session = req
+        .headers()
+        .get(http::header::COOKIE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|header| JabraSession::from_cookie_header(header).ok());
+    let permissions = session.as_ref().map(|s| s.cookie.permissions.as_slice());
+
+    match guard_redirect(path, req.uri().query(), permissions) {
+        Some(to) => Redirect::to(&to).into_response(),
+        None => next.run(req).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRADER: &[Permission] = &[Permission::CreateQuote];
+
+    #[test]
+    fn test_protected_route_without_session() {
+        assert_eq!(
+            guard_redirect("/quote_builder", None, None),
+            Some("/login?return_to=%2Fquote_builder".to_string())
+        );
+        assert_eq!(
+            guard_redirect("/active_quotes", Some("tab=approved"), None),
+            Some("/login?return_to=%2Factive_quotes%3Ftab%3Dapproved".to_string())
+        );
+        assert_eq!(guard_redirect("/", None, None), Some("/login".to_string()));
+        assert_eq!(guard_redirect("/quote_builder", None, Some(TRADER)), None);
+    }
+
+    #[test]
+    fn test_login_with_session_bounces_to_return_to() {
+        assert_eq!(guard_redirect("/login", None, None), None);
+        assert_eq!(
+            guard_redirect(
+                "/login",
+                Some("return_to=%2Factive_quotes%3Ftab%3Dapproved"),
+                Some(TRADER)
+            ),
+            Some("/active_quotes?tab=approved".to_string())
+        );
+        assert_eq!(
+            guard_redirect("/login", None, Some(&[])),
+            Some(DEFAULT_LANDING_ROUTE.to_string())
+        );
+    }
+
+    #[test]
+    fn test_other_public_routes_allow_sessions() {
+        assert_eq!(
+            guard_redirect("/reset_password", Some("token=abc"), Some(TRADER)),
+            None
+        );
+        assert_eq!(
+            guard_redirect("/forgot_password/", None, Some(TRADER)),
+            None
+        );
+        assert_eq!(guard_redirect("/reset_password", None, None), None);
+    }
+
+    #[test]
+    fn test_route_permissions() {
+        assert_eq!(
+            guard_redirect("/components", None, Some(TRADER)),
+            Some(DEFAULT_LANDING_ROUTE.to_string())
+        );
+        assert_eq!(
+            guard_redirect("/components", None, Some(&[Permission::Admin])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_server_routes() {
+        assert!(is_server_route("/metrics"));
+        assert!(is_server_route("/readyz/"));
+        assert!(!is_server_route("/quote_builder"));
+    }
+
+    #[test]
+    fn test_return_to_rejects_open_redirects() {
+        assert_eq!(
+            safe_return_to("https://evil.example"),
+            DEFAULT_LANDING_ROUTE
+        );
+        assert_eq!(safe_return_to("//evil.example"), DEFAULT_LANDING_ROUTE);
+        assert_eq!(safe_return_to("/\\evil.example"), DEFAULT_LANDING_ROUTE);
+        assert_eq!(safe_return_to("/login"), DEFAULT_LANDING_ROUTE);
+        assert_eq!(safe_return_to("/positions"), "/positions");
+    }
+}
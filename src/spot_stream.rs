@@ -0,0 +1,243 @@
+//! Live spot prices pushed to the quote builders as server-sent events on `GET /spot_stream?pair=BTC-USD`.
+//! Every open builder of a pair shares one poller, which stops once the last builder disconnects.
+//! Only active supported pairs are streamed, and a stream lasts as long as the access token it was
+//! opened with, or until the user logs out. The browser then reconnects with the refreshed session
+//! cookie, or gets a 401 once the session is gone.
+
+use crate::components::common::models::common_models::{
+    CurrencyPair, UnifiedCurrencyPairConfigurationResponse,
+};
+
+pub const SPOT_STREAM_ROUTE: &str = "/spot_stream";
+
+/// Url of the spot stream of `pair`, given as `BASE-QUOTE`.
+pub fn spot_stream_url(pair: &str) -> String {
+    format!("{}?pair={}", SPOT_STREAM_ROUTE, pair)
+}
+
+/// Parses a pair as `BASE-QUOTE`, e.g. `BTC-USD`. Tickers end up in upstream urls,
+/// so only short alphanumeric ones are accepted.
+pub fn parse_pair(pair: &str) -> Option<CurrencyPair> {
+    let is_ticker =
+        |t: &str| (1..=10).contains(&t.len()) && t.chars().all(|c| c.is_ascii_alphanumeric());
+    let (base, quote) = pair.split_once('-')?;
+    if !is_ticker(base) || !is_ticker(quote) {
+        return None;
+    }
+    let mut currency_pair = CurrencyPair::default();
+    currency_pair.base.ticker = base.to_uppercase();
+    currency_pair.quote.ticker = quote.to_uppercase();
+    Some(currency_pair)
+}
+
+/// The active supported pair named by `pair`, as configured in Directus.
+pub fn find_supported_pair(
+    configuration: &UnifiedCurrencyPairConfigurationResponse,
+    pair: &str,
+) -> Option<CurrencyPair> {
+    let requested = parse_pair(pair)?.coinbase_name();
+    configuration
+        .data
+        .iter()
+        .find(|supported| supported.coinbase_name().eq_ignore_ascii_case(&requested))
+        .cloned()
+}
+
+#[cfg(feature = "ssr")]
+pub use server::*;
+
+#[cfg(feature = "ssr")]
+mod server {
+    use super::find_supported_pair;
+    use crate::components::common::functions::spot_price::spot_price_source;
+    use crate::components::common::functions::utils::unified_configuration;
+    use crate::components::common::functions::wrapper::{JabraSession, TOKEN_REFRESH_MARGIN_MS};
+    use crate::components::common::models::common_models::CurrencyPair;
+    use crate::components::common::models::spot_price_model::SpotPrice;
+    use crate::errors::JabraError;
+    use axum::extract::Query;
+    use axum::response::sse::{Event, KeepAlive, Sse};
+    use axum::response::{IntoResponse, Response};
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use std::sync::{Mutex, MutexGuard, OnceLock};
+    use std::time::{Duration, Instant};
+    use tokio::sync::broadcast;
+
+    /// How often each streamed pair is polled, `SPOT_STREAM_INTERVAL_MS` (2 seconds by default).
+    fn poll_interval() -> Duration {
+        Duration::from_millis(
+            std::env::var("SPOT_STREAM_INTERVAL_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2_000),
+        )
+    }
+
+    static TICKERS: OnceLock<Mutex<HashMap<String, broadcast::Sender<SpotPrice>>>> =
+        OnceLock::new();
+
+    fn tickers() -> MutexGuard<'static, HashMap<String, broadcast::Sender<SpotPrice>>> {
+        TICKERS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Subscribes to the prices of `pair`, starting its poller when nobody else listens to it.
+    fn subscribe(pair: CurrencyPair) -> broadcast::Receiver<SpotPrice> {
+        let mut tickers = tickers();
+        let name = pair.coinbase_name();
+        if let Some(sender) = tickers.get(&name) {
+            return sender.subscribe();
+        }
+        let (sender, receiver) = broadcast::channel(16);
+        tickers.insert(name, sender.clone());
+        tokio::spawn(poll(pair, sender));
+        receiver
+    }
+
+    /// Publishes the spot price of `pair` until it has no subscribers left.
+    /// Failed polls are skipped; the builders flag a spot that stops updating as stale.
+    async fn poll(pair: CurrencyPair, sender: broadcast::Sender<SpotPrice>) {
+        let name = pair.coinbase_name();
+        let mut interval = tokio::time::interval(poll_interval());
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            // Checked under the lock so a concurrent subscribe cannot pick up a finished poller
+            if sender.receiver_count() == 0 {
+                let mut tickers = tickers();
+                if sender.receiver_count() == 0 {
+                    tickers.remove(&name);
+                    return;
+                }
+            }
+            match spot_price_source().spot_price(&pair).await {
+                Ok(price) => {
+                    let _ = sender.send(price);
+                }
+                Err(e) => log::warn!("Spot stream for {} failed: {}", name, e),
+            }
+        }
+    }
+
+    static LOGOUTS: OnceLock<Mutex<HashMap<String, Instant>>> = OnceLock::new();
+
+    fn logouts() -> MutexGuard<'static, HashMap<String, Instant>> {
+        LOGOUTS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// How long a logout is remembered. Streams end with their access token long before that.
+    const LOGOUT_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// Ends the open streams of the session holding `refresh_token`, called on logout.
+    /// The user's other sessions, on other devices, keep streaming.
+    pub fn end_streams(refresh_token: &str) {
+        let mut logouts = logouts();
+        logouts.retain(|_, at| at.elapsed() < LOGOUT_RETENTION);
+        logouts.insert(refresh_token.to_string(), Instant::now());
+    }
+
+    fn logged_out_since(refresh_token: &str, since: Instant) -> bool {
+        logouts().get(refresh_token).is_some_and(|at| *at >= since)
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct SpotStreamQuery {
+        pub pair: String,
+    }
+
+    /// `GET /spot_stream?pair=BTC-USD`, one [`SpotPrice`] as JSON per event.
+    pub async fn spot_stream(
+        headers: http::HeaderMap,
+        Query(query): Query<SpotStreamQuery>,
+    ) -> Response {
+        let session = headers
+            .get(http::header::COOKIE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|header| JabraSession::from_cookie_header(header).ok());
+        let session = match session {
+            Some(session) if !session.cookie.is_expired() => session,
+            _ => return http::StatusCode::UNAUTHORIZED.into_response(),
+        };
+        let pair = match unified_configuration(&session).await {
+            Ok(configuration) => match find_supported_pair(&configuration, &query.pair) {
+                Some(pair) => pair,
+                None => return http::StatusCode::BAD_REQUEST.into_response(),
+            },
+            Err(JabraError::Unauthorized(_)) => {
+                return http::StatusCode::UNAUTHORIZED.into_response()
+            }
+            Err(e) => {
+                log::error!("Spot stream configuration error: {}", e);
+                return http::StatusCode::SERVICE_UNAVAILABLE.into_response();
+            }
+        };
+
+        // `expires_in` is set TOKEN_REFRESH_MARGIN_MS before the access token actually expires
+        let lifetime = session.cookie.refresh_due_in() + TOKEN_REFRESH_MARGIN_MS;
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(lifetime as u64);
+        // A refresh during the stream rotates the token only within its last TOKEN_REFRESH_MARGIN_MS
+        let refresh_token = session.cookie.refresh_token;
+        let opened_at = Instant::now();
+
+        let stream = futures::stream::unfold(subscribe(pair), move |mut receiver| {
+            let refresh_token = refresh_token.clone();
+            async move {
+                loop {
+                    let price = match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                        Ok(Ok(price)) => price,
+                        // A slow client only needs the latest price
+                        Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                        Ok(Err(broadcast::error::RecvError::Closed)) | Err(_) => return None,
+                    };
+                    if logged_out_since(&refresh_token, opened_at) {
+                        return None;
+                    }
+                    return Some((Event::default().json_data(&price), receiver));
+                }
+            }
+        });
+        Sse::new(stream)
+            .keep_alive(KeepAlive::default())
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pair() {
+        let pair = parse_pair("btc-USD").unwrap();
+        assert_eq!(pair.coinbase_name(), "BTC-USD");
+        assert_eq!(
+            spot_stream_url(&pair.coinbase_name()),
+            "/spot_stream?pair=BTC-USD"
+        );
+        assert!(parse_pair("BTCUSD").is_none());
+        assert!(parse_pair("BTC-").is_none());
+        assert!(parse_pair("BTC-USD/../x").is_none());
+        assert!(parse_pair("BTC-USD-EUR").is_none());
+    }
+
+    #[test]
+    fn test_find_supported_pair() {
+        let mut btc_usd = parse_pair("BTC-USD").unwrap();
+        btc_usd.id = 1;
+        let configuration = UnifiedCurrencyPairConfigurationResponse {
+            data: vec![btc_usd],
+        };
+        assert_eq!(
+            find_supported_pair(&configuration, "btc-usd").map(|pair| pair.id),
+            Some(1)
+        );
+        assert!(find_supported_pair(&configuration, "ETH-USD").is_none());
+        assert!(find_supported_pair(&configuration, "BTC-USD/../x").is_none());
+    }
+}
@@ -0,0 +1,122 @@
+//! Structured logging for the server.
+//! Logs are written as JSON lines through `tracing`, including everything logged with `log::`.
+//! Every request gets an id, taken from the `X-Request-Id` set by the load balancer or generated,
+//! that is attached to its span, echoed in the response and forwarded to the upstreams.
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest request id accepted from a caller.
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Keeps a caller supplied request id only if it is short and made of safe characters,
+/// so it can be logged and forwarded without escaping.
+pub fn sanitize_request_id(request_id: Option<&str>) -> Option<String> {
+    request_id
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .filter(|id| {
+            id.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        })
+        .map(|id| id.to_string())
+}
+
+/// The path of an upstream url, without host or query string, for logging.
+pub fn url_path(url: &str) -> &str {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let path = match rest.find('/') {
+        Some(start) => &rest[start..],
+        None => "/",
+    };
+    path.split(['?', '#']).next().unwrap_or(path)
+}
+
+/// Installs the global subscriber. `RUST_LOG` sets the filter (`info` by default) and
+/// `LOG_FORMAT=text` switches to human readable output for local development.
+#[cfg(feature = "ssr")]
+pub fn init_tracing() {
+    use tracing_subscriber::EnvFilter;
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(true);
+    match std::env::var("LOG_FORMAT").as_deref() {
+        Ok("text") => builder.init(),
+        _ => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .init(),
+    }
+}
+
+/// Assigns the request id and runs the request inside a `request` span,
+/// logging the method, path, status and latency once it completes.
+#[cfg(feature = "ssr")]
+pub async fn request_id_layer(
+    mut req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use tracing::Instrument;
+
+    let request_id = sanitize_request_id(
+        req.headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok()),
+    )
+    .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let header = http::HeaderValue::from_str(&request_id)
+        .unwrap_or_else(|_| http::HeaderValue::from_static("invalid"));
+    // Server functions read the id back from the request headers
+    req.headers_mut().insert(REQUEST_ID_HEADER, header.clone());
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.uri().path(),
+        status = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+    );
+    let start = std::time::Instant::now();
+    let mut response = next.run(req).instrument(span.clone()).await;
+
+    let status = response.status().as_u16();
+    span.record("status", status);
+    span.record("latency_ms", start.elapsed().as_millis() as u64);
+    if status >= 500 {
+        tracing::error!(parent: &span, "request failed");
+    } else {
+        tracing::info!(parent: &span, "request finished");
+    }
+    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_request_id() {
+        assert_eq!(
+            sanitize_request_id(Some(" 3f2c1e9a-0b1d-4c58-9a0e-5d1c2b3a4f5e ")),
+            Some("3f2c1e9a-0b1d-4c58-9a0e-5d1c2b3a4f5e".to_string())
+        );
+        assert_eq!(sanitize_request_id(None), None);
+        assert_eq!(sanitize_request_id(Some("")), None);
+        assert_eq!(sanitize_request_id(Some("id\nforged log line")), None);
+        assert_eq!(sanitize_request_id(Some(&"a".repeat(65))), None);
+    }
+
+    #[test]
+    fn test_url_path() {
+        assert_eq!(
+            url_path("https://cms.jabra.io/items/quotes_option?filter[id][_in]=1"),
+            "/items/quotes_option"
+        );
+        assert_eq!(url_path("https://api.coinbase.com"), "/");
+        assert_eq!(url_path("/auth/refresh#x"), "/auth/refresh");
+    }
+}